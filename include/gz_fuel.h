@@ -0,0 +1,61 @@
+/* C API of gz-fuel, built as a static library with
+ * `cargo rustc --release --lib --features ffi --crate-type staticlib`, or `--crate-type cdylib`
+ * for a shared one.
+ *
+ * Functions returning a pointer return NULL on failure and functions returning an int return 0 on
+ * success and -1 on failure, `gz_fuel_last_error` returns the message of the last failure on the
+ * calling thread. Everything allocated by the library must be released with the matching `_free`
+ * function.
+ */
+#ifndef GZ_FUEL_H
+#define GZ_FUEL_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct GzFuelClient GzFuelClient;
+
+typedef struct GzFuelModel {
+  char *owner;
+  char *name;
+  char *description;
+  uint32_t downloads;
+  uint32_t likes;
+  /* Archive size in bytes */
+  uint64_t filesize;
+  bool is_private;
+} GzFuelModel;
+
+typedef struct GzFuelModelList {
+  GzFuelModel *models;
+  size_t len;
+} GzFuelModelList;
+
+/* Owned by the library, valid until the next failing call on the same thread */
+const char *gz_fuel_last_error(void);
+
+/* `url` and `token` can be NULL to use the defaults */
+GzFuelClient *gz_fuel_client_new(const char *url, const char *token);
+void gz_fuel_client_free(GzFuelClient *client);
+
+int gz_fuel_update_cache(const GzFuelClient *client, bool write_to_disk);
+
+/* Cached models, only the ones of `owner` if it isn't NULL */
+GzFuelModelList *gz_fuel_list_models(const GzFuelClient *client, const char *owner);
+void gz_fuel_model_list_free(GzFuelModelList *list);
+
+/* `version` 0 is the latest version, returns the directory the model was extracted to */
+char *gz_fuel_download_model(const GzFuelClient *client, const char *owner, const char *name,
+                             uint32_t version);
+void gz_fuel_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* GZ_FUEL_H */
@@ -0,0 +1,139 @@
+use futures_lite::future;
+
+use crate::{uri, FuelClient, FuelError, KNOWN_API_VERSIONS};
+
+/// Versions listed in the answer of a server root, either as a list or under `versions`, whose
+/// entries are version strings or objects with a `version`
+fn advertised_versions(body: &[u8]) -> Vec<String> {
+    let Ok(value) = serde_json::de::from_slice::<serde_json::Value>(body) else {
+        return Vec::new();
+    };
+    let entries = match &value {
+        serde_json::Value::Array(entries) => entries,
+        value => match value.get("versions").and_then(|v| v.as_array()) {
+            Some(entries) => entries,
+            None => return Vec::new(),
+        },
+    };
+    let is_version = |s: &&str| {
+        s.starts_with(|c: char| c.is_ascii_digit())
+            && s.chars().all(|c| c.is_ascii_digit() || c == '.')
+    };
+    entries
+        .iter()
+        .filter_map(|entry| entry.as_str().or_else(|| entry.get("version")?.as_str()))
+        .filter(is_version)
+        .map(String::from)
+        .collect()
+}
+
+impl FuelClient {
+    /// Url of the server without the API version, i.e. `https://fuel.gazebosim.org`
+    pub fn server_url(&self) -> String {
+        uri::split_api_url(&self.url).0
+    }
+
+    /// API version the client targets, None if the server is mounted without one
+    pub fn api_version(&self) -> Option<String> {
+        uri::split_api_url(&self.url).1
+    }
+
+    /// Url of an endpoint of the targeted API version, i.e. `models` or `{owner}/models`. Every
+    /// request to the API is built from it.
+    pub(crate) fn endpoint(&self, path: &str) -> String {
+        self.versioned_endpoint(self.api_version().as_deref(), path)
+    }
+
+    /// Url of an endpoint of version `version` of the API of the client server, or of the
+    /// server itself if None
+    fn versioned_endpoint(&self, version: Option<&str>, path: &str) -> String {
+        let server = self.server_url();
+        match version {
+            Some(version) => format!("{}{path}", uri::api_url(&server, version)),
+            None => format!("{server}/{path}"),
+        }
+    }
+
+    /// Versions of the API the server advertises at its root, i.e. `https://fuel.gazebosim.org/`.
+    /// Servers that don't advertise them are probed for each of `KNOWN_API_VERSIONS` with a
+    /// listing request. Fails if the server couldn't be reached.
+    pub async fn detect_api_versions(&self) -> Result<Vec<String>, FuelError> {
+        let req = self.uncached_get_request(self.versioned_endpoint(None, ""));
+        let res = self.fetch(req).await?;
+        let advertised = match res.ok {
+            true => advertised_versions(&res.bytes),
+            false => Vec::new(),
+        };
+        if !advertised.is_empty() {
+            return Ok(advertised);
+        }
+        let mut supported = Vec::new();
+        let mut error = None;
+        for version in KNOWN_API_VERSIONS {
+            let url = format!(
+                "{}?page=1&per_page=1",
+                self.versioned_endpoint(Some(version), "models")
+            );
+            match self.fetch(self.uncached_get_request(url)).await {
+                // Permission errors still mean the version exists
+                Ok(res) if res.ok || matches!(res.status, 401 | 403) => {
+                    supported.push((*version).to_owned())
+                }
+                Ok(_) => {}
+                Err(e) => error = Some(e),
+            }
+        }
+        match error {
+            Some(e) if supported.is_empty() => Err(e),
+            _ => Ok(supported),
+        }
+    }
+
+    pub fn detect_api_versions_blocking(&self) -> Result<Vec<String>, FuelError> {
+        future::block_on(self.detect_api_versions())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, response, TempDir};
+
+    fn detect(root: &'static str) -> Result<Vec<String>, FuelError> {
+        let dir = TempDir::new();
+        testing::builder(&dir, move |req| {
+            Ok(match req.url.as_str() {
+                "https://fuel.test/" => response(&req.url, 200, root),
+                "https://fuel.test/1.0/models?page=1&per_page=1" => response(&req.url, 200, "[]"),
+                _ => response(&req.url, 404, ""),
+            })
+        })
+        .build()
+        .detect_api_versions_blocking()
+    }
+
+    #[test]
+    fn detect_advertised_versions() {
+        assert_eq!(
+            detect(r#"["1.0", "2.0"]"#),
+            Ok(vec!["1.0".into(), "2.0".into()])
+        );
+        assert_eq!(
+            detect(r#"{"versions": [{"version": "2.0"}, "latest"]}"#),
+            Ok(vec!["2.0".into()])
+        );
+        // Nothing advertised, the known versions are probed
+        assert_eq!(detect("<html></html>"), Ok(vec!["1.0".into()]));
+    }
+
+    #[test]
+    fn endpoints_of_unversioned_servers() {
+        let dir = TempDir::new();
+        let client = testing::builder(&dir, |_| Err("offline".into()))
+            .url("https://fuel.test/fuel")
+            .build();
+        assert_eq!(client.endpoint("models"), "https://fuel.test/fuel/models");
+        let client = testing::builder(&dir, |_| Err("offline".into())).build();
+        assert_eq!(client.endpoint("models"), "https://fuel.test/1.0/models");
+    }
+}
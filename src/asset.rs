@@ -0,0 +1,272 @@
+use itertools::Itertools;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{fmt, sync::Arc};
+
+use crate::{intern::Interner, uri};
+
+/// Kind of asset hosted on a Fuel server
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetKind {
+    Model,
+    World,
+}
+
+impl AssetKind {
+    /// Path segment used both in the server API and in the gz-fuel-tools layout
+    pub fn path_segment(self) -> &'static str {
+        match self {
+            AssetKind::Model => "models",
+            AssetKind::World => "worlds",
+        }
+    }
+}
+
+// TODO(luca) decide which fields we should skip to save on memory footprint
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct FuelModel {
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: String,
+    pub name: String,
+    pub owner: Arc<str>,
+    pub description: String,
+    pub likes: u32,
+    pub downloads: u32,
+    pub filesize: usize,
+    pub upload_date: String,
+    pub modify_date: String,
+    pub license_id: u32,
+    pub license_name: Arc<str>,
+    pub license_url: Arc<str>,
+    pub license_image: Arc<str>,
+    pub permission: u32,
+    pub url_name: String,
+    pub private: bool,
+    #[serde(default)]
+    pub tags: Vec<Arc<str>>,
+    #[serde(default)]
+    pub categories: Vec<Arc<str>>,
+    #[serde(default)]
+    pub metadata: Vec<ModelMetadata>,
+}
+
+/// Arbitrary key / value entry of a model, i.e. a semantic label
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ModelMetadata {
+    pub key: Arc<str>,
+    #[serde(default)]
+    pub value: String,
+}
+
+/// A world as listed by the server, worlds share most of their metadata with models
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FuelWorld {
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: String,
+    pub name: String,
+    pub owner: Arc<str>,
+    pub description: String,
+    pub likes: u32,
+    pub downloads: u32,
+    pub filesize: usize,
+    pub upload_date: String,
+    pub modify_date: String,
+    pub license_id: u32,
+    pub license_name: Arc<str>,
+    pub license_url: Arc<str>,
+    pub license_image: Arc<str>,
+    pub permission: u32,
+    pub url_name: String,
+    pub private: bool,
+    #[serde(default)]
+    pub tags: Vec<Arc<str>>,
+}
+
+impl FuelModel {
+    /// Value of the first metadata entry with the given key
+    pub fn metadata_value(&self, key: &str) -> Option<&str> {
+        self.metadata
+            .iter()
+            .find(|entry| &*entry.key == key)
+            .map(|entry| entry.value.as_str())
+    }
+
+    /// Whether any of the metadata entries with the given key has the given value, keys can be
+    /// repeated
+    pub fn has_metadata(&self, key: &str, value: &str) -> bool {
+        self.metadata
+            .iter()
+            .any(|entry| &*entry.key == key && entry.value == value)
+    }
+}
+
+// Urls of a model on the server at `base_url`, usually the client `url`
+impl FuelModel {
+    /// Page of the model on the Fuel website
+    pub fn web_url(&self, base_url: &str) -> String {
+        uri::website_url(base_url, AssetKind::Model, &self.owner, &self.name)
+    }
+
+    /// Metadata of the model in the server API
+    pub fn api_url(&self, base_url: &str) -> String {
+        uri::asset_url(base_url, AssetKind::Model, &self.owner, &self.name)
+    }
+
+    /// Zip archive of the given version, or of the latest version if None
+    pub fn archive_url(&self, base_url: &str, version: Option<u32>) -> String {
+        uri::archive_url(base_url, AssetKind::Model, &self.owner, &self.name, version)
+    }
+
+    /// Thumbnail of the latest version of the model
+    pub fn thumbnail_url(&self, base_url: &str) -> String {
+        uri::thumbnail_url(base_url, &self.owner, &self.name)
+    }
+}
+
+/// One line description, i.e. `OpenRobotics/Table (1.2 MB, 340 downloads, CC-BY 4.0)`
+impl fmt::Display for FuelModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} ({:.1} MB, {} downloads, {})",
+            self.owner,
+            self.name,
+            self.filesize as f64 / 1_000_000.0,
+            self.downloads,
+            self.license_name
+        )
+    }
+}
+
+impl FuelModel {
+    /// Short multi-line description, the one line description followed by the tags and the first
+    /// line of the description if present
+    pub fn summary(&self) -> String {
+        let mut summary = self.to_string();
+        if !self.tags.is_empty() {
+            summary.push_str(&format!("\n  tags: {}", self.tags.iter().join(", ")));
+        }
+        if let Some(description) = self.description.lines().find(|l| !l.trim().is_empty()) {
+            summary.push_str(&format!("\n  {}", description.trim()));
+        }
+        summary
+    }
+}
+
+/// Fields shared by all the asset kinds hosted on a Fuel server, lets filtering, caching and
+/// downloads work the same way for models and worlds
+pub trait FuelAsset {
+    fn kind(&self) -> AssetKind;
+    fn owner(&self) -> &str;
+    fn name(&self) -> &str;
+    fn tags(&self) -> &[Arc<str>];
+    fn private(&self) -> bool;
+    /// RFC 3339 timestamp
+    fn created_at(&self) -> &str;
+    /// RFC 3339 timestamp
+    fn updated_at(&self) -> &str;
+}
+
+/// Assets that can be crawled from a listing endpoint and stored in a cache file
+pub(crate) trait CacheEntry:
+    FuelAsset + Serialize + DeserializeOwned + Clone + Send + Sync + 'static
+{
+    fn intern(&mut self, interner: &mut Interner);
+}
+
+macro_rules! impl_fuel_asset {
+    ($ty:ty, $kind:expr) => {
+        impl FuelAsset for $ty {
+            fn kind(&self) -> AssetKind {
+                $kind
+            }
+
+            fn owner(&self) -> &str {
+                &self.owner
+            }
+
+            fn name(&self) -> &str {
+                &self.name
+            }
+
+            fn tags(&self) -> &[Arc<str>] {
+                &self.tags
+            }
+
+            fn private(&self) -> bool {
+                self.private
+            }
+
+            fn created_at(&self) -> &str {
+                &self.created_at
+            }
+
+            fn updated_at(&self) -> &str {
+                &self.updated_at
+            }
+        }
+    };
+}
+
+impl_fuel_asset!(FuelModel, AssetKind::Model);
+impl_fuel_asset!(FuelWorld, AssetKind::World);
+
+impl CacheEntry for FuelModel {
+    fn intern(&mut self, interner: &mut Interner) {
+        interner.intern(&mut self.owner);
+        interner.intern(&mut self.license_name);
+        interner.intern(&mut self.license_url);
+        interner.intern(&mut self.license_image);
+        self.tags
+            .iter_mut()
+            .chain(self.categories.iter_mut())
+            .chain(self.metadata.iter_mut().map(|entry| &mut entry.key))
+            .for_each(|s| interner.intern(s));
+    }
+}
+
+impl CacheEntry for FuelWorld {
+    fn intern(&mut self, interner: &mut Interner) {
+        interner.intern(&mut self.owner);
+        interner.intern(&mut self.license_name);
+        interner.intern(&mut self.license_url);
+        interner.intern(&mut self.license_image);
+        self.tags.iter_mut().for_each(|s| interner.intern(s));
+    }
+}
+
+// Filtering functions shared by all asset kinds
+pub fn assets_by_owner<A: FuelAsset + Clone>(assets: &[A], owner: &str) -> Vec<A> {
+    assets
+        .iter()
+        .filter(|asset| asset.owner() == owner)
+        .cloned()
+        .collect()
+}
+
+pub fn assets_by_private<A: FuelAsset + Clone>(assets: &[A], private: bool) -> Vec<A> {
+    assets
+        .iter()
+        .filter(|asset| asset.private() == private)
+        .cloned()
+        .collect()
+}
+
+pub fn assets_by_tag<A: FuelAsset + Clone>(assets: &[A], tag: &str) -> Vec<A> {
+    assets
+        .iter()
+        .filter(|asset| asset.tags().iter().any(|t| &**t == tag))
+        .cloned()
+        .collect()
+}
+
+/// Finds an asset by owner and name, both compared case insensitively like the server does
+pub fn find_asset<'a, A: FuelAsset>(assets: &'a [A], owner: &str, name: &str) -> Option<&'a A> {
+    assets.iter().find(|asset| {
+        asset.owner().eq_ignore_ascii_case(owner) && asset.name().eq_ignore_ascii_case(name)
+    })
+}
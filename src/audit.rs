@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{AssetKind, DownloadRequest, DownloadedModel, FuelClient, FuelError};
+
+/// A download as recorded in the audit log, see `FuelClientBuilder::audit_log`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    /// Seconds since the unix epoch at which the download started
+    pub time: u64,
+    /// Url of the server the asset was downloaded from
+    pub server: String,
+    pub kind: AssetKind,
+    pub owner: String,
+    pub name: String,
+    /// Version that was asked for, None for the latest version
+    pub requested_version: Option<u32>,
+    /// Version the server resolved, None if the download failed before it was known
+    pub version: Option<u32>,
+    /// Size of the downloaded archive in bytes
+    pub size: u64,
+    pub duration_ms: u64,
+    /// Directory the asset was extracted to, None if the download failed
+    pub destination: Option<PathBuf>,
+    /// Error message of failed downloads
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Reads an audit log, one JSON entry per line. Lines that can't be parsed, i.e. one left
+/// truncated by a crash, are skipped.
+pub fn read_audit_log(path: impl AsRef<Path>) -> Result<Vec<AuditEntry>, FuelError> {
+    let content = fs::read_to_string(path).map_err(|e| FuelError::Io(e.to_string()))?;
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::de::from_str(line).ok())
+        .collect())
+}
+
+impl FuelClient {
+    /// Entries of the client audit log started at or after `since`, oldest first. Empty if the
+    /// client has no audit log or nothing was downloaded yet.
+    pub fn audit_entries(&self, since: Option<SystemTime>) -> Result<Vec<AuditEntry>, FuelError> {
+        let Some(path) = self.audit_log.as_ref().filter(|path| path.exists()) else {
+            return Ok(Vec::new());
+        };
+        let since = since.map_or(0, |since| {
+            since
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        });
+        let mut entries = read_audit_log(path)?;
+        entries.retain(|entry| entry.time >= since);
+        Ok(entries)
+    }
+
+    /// Appends a finished download to the audit log, if the client has one. Failing to write the
+    /// log doesn't fail the download.
+    pub(crate) fn audit_download(
+        &self,
+        request: DownloadRequest,
+        started: SystemTime,
+        size: u64,
+        result: &Result<DownloadedModel, FuelError>,
+    ) {
+        let Some(path) = &self.audit_log else {
+            return;
+        };
+        let entry = AuditEntry {
+            time: started
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            server: self.url.clone(),
+            kind: request.kind,
+            owner: request.owner,
+            name: request.name,
+            requested_version: request.version,
+            version: result.as_ref().ok().map(|downloaded| downloaded.version),
+            size,
+            duration_ms: started.elapsed().unwrap_or(Duration::ZERO).as_millis() as u64,
+            destination: result
+                .as_ref()
+                .ok()
+                .map(|downloaded| downloaded.path.clone()),
+            error: result.as_ref().err().map(|e| e.to_string()),
+        };
+        let Ok(mut line) = serde_json::ser::to_string(&entry) else {
+            return;
+        };
+        line.push('\n');
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).ok();
+        }
+        let _lock = self.lock_cache_file();
+        // A single write per entry, so entries of concurrent downloads don't interleave
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TempDir};
+
+    #[test]
+    fn downloads_are_audited() {
+        let dir = TempDir::new();
+        let archive = testing::zip_archive(&[("model.sdf", "<sdf/>")]);
+        let size = archive.len() as u64;
+        let builder = testing::builder(&dir, move |req| {
+            if req.url.contains("/missing/") {
+                return Ok(testing::response(&req.url, 404, ""));
+            }
+            let mut res = testing::response(&req.url, 200, archive.clone());
+            res.headers.insert("X-Ign-Resource-Version", "3");
+            Ok(res)
+        })
+        .download_dir(dir.0.join("downloads"));
+        let unaudited = builder.clone().build();
+        unaudited.download_model_blocking("o", "a", None).unwrap();
+        assert_eq!(unaudited.audit_entries(None), Ok(Vec::new()));
+
+        let log = dir.0.join("logs/audit.jsonl");
+        let client = builder.audit_log(&log).build();
+        let started = SystemTime::now() - Duration::from_secs(1);
+        let downloaded = client.download_model_blocking("o", "a", None).unwrap();
+        assert!(client
+            .download_model_blocking("o", "missing", Some(2))
+            .is_err());
+        let entries = client.audit_entries(Some(started)).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].succeeded());
+        assert_eq!(entries[0].server, testing::SERVER);
+        assert_eq!(
+            (entries[0].requested_version, entries[0].version),
+            (None, Some(3))
+        );
+        assert_eq!(entries[0].size, size);
+        assert_eq!(entries[0].destination, Some(downloaded.path));
+        assert!(!entries[1].succeeded());
+        assert_eq!(entries[1].name, "missing");
+        assert_eq!(
+            (entries[1].requested_version, entries[1].version),
+            (Some(2), None)
+        );
+        assert_eq!(entries[1].destination, None);
+        let later = SystemTime::now() + Duration::from_secs(3600);
+        assert_eq!(client.audit_entries(Some(later)), Ok(Vec::new()));
+
+        // A line truncated by a crash is skipped
+        let mut file = fs::OpenOptions::new().append(true).open(&log).unwrap();
+        file.write_all(b"{\"time\": 1").unwrap();
+        assert_eq!(read_audit_log(&log).unwrap(), entries);
+    }
+}
@@ -0,0 +1,355 @@
+use crossbeam_channel::Sender;
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use crate::{
+    uri, BasicAuth, ContentStore, DownloadHook, DownloadLayout, DownloadProgress, FuelClient,
+    FuelModel, HttpBackend, Metrics, Middleware, PageProgress, RateLimiter, RedirectPolicy,
+    ResponseCache, RetryPolicy, TlsConfig, TokenRefreshFn, UreqBackend, DEFAULT_API_VERSION,
+    DEFAULT_SERVER, DEFAULT_URL,
+};
+
+/// Builder for `FuelClient`, all options are optional and default to the public Fuel server with
+/// the default cache location.
+#[derive(Clone, Default)]
+pub struct FuelClientBuilder {
+    url: Option<String>,
+    server: Option<String>,
+    api_version: Option<String>,
+    token: Option<String>,
+    token_refresh: Option<Arc<TokenRefreshFn>>,
+    basic_auth: Option<BasicAuth>,
+    cache_path: Option<PathBuf>,
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    tls: Option<TlsConfig>,
+    per_page: Option<u32>,
+    retry: Option<RetryPolicy>,
+    redirect: Option<RedirectPolicy>,
+    requests_per_sec: Option<f64>,
+    metrics: Option<Arc<dyn Metrics>>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+    response_cache: Option<ResponseCache>,
+    download_hooks: Vec<Arc<dyn DownloadHook>>,
+    refresh_threshold: Option<Duration>,
+    progress: Option<Sender<FuelModel>>,
+    page_progress: Option<Sender<PageProgress>>,
+    download_progress: Option<Sender<DownloadProgress>>,
+    backend: Option<Arc<dyn HttpBackend>>,
+    offline: bool,
+    download_dir: Option<PathBuf>,
+    layout: DownloadLayout,
+    content_store: Option<ContentStore>,
+    track_stats: bool,
+    keep_snapshots: usize,
+    commit_partial_crawls: bool,
+    disk_space_check: Option<bool>,
+    pretty_cache: bool,
+    presets_path: Option<PathBuf>,
+    audit_log: Option<PathBuf>,
+}
+
+impl FuelClientBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Base url of the server API, including the version, i.e. `https://fuel.gazebosim.org/1.0/`
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        let mut url = url.into();
+        if !url.ends_with('/') {
+            url.push('/');
+        }
+        self.url = Some(url);
+        self
+    }
+
+    /// Url of the server without the API version, i.e. `https://fuel.example.com/fuel` for a
+    /// server mounted under a path prefix. Replaces the server part of `url`.
+    pub fn server(mut self, server: impl Into<String>) -> Self {
+        self.server = Some(server.into());
+        self
+    }
+
+    /// Version of the API to target, `DEFAULT_API_VERSION` by default. Replaces the version part
+    /// of `url`, see `FuelClient::detect_api_versions` for the versions a server supports.
+    pub fn api_version(mut self, version: impl Into<String>) -> Self {
+        self.api_version = Some(version.into());
+        self
+    }
+
+    /// Api url from `url`, `server` and `api_version`
+    fn api_url(&self) -> String {
+        if self.server.is_none() && self.api_version.is_none() {
+            return self.url.clone().unwrap_or_else(|| DEFAULT_URL.to_owned());
+        }
+        let (server, version) = match &self.url {
+            Some(url) => uri::split_api_url(url),
+            None => (DEFAULT_SERVER.to_owned(), None),
+        };
+        let version = self.api_version.clone().or(version);
+        uri::api_url(
+            self.server.as_deref().unwrap_or(&server),
+            version.as_deref().unwrap_or(DEFAULT_API_VERSION),
+        )
+    }
+
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Called when the server rejects the token with a 401, if it returns a new token the client
+    /// switches to it and retries the request once
+    pub fn token_refresh(
+        mut self,
+        refresh: impl Fn() -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.token_refresh = Some(Arc::new(refresh));
+        self
+    }
+
+    /// HTTP basic auth credentials sent with every request, for servers behind a proxy
+    pub fn basic_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.basic_auth = Some(BasicAuth::new(username, password));
+        self
+    }
+
+    /// Path of the model cache file, the platform cache directory is used if not set
+    pub fn cache_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cache_path = Some(path.into());
+        self
+    }
+
+    /// Timeout for establishing connections, ignored if a custom backend is set
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Overall timeout of a single request, ignored if a custom backend is set
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Custom root certificates and client certificates, ignored if a custom backend is set
+    pub fn tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Number of models requested per page when listing models, larger pages mean fewer but
+    /// heavier requests
+    pub fn per_page(mut self, per_page: u32) -> Self {
+        self.per_page = Some(per_page.max(1));
+        self
+    }
+
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Redirects are followed by the client, also with custom backends, as long as the backend
+    /// doesn't follow them itself. Up to 5 by default, without auth headers across hosts.
+    pub fn redirect_policy(mut self, redirect: RedirectPolicy) -> Self {
+        self.redirect = Some(redirect);
+        self
+    }
+
+    /// Maximum number of requests sent per second by the client and its clones, retries included.
+    /// Unlimited by default or if the rate isn't positive, at least one request a day is sent.
+    pub fn rate_limit(mut self, requests_per_sec: f64) -> Self {
+        self.requests_per_sec = Some(requests_per_sec);
+        self
+    }
+
+    /// Hooks notified of every request, retry and cache hit or miss
+    pub fn metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Adds a middleware, middlewares run in the order they were added before a request is sent
+    /// and in reverse order once its response is received
+    pub fn middleware(mut self, middleware: Arc<dyn Middleware>) -> Self {
+        self.middlewares.push(middleware);
+        self
+    }
+
+    /// Adds a hook run on every downloaded model, hooks run in the order they were added. See
+    /// `DownloadHook`.
+    pub fn download_hook(mut self, hook: Arc<dyn DownloadHook>) -> Self {
+        self.download_hooks.push(hook);
+        self
+    }
+
+    /// Stores raw API responses on disk, see `ResponseCache`. It runs after the other
+    /// middlewares, whatever the order they are added in.
+    pub fn response_cache(mut self, cache: ResponseCache) -> Self {
+        self.response_cache = Some(cache);
+        self
+    }
+
+    /// Cache age after which `FuelClient::should_refresh` returns true
+    pub fn refresh_threshold(mut self, threshold: Duration) -> Self {
+        self.refresh_threshold = Some(threshold);
+        self
+    }
+
+    /// Default sink for cache update progress, used when no sink is passed to the update call
+    pub fn progress(mut self, progress: Sender<FuelModel>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Sink for the pages fetched by listing crawls, see `PageProgress`
+    pub fn page_progress(mut self, pages: Sender<PageProgress>) -> Self {
+        self.page_progress = Some(pages);
+        self
+    }
+
+    /// Default sink for the progress of batch downloads and mirror syncs, used when no sink is
+    /// passed to the download call
+    pub fn download_progress(mut self, progress: Sender<DownloadProgress>) -> Self {
+        self.download_progress = Some(progress);
+        self
+    }
+
+    /// HTTP implementation to use instead of the default `UreqBackend`
+    pub fn backend(mut self, backend: Arc<dyn HttpBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// In offline mode network methods fail immediately and only cached data is served
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Directory downloaded models are extracted to, a directory in the platform data dir by
+    /// default. It is independent of `cache_path`, clearing the cache keeps the downloads.
+    pub fn download_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.download_dir = Some(path.into());
+        self
+    }
+
+    /// How downloaded models are laid out in the download directory
+    pub fn layout(mut self, layout: DownloadLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Deduplicates the files of downloaded assets in a content addressed store at `root`, see
+    /// `ContentStore`
+    pub fn content_store(mut self, root: impl Into<PathBuf>) -> Self {
+        self.content_store = Some(ContentStore::new(root));
+        self
+    }
+
+    /// Keeps a history of the downloads and likes of every model, see `FuelClient::stats_history`
+    pub fn track_stats(mut self, track_stats: bool) -> Self {
+        self.track_stats = track_stats;
+        self
+    }
+
+    /// Keeps copies of the cache file of the last `count` refreshes, see `FuelClient::snapshots`
+    pub fn keep_snapshots(mut self, count: usize) -> Self {
+        self.keep_snapshots = count;
+        self
+    }
+
+    /// Updates the cache with the models fetched by refreshes that fail midway, see
+    /// `FuelClient::commit_partial_crawls`
+    pub fn commit_partial_crawls(mut self, commit: bool) -> Self {
+        self.commit_partial_crawls = commit;
+        self
+    }
+
+    /// Checks the available disk space before downloading, enabled by default, see
+    /// `FuelClient::check_disk_space`
+    pub fn disk_space_check(mut self, check: bool) -> Self {
+        self.disk_space_check = Some(check);
+        self
+    }
+
+    /// Pretty prints the cache files instead of writing them compact, i.e. to read them by hand
+    pub fn pretty_cache(mut self, pretty: bool) -> Self {
+        self.pretty_cache = pretty;
+        self
+    }
+
+    /// File the query presets are saved to, a file in the platform config directory by default
+    pub fn presets_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.presets_path = Some(path.into());
+        self
+    }
+
+    /// Records every download, successful or not, to an append only log file of JSON lines
+    pub fn audit_log(mut self, path: impl Into<PathBuf>) -> Self {
+        self.audit_log = Some(path.into());
+        self
+    }
+
+    pub fn build(self) -> FuelClient {
+        let url = self.api_url();
+        let backend = self.backend.unwrap_or_else(|| {
+            let mut agent = ureq::AgentBuilder::new().redirects(0);
+            if let Some(connect) = self.connect_timeout {
+                agent = agent.timeout_connect(connect);
+            }
+            if let Some(timeout) = self.timeout {
+                agent = agent.timeout(timeout);
+            }
+            if let Some(tls) = self.tls {
+                agent = agent.tls_config(tls.client_config);
+            }
+            Arc::new(UreqBackend::new(agent))
+        });
+        let mut middlewares = self.middlewares;
+        if let Some(mut cache) = self.response_cache {
+            cache.metrics = self.metrics.clone();
+            middlewares.push(Arc::new(cache));
+        }
+        let mut client = FuelClient {
+            url,
+            cache_path: None,
+            models: Default::default(),
+            worlds: Default::default(),
+            local_models: Default::default(),
+            cache_file_lock: Default::default(),
+            token: Arc::new(self.token.into()),
+            token_refresh: self.token_refresh,
+            basic_auth: self.basic_auth,
+            per_page: self.per_page.unwrap_or(100),
+            offline: self.offline,
+            retry: self.retry.unwrap_or_default(),
+            redirect: self.redirect.unwrap_or_default(),
+            rate_limiter: self
+                .requests_per_sec
+                .map(|rate| Arc::new(RateLimiter::new(rate))),
+            metrics: self.metrics,
+            middlewares,
+            download_hooks: self.download_hooks,
+            refresh_threshold: self.refresh_threshold,
+            progress: self.progress,
+            page_progress: self.page_progress,
+            download_progress: self.download_progress,
+            backend,
+            download_dir: self.download_dir,
+            layout: self.layout,
+            content_store: self.content_store,
+            track_stats: self.track_stats,
+            keep_snapshots: self.keep_snapshots,
+            commit_partial_crawls: self.commit_partial_crawls,
+            disk_space_check: self.disk_space_check.unwrap_or(true),
+            pretty_cache: self.pretty_cache,
+            load_status: Default::default(),
+            presets_path: self.presets_path,
+            audit_log: self.audit_log,
+        };
+        client.load_cache(self.cache_path);
+        client
+    }
+}
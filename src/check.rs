@@ -0,0 +1,165 @@
+use futures_lite::future;
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use crate::{fuel_client::CREDENTIALS_ENDPOINT, FuelClient, FuelError, RetryPolicy};
+
+/// Whether the server accepted the credentials of the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthStatus {
+    /// The client has no token nor basic auth credentials
+    Anonymous,
+    Accepted,
+    /// The server rejected the credentials (401), the token is invalid or expired
+    Rejected,
+    /// The credentials were accepted but don't grant access to the account (403)
+    Forbidden,
+    /// The server couldn't be reached
+    Unknown,
+}
+
+/// Outcome of `FuelClient::check`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerCheck {
+    pub reachable: bool,
+    /// Time until the response was received, without retries nor rate limiting. None if the
+    /// server couldn't be reached.
+    pub latency: Option<Duration>,
+    pub auth: AuthStatus,
+    /// API version configured in the client url, i.e. `1.0` for `https://fuel.gazebosim.org/1.0/`.
+    /// It isn't reported by the server, see `FuelClient::detect_api_versions` for that.
+    pub api_version: Option<String>,
+    /// Status code of the response
+    pub status: Option<u16>,
+    /// Why the check failed, None if the server answered successfully
+    pub error: Option<FuelError>,
+}
+
+impl ServerCheck {
+    /// True if the server is reachable and accepted the credentials, if any
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+impl FuelClient {
+    /// Sends a single request to the endpoint checking the client credentials, i.e. to show an
+    /// actionable message before starting a long operation. The request is neither retried nor
+    /// rate limited, and a rejected token isn't refreshed so the client credentials are left as
+    /// they are. Never fails, errors are reported in the returned status.
+    pub async fn check(&self) -> ServerCheck {
+        let client = FuelClient {
+            retry: RetryPolicy::none(),
+            rate_limiter: None,
+            token: Arc::new(RwLock::new(self.token())),
+            token_refresh: None,
+            ..self.clone()
+        };
+        // A stored response wouldn't say anything about the server
+        let req = client.uncached_get_request(client.endpoint(CREDENTIALS_ENDPOINT));
+        let has_credentials = client.token().is_some() || client.basic_auth.is_some();
+        let start = Instant::now();
+        let res = client.fetch(req).await;
+        let latency = start.elapsed();
+        let mut check = ServerCheck {
+            reachable: false,
+            latency: None,
+            auth: AuthStatus::Unknown,
+            api_version: self.api_version(),
+            status: None,
+            error: None,
+        };
+        let res = match res {
+            Ok(res) => res,
+            Err(e) => {
+                check.error = Some(e);
+                return check;
+            }
+        };
+        check.reachable = true;
+        check.latency = Some(latency);
+        check.status = Some(res.status);
+        check.auth = match (res.status, has_credentials) {
+            // The endpoint requires credentials, refusing anonymous clients is expected
+            (401 | 403, false) => AuthStatus::Anonymous,
+            (401, true) => AuthStatus::Rejected,
+            (403, true) => AuthStatus::Forbidden,
+            _ if !res.ok => AuthStatus::Unknown,
+            (_, false) => AuthStatus::Anonymous,
+            (_, true) => AuthStatus::Accepted,
+        };
+        if !res.ok && check.auth != AuthStatus::Anonymous {
+            check.error = Some(FuelError::from_status(res.status));
+        }
+        check
+    }
+
+    pub fn check_blocking(&self) -> ServerCheck {
+        future::block_on(self.check())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, response, TempDir};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn check(token: Option<&str>, status: u16) -> (ServerCheck, usize) {
+        let dir = TempDir::new();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let mut builder = testing::builder(&dir, move |req| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            assert!(req.url.ends_with("/login"));
+            Ok(response(&req.url, status, "{}"))
+        })
+        .retry_policy(RetryPolicy::default());
+        if let Some(token) = token {
+            builder = builder.token(token);
+        }
+        let check = builder.build().check_blocking();
+        (check, requests.load(Ordering::Relaxed))
+    }
+
+    #[test]
+    fn check_credentials() {
+        let (accepted, _) = check(Some("token"), 200);
+        assert_eq!(accepted.auth, AuthStatus::Accepted);
+        assert!(accepted.is_ok());
+        let (rejected, _) = check(Some("token"), 401);
+        assert_eq!(rejected.auth, AuthStatus::Rejected);
+        assert_eq!(rejected.error, Some(FuelError::Unauthorized));
+        let (anonymous, _) = check(None, 401);
+        assert_eq!(anonymous.auth, AuthStatus::Anonymous);
+        assert!(anonymous.is_ok());
+    }
+
+    #[test]
+    fn checks_are_not_retried() {
+        let (unavailable, requests) = check(None, 503);
+        assert_eq!(unavailable.auth, AuthStatus::Unknown);
+        assert_eq!(unavailable.status, Some(503));
+        assert_eq!(requests, 1);
+    }
+
+    #[test]
+    fn rejected_tokens_are_not_refreshed() {
+        let dir = TempDir::new();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let client = testing::builder(&dir, move |req| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            Ok(response(&req.url, 401, "{}"))
+        })
+        .token("expired")
+        .token_refresh(|| Some("fresh".to_owned()))
+        .build();
+        let check = client.check_blocking();
+        assert_eq!(check.auth, AuthStatus::Rejected);
+        assert_eq!(requests.load(Ordering::Relaxed), 1);
+        assert_eq!(client.token().as_deref(), Some("expired"));
+    }
+}
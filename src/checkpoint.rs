@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{asset::CacheEntry, CacheValidators};
+
+/// Checkpoints older than this are discarded, so resumed pages aren't merged with much newer ones
+const MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// First line of a checkpoint file, identifies the crawl the pages belong to
+#[derive(Serialize, Deserialize)]
+struct CheckpointHeader {
+    listing: String,
+    per_page: u32,
+    validators: CacheValidators,
+    /// Seconds since the unix epoch at which the crawl started, 0 for checkpoints written before
+    /// it was recorded
+    #[serde(default)]
+    started_at: u64,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "A: CacheEntry")]
+struct CheckpointPage<A> {
+    page: u32,
+    models: Vec<A>,
+}
+
+/// Progress of an interrupted crawl
+pub(crate) struct Checkpoint<A> {
+    pub(crate) last_page: u32,
+    pub(crate) models: Vec<A>,
+    pub(crate) validators: CacheValidators,
+}
+
+/// Appends every completed page of a crawl to a JSON lines file, so an interrupted crawl can be
+/// resumed from the last completed page. Writing is best effort, a failure only means the crawl
+/// can't be resumed.
+pub(crate) struct CheckpointWriter {
+    path: PathBuf,
+    file: Option<fs::File>,
+}
+
+impl<A: CacheEntry> Checkpoint<A> {
+    /// Loads the checkpoint at `path` if it belongs to a crawl of the same listing with the same
+    /// page size, started less than `MAX_AGE` ago. Only consecutive pages are kept, a truncated
+    /// last line is ignored.
+    pub(crate) fn load(path: &Path, listing: &str, per_page: u32) -> Option<Self> {
+        let mut lines = BufReader::new(fs::File::open(path).ok()?).lines();
+        let header = serde_json::from_str::<CheckpointHeader>(&lines.next()?.ok()?).ok()?;
+        let age = unix_now().saturating_sub(header.started_at);
+        if header.listing != listing || header.per_page != per_page || age > MAX_AGE.as_secs() {
+            return None;
+        }
+        let mut checkpoint = Checkpoint {
+            last_page: 0,
+            models: Vec::new(),
+            validators: header.validators,
+        };
+        for line in lines {
+            let Some(mut page) = line
+                .ok()
+                .and_then(|l| serde_json::from_str::<CheckpointPage<A>>(&l).ok())
+            else {
+                break;
+            };
+            if page.page != checkpoint.last_page + 1 {
+                break;
+            }
+            checkpoint.last_page = page.page;
+            checkpoint.models.append(&mut page.models);
+        }
+        (checkpoint.last_page > 0).then_some(checkpoint)
+    }
+}
+
+impl CheckpointWriter {
+    /// Starts a new checkpoint file, replacing any existing one
+    pub(crate) fn create(
+        path: PathBuf,
+        listing: &str,
+        per_page: u32,
+        validators: &CacheValidators,
+    ) -> Self {
+        let header = CheckpointHeader {
+            listing: listing.to_owned(),
+            per_page,
+            validators: validators.clone(),
+            started_at: unix_now(),
+        };
+        let file = path
+            .parent()
+            .and_then(|parent| fs::create_dir_all(parent).ok())
+            .and_then(|_| fs::File::create(&path).ok())
+            .and_then(|mut file| {
+                let line = serde_json::to_string(&header).ok()?;
+                writeln!(file, "{line}").ok()?;
+                Some(file)
+            });
+        Self { path, file }
+    }
+
+    /// Keeps appending to an existing checkpoint file
+    pub(crate) fn resume(path: PathBuf) -> Self {
+        let file = fs::File::options().append(true).open(&path).ok();
+        Self { path, file }
+    }
+
+    pub(crate) fn append_page<A: CacheEntry>(&mut self, page: u32, models: &[A]) {
+        let Some(file) = &mut self.file else {
+            return;
+        };
+        let page = CheckpointPage {
+            page,
+            models: models.to_vec(),
+        };
+        let written = serde_json::to_string(&page)
+            .ok()
+            .and_then(|line| writeln!(file, "{line}").ok());
+        if written.is_none() {
+            // Stop writing rather than leaving a gap in the pages
+            self.file = None;
+        }
+    }
+
+    /// Removes the checkpoint once the crawl completed
+    pub(crate) fn finish(self) {
+        drop(self.file);
+        fs::remove_file(&self.path).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{model, TempDir},
+        FuelModel,
+    };
+
+    fn write(path: &Path) {
+        let mut writer = CheckpointWriter::create(path.into(), "models", 2, &Default::default());
+        writer.append_page(1, &[model("o", "a", ""), model("o", "b", "")]);
+        writer.append_page(2, &[model("o", "c", "")]);
+    }
+
+    #[test]
+    fn resume_checkpoint() {
+        let dir = TempDir::new();
+        let path = dir.0.join("crawl.jsonl");
+        write(&path);
+        let checkpoint = Checkpoint::<FuelModel>::load(&path, "models", 2).unwrap();
+        assert_eq!(checkpoint.last_page, 2);
+        assert_eq!(checkpoint.models.len(), 3);
+        // Another crawl
+        assert!(Checkpoint::<FuelModel>::load(&path, "o/models", 2).is_none());
+        assert!(Checkpoint::<FuelModel>::load(&path, "models", 100).is_none());
+    }
+
+    #[test]
+    fn discard_old_checkpoint() {
+        let dir = TempDir::new();
+        let path = dir.0.join("crawl.jsonl");
+        write(&path);
+        let content = fs::read_to_string(&path).unwrap();
+        let (header, pages) = content.split_once('\n').unwrap();
+        let mut header = serde_json::from_str::<CheckpointHeader>(header).unwrap();
+        header.started_at -= MAX_AGE.as_secs() + 1;
+        let header = serde_json::to_string(&header).unwrap();
+        fs::write(&path, format!("{header}\n{pages}")).unwrap();
+        assert!(Checkpoint::<FuelModel>::load(&path, "models", 2).is_none());
+        // Checkpoints written before the start time was recorded
+        let legacy = r#"{"listing":"models","per_page":2,"validators":{}}"#;
+        fs::write(&path, format!("{legacy}\n{pages}")).unwrap();
+        assert!(Checkpoint::<FuelModel>::load(&path, "models", 2).is_none());
+    }
+
+    #[test]
+    fn finish_removes_checkpoint() {
+        let dir = TempDir::new();
+        let path = dir.0.join("crawl.jsonl");
+        write(&path);
+        CheckpointWriter::resume(path.clone()).finish();
+        assert!(!path.exists());
+    }
+}
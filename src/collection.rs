@@ -0,0 +1,305 @@
+use crossbeam_channel::Sender;
+use futures_lite::future;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::{
+    upload::Multipart, uri, DownloadProgress, DownloadRequest, DownloadResult, FuelClient,
+    FuelError, FuelModel, FuelWorld,
+};
+
+/// A curated set of models and worlds
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct FuelCollection {
+    pub name: String,
+    pub owner: String,
+    pub description: String,
+    pub private: bool,
+}
+
+impl FuelClient {
+    /// Api url of a collection
+    pub(crate) fn collection_url(&self, owner: &str, collection: &str) -> String {
+        self.endpoint(&format!(
+            "{}/collections/{}",
+            uri::encode_segment(owner),
+            uri::encode_segment(collection)
+        ))
+    }
+
+    /// Creates an empty collection owned by `collection.owner`, which can be an organization the
+    /// token has write access to
+    pub async fn create_collection(
+        &self,
+        collection: &FuelCollection,
+    ) -> Result<FuelCollection, FuelError> {
+        let mut form = Multipart::new();
+        form.text("name", &collection.name);
+        form.text("owner", &collection.owner);
+        form.text("description", &collection.description);
+        form.text("private", &collection.private.to_string());
+        let (content_type, body) = form.finish();
+        let mut req = self.get_request(self.endpoint("collections"));
+        req.method = "POST".to_owned();
+        req.headers.insert("Content-Type", content_type);
+        req.body = body;
+        let res = self.fetch(req).await?;
+        if !res.ok {
+            return Err(FuelError::from_status(res.status));
+        }
+        Ok(serde_json::de::from_slice(&res.bytes).unwrap_or_else(|_| collection.clone()))
+    }
+
+    pub fn create_collection_blocking(
+        &self,
+        collection: &FuelCollection,
+    ) -> Result<FuelCollection, FuelError> {
+        future::block_on(self.create_collection(collection))
+    }
+
+    /// Deletes a collection, the models it contains are not affected
+    pub async fn delete_collection(&self, owner: &str, collection: &str) -> Result<(), FuelError> {
+        let mut req = self.get_request(self.collection_url(owner, collection));
+        req.method = "DELETE".to_owned();
+        self.send_collection_request(req).await
+    }
+
+    pub fn delete_collection_blocking(
+        &self,
+        owner: &str,
+        collection: &str,
+    ) -> Result<(), FuelError> {
+        future::block_on(self.delete_collection(owner, collection))
+    }
+
+    /// Adds the model `model_owner/model_name` to a collection
+    pub async fn add_to_collection(
+        &self,
+        owner: &str,
+        collection: &str,
+        model_owner: &str,
+        model_name: &str,
+    ) -> Result<(), FuelError> {
+        let url = format!("{}/models", self.collection_url(owner, collection));
+        let mut req = self.get_request(url);
+        req.method = "POST".to_owned();
+        req.headers.insert("Content-Type", "application/json");
+        req.body = serde_json::json!({ "owner": model_owner, "name": model_name })
+            .to_string()
+            .into_bytes();
+        self.send_collection_request(req).await
+    }
+
+    pub fn add_to_collection_blocking(
+        &self,
+        owner: &str,
+        collection: &str,
+        model_owner: &str,
+        model_name: &str,
+    ) -> Result<(), FuelError> {
+        future::block_on(self.add_to_collection(owner, collection, model_owner, model_name))
+    }
+
+    /// Removes the model `model_owner/model_name` from a collection
+    pub async fn remove_from_collection(
+        &self,
+        owner: &str,
+        collection: &str,
+        model_owner: &str,
+        model_name: &str,
+    ) -> Result<(), FuelError> {
+        let url = format!(
+            "{}/models?o={}&n={}",
+            self.collection_url(owner, collection),
+            uri::encode_query_value(model_owner),
+            uri::encode_query_value(model_name)
+        );
+        let mut req = self.get_request(url);
+        req.method = "DELETE".to_owned();
+        self.send_collection_request(req).await
+    }
+
+    pub fn remove_from_collection_blocking(
+        &self,
+        owner: &str,
+        collection: &str,
+        model_owner: &str,
+        model_name: &str,
+    ) -> Result<(), FuelError> {
+        future::block_on(self.remove_from_collection(owner, collection, model_owner, model_name))
+    }
+
+    /// Models in a collection
+    pub async fn collection_models(
+        &self,
+        owner: &str,
+        collection: &str,
+    ) -> Result<Vec<FuelModel>, FuelError> {
+        let listing = format!(
+            "{}/collections/{}/models",
+            uri::encode_segment(owner),
+            uri::encode_segment(collection)
+        );
+        let crawl = self.crawl_listing(&listing, None, None, None).await?;
+        Ok(crawl.map(|crawl| crawl.models).unwrap_or_default())
+    }
+
+    /// Worlds in a collection
+    pub async fn collection_worlds(
+        &self,
+        owner: &str,
+        collection: &str,
+    ) -> Result<Vec<FuelWorld>, FuelError> {
+        let listing = format!(
+            "{}/collections/{}/worlds",
+            uri::encode_segment(owner),
+            uri::encode_segment(collection)
+        );
+        let crawl = self.crawl_listing(&listing, None, None, None).await?;
+        Ok(crawl.map(|crawl| crawl.models).unwrap_or_default())
+    }
+
+    /// Downloads the latest version of every model and world in a collection to `dest`, following
+    /// the client layout. Fails only if the collection can't be listed, the result of every
+    /// download is returned otherwise.
+    pub async fn download_collection(
+        &self,
+        owner: &str,
+        collection: &str,
+        dest: impl Into<PathBuf>,
+        concurrency: usize,
+        progress: Option<Sender<DownloadProgress>>,
+    ) -> Result<Vec<DownloadResult>, FuelError> {
+        let models = self.collection_models(owner, collection).await?;
+        let worlds = self.collection_worlds(owner, collection).await?;
+        let requests = models
+            .iter()
+            .map(DownloadRequest::from)
+            .chain(worlds.iter().map(DownloadRequest::from))
+            .collect::<Vec<_>>();
+        let mut client = self.clone();
+        client.download_dir = Some(dest.into());
+        Ok(client
+            .download_models_with_progress(requests, concurrency, progress)
+            .await)
+    }
+
+    pub fn download_collection_blocking(
+        &self,
+        owner: &str,
+        collection: &str,
+        dest: impl Into<PathBuf>,
+        concurrency: usize,
+    ) -> Result<Vec<DownloadResult>, FuelError> {
+        future::block_on(self.download_collection(owner, collection, dest, concurrency, None))
+    }
+
+    async fn send_collection_request(&self, req: ehttp::Request) -> Result<(), FuelError> {
+        let res = self.fetch(req).await?;
+        if !res.ok {
+            return Err(FuelError::from_status(res.status));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{self, TempDir},
+        AssetKind,
+    };
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn manage_collections() {
+        let dir = TempDir::new();
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let log = sent.clone();
+        let client = testing::builder(&dir, move |req| {
+            log.lock().unwrap().push(req.clone());
+            let body = match req.method.as_str() {
+                "POST" if req.url.ends_with("/collections") => {
+                    r#"{"name": "Kitchen", "owner": "o", "private": true}"#
+                }
+                _ => "",
+            };
+            Ok(testing::response(&req.url, 200, body))
+        })
+        .build();
+        let collection = FuelCollection {
+            name: "Kitchen".to_owned(),
+            owner: "o".to_owned(),
+            private: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            client.create_collection_blocking(&collection),
+            Ok(collection)
+        );
+        client
+            .add_to_collection_blocking("o", "Kitchen", "p", "table")
+            .unwrap();
+        client
+            .remove_from_collection_blocking("o", "Kitchen", "p", "big table")
+            .unwrap();
+        client.delete_collection_blocking("o", "Kitchen").unwrap();
+
+        let sent = sent.lock().unwrap();
+        let collection_url = format!("{}o/collections/Kitchen", testing::SERVER);
+        let requests = sent
+            .iter()
+            .map(|req| (req.method.as_str(), req.url.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            requests,
+            [
+                ("POST", format!("{}collections", testing::SERVER).as_str()),
+                ("POST", format!("{collection_url}/models").as_str()),
+                (
+                    "DELETE",
+                    format!("{collection_url}/models?o=p&n=big%20table").as_str()
+                ),
+                ("DELETE", collection_url.as_str()),
+            ]
+        );
+        let form = String::from_utf8_lossy(&sent[0].body);
+        assert!(form.contains("name=\"private\"\r\n\r\ntrue\r\n"));
+        assert_eq!(sent[1].body, br#"{"name":"table","owner":"p"}"#);
+    }
+
+    #[test]
+    fn download_collection() {
+        let dir = TempDir::new();
+        let archive = testing::zip_archive(&[("model.sdf", "<sdf/>")]);
+        let client = testing::builder(&dir, move |req| {
+            let listing = match req.url.split_once("/collections/Kitchen/") {
+                Some((_, rest)) if rest.starts_with("models") => {
+                    vec![testing::model("p", "a", "1")]
+                }
+                Some(_) => vec![testing::model("p", "w", "1")],
+                None => return Ok(testing::response(&req.url, 200, archive.clone())),
+            };
+            Ok(testing::listing(req, &listing))
+        })
+        .build();
+        let dest = dir.0.join("downloads");
+        let results = client
+            .download_collection_blocking("o", "Kitchen", &dest, 2)
+            .unwrap();
+        let requests = results
+            .iter()
+            .map(|r| (r.request.name.as_str(), r.request.kind));
+        assert_eq!(
+            requests.collect::<Vec<_>>(),
+            [("a", AssetKind::Model), ("w", AssetKind::World)]
+        );
+        for result in &results {
+            let downloaded = result.result.as_ref().unwrap();
+            assert!(downloaded.path.starts_with(&dest));
+            assert!(downloaded.path.join("model.sdf").is_file());
+        }
+    }
+}
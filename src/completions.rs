@@ -0,0 +1,33 @@
+//! Shell completion scripts, generated from the clap definition of the CLI
+
+use clap::Command;
+
+pub use clap_complete::Shell;
+
+/// Completion script for `shell`, to be sourced by the shell
+pub fn generate(command: &mut Command, shell: Shell) -> String {
+    let bin = command.get_name().to_owned();
+    let mut script = Vec::new();
+    clap_complete::generate(shell, command, bin, &mut script);
+    String::from_utf8_lossy(&script).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_complete_subcommands() {
+        let command = || {
+            Command::new("gz-fuel")
+                .subcommand(Command::new("download").arg(clap::arg!(--output <DIR>)))
+                .subcommand(Command::new("search"))
+        };
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let script = generate(&mut command(), shell);
+            assert!(script.contains("download"), "{shell}");
+            assert!(script.contains("search"), "{shell}");
+            assert!(script.contains("output"), "{shell}");
+        }
+    }
+}
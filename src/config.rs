@@ -0,0 +1,268 @@
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path, path::PathBuf, time::Duration};
+
+use crate::{FuelClient, FuelClientBuilder, FuelError, TlsConfig};
+
+/// Serializable description of one or more clients, meant to be embedded in application config
+/// files. Any serde format works, `FuelConfig::load` reads JSON and, with the `toml` and `yaml`
+/// features, TOML and YAML files.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct FuelConfig {
+    /// Servers to talk to, the public Fuel server is used if empty
+    pub servers: Vec<ServerConfig>,
+    /// Token used for the servers that don't set their own
+    pub token: Option<TokenSource>,
+    /// HTTP basic auth credentials used for all servers
+    pub basic_auth: Option<BasicAuthConfig>,
+    pub per_page: Option<u32>,
+    /// Maximum number of requests per second sent to each server
+    pub requests_per_sec: Option<f64>,
+    /// Age after which the cache should be refreshed, see `FuelClient::should_refresh`
+    pub refresh_threshold_secs: Option<u64>,
+    pub offline: bool,
+    /// Where downloaded assets are extracted for all servers, the platform data dir is used if
+    /// not set. Assets of different servers are kept apart by the default download layout.
+    pub download_dir: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ServerConfig {
+    /// Base url of the server API, i.e. `https://fuel.gazebosim.org/1.0/`
+    pub url: String,
+    /// API version to target instead of the one in `url`, i.e. `2.0`
+    #[serde(default)]
+    pub api_version: Option<String>,
+    /// Path of the model cache file, derived from the server host if not set
+    #[serde(default)]
+    pub cache_path: Option<PathBuf>,
+    /// PEM bundle of root certificates to trust, in addition to the public web roots
+    #[serde(default)]
+    pub ca_bundle: Option<PathBuf>,
+    /// PEM files of a client certificate chain and its private key, for mutual TLS
+    #[serde(default)]
+    pub client_cert: Option<PathBuf>,
+    #[serde(default)]
+    pub client_key: Option<PathBuf>,
+    /// Token for this server only, replaces the global token. An empty token makes requests to
+    /// this server anonymous even if a global token is set.
+    #[serde(default)]
+    pub token: Option<TokenSource>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BasicAuthConfig {
+    pub username: String,
+    pub password: TokenSource,
+}
+
+impl ServerConfig {
+    fn tls(&self) -> Result<Option<TlsConfig>, FuelError> {
+        let client_auth = match (&self.client_cert, &self.client_key) {
+            (Some(cert), Some(key)) => Some((cert.as_path(), key.as_path())),
+            (None, None) => None,
+            _ => {
+                return Err(FuelError::Config(
+                    "client_cert and client_key must be set together".into(),
+                ))
+            }
+        };
+        if self.ca_bundle.is_none() && client_auth.is_none() {
+            return Ok(None);
+        }
+        TlsConfig::load(self.ca_bundle.as_deref(), true, client_auth).map(Some)
+    }
+}
+
+/// Where to read a token from, so secrets don't need to be stored in the config itself
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenSource {
+    /// Name of an environment variable containing the token
+    Env(String),
+    /// File containing the token, surrounding whitespace is ignored
+    File(PathBuf),
+    /// The token itself
+    Value(String),
+}
+
+impl TokenSource {
+    pub fn resolve(&self) -> Result<String, FuelError> {
+        match self {
+            TokenSource::Env(var) => std::env::var(var)
+                .map_err(|e| FuelError::Config(format!("token variable {var}: {e}"))),
+            TokenSource::File(path) => fs::read_to_string(path)
+                .map(|token| token.trim().to_owned())
+                .map_err(|e| FuelError::Config(format!("token file {}: {e}", path.display()))),
+            TokenSource::Value(token) => Ok(token.clone()),
+        }
+    }
+}
+
+impl FuelConfig {
+    /// Loads a config file, the format is picked based on the file extension
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, FuelError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|e| FuelError::Io(e.to_string()))?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => {
+                serde_json::from_str(&contents).map_err(|e| FuelError::Config(e.to_string()))
+            }
+            #[cfg(feature = "toml")]
+            Some("toml") => toml::from_str(&contents).map_err(|e| FuelError::Config(e.to_string())),
+            #[cfg(feature = "yaml")]
+            Some("yaml" | "yml") => {
+                serde_yaml::from_str(&contents).map_err(|e| FuelError::Config(e.to_string()))
+            }
+            _ => Err(FuelError::Config(format!(
+                "unsupported config format {}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Builders for every configured server, in order
+    pub fn builders(&self) -> Result<Vec<FuelClientBuilder>, FuelError> {
+        let token = self.token.as_ref().map(TokenSource::resolve).transpose()?;
+        let basic_auth = self
+            .basic_auth
+            .as_ref()
+            .map(|auth| Ok::<_, FuelError>((&auth.username, auth.password.resolve()?)))
+            .transpose()?;
+        let servers = if self.servers.is_empty() {
+            vec![ServerConfig {
+                url: crate::DEFAULT_URL.to_owned(),
+                ..Default::default()
+            }]
+        } else {
+            self.servers.clone()
+        };
+        servers
+            .into_iter()
+            .enumerate()
+            .map(|(idx, server)| {
+                let mut builder = FuelClient::builder().url(&server.url).offline(self.offline);
+                if let Some(version) = &server.api_version {
+                    builder = builder.api_version(version);
+                }
+                // Only the first server can use the default path without colliding with others
+                let cache_path = server.cache_path.clone().or_else(|| {
+                    (idx > 0)
+                        .then(|| FuelClient::host_cache_path(&server.url))
+                        .flatten()
+                });
+                if let Some(path) = cache_path {
+                    builder = builder.cache_path(path);
+                }
+                let token = match &server.token {
+                    Some(source) => Some(source.resolve()?),
+                    None => token.clone(),
+                };
+                if let Some(token) = token.filter(|token| !token.is_empty()) {
+                    builder = builder.token(token);
+                }
+                if let Some((username, password)) = &basic_auth {
+                    builder = builder.basic_auth(*username, password);
+                }
+                if let Some(per_page) = self.per_page {
+                    builder = builder.per_page(per_page);
+                }
+                if let Some(rate) = self.requests_per_sec {
+                    builder = builder.rate_limit(rate);
+                }
+                if let Some(secs) = self.refresh_threshold_secs {
+                    builder = builder.refresh_threshold(Duration::from_secs(secs));
+                }
+                if let Some(dir) = &self.download_dir {
+                    builder = builder.download_dir(dir);
+                }
+                if let Some(tls) = server.tls()? {
+                    builder = builder.tls(tls);
+                }
+                Ok(builder)
+            })
+            .collect()
+    }
+}
+
+impl FuelClient {
+    /// Builds a client for the first server in the config
+    pub fn from_config(config: &FuelConfig) -> Result<Self, FuelError> {
+        let builder = config.builders()?.into_iter().next();
+        Ok(builder.unwrap_or_default().build())
+    }
+
+    /// Builds a client for every server in the config
+    pub fn clients_from_config(config: &FuelConfig) -> Result<Vec<Self>, FuelError> {
+        Ok(config
+            .builders()?
+            .into_iter()
+            .map(FuelClientBuilder::build)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn expected() -> FuelConfig {
+        FuelConfig {
+            servers: vec![ServerConfig {
+                url: "https://fuel.test/1.0/".into(),
+                token: Some(TokenSource::Env("FUEL_TOKEN".into())),
+                ..Default::default()
+            }],
+            per_page: Some(50),
+            ..Default::default()
+        }
+    }
+
+    fn load(file: &str, contents: &str) -> Result<FuelConfig, FuelError> {
+        let dir = TempDir::new();
+        let path = dir.0.join(file);
+        fs::write(&path, contents).unwrap();
+        FuelConfig::load(path)
+    }
+
+    #[test]
+    fn load_json() {
+        let json = r#"{"servers": [{"url": "https://fuel.test/1.0/", "token": {"env": "FUEL_TOKEN"}}],
+            "per_page": 50}"#;
+        assert_eq!(load("config.json", json).unwrap(), expected());
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn load_yaml() {
+        let yaml =
+            "servers:\n  - url: https://fuel.test/1.0/\n    token: !env FUEL_TOKEN\nper_page: 50\n";
+        assert_eq!(load("config.yaml", yaml).unwrap(), expected());
+        let round_trip = serde_yaml::to_string(&expected()).unwrap();
+        assert_eq!(load("config.yml", &round_trip).unwrap(), expected());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn load_toml() {
+        let toml = "per_page = 50\n[[servers]]\nurl = \"https://fuel.test/1.0/\"\ntoken = { env = \"FUEL_TOKEN\" }\n";
+        assert_eq!(load("config.toml", toml).unwrap(), expected());
+    }
+
+    #[test]
+    fn load_unknown_format() {
+        assert!(matches!(load("config.ini", ""), Err(FuelError::Config(_))));
+    }
+
+    #[test]
+    fn resolve_token_file() {
+        let dir = TempDir::new();
+        let path = dir.0.join("token");
+        fs::write(&path, "  secret\n").unwrap();
+        assert_eq!(TokenSource::File(path).resolve().unwrap(), "secret");
+        assert!(TokenSource::Env("GZ_FUEL_TEST_UNSET".into())
+            .resolve()
+            .is_err());
+    }
+}
@@ -0,0 +1,834 @@
+use crossbeam_channel::Sender;
+use futures_lite::future;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt, fs, io,
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::SystemTime,
+};
+
+use crate::{
+    find_asset, uri, AssetKind, ContentStore, FuelAsset, FuelClient, FuelError, ModelConfig,
+    ModelRef, MODEL_CONFIG_FILE,
+};
+
+/// Maps `(owner, name, version)` to a path relative to the download directory, absolute paths and
+/// paths leaving the download directory are rejected
+pub type LayoutFn = dyn Fn(&str, &str, u32) -> PathBuf + Send + Sync;
+
+/// How downloaded assets are laid out in the download directory
+#[derive(Clone, Default)]
+pub enum DownloadLayout {
+    /// `name/`, only the latest downloaded version of an asset is kept. Models and worlds share
+    /// the same namespace.
+    Flat,
+    /// Same hierarchy as gz-fuel-tools, `host/owner/models/name/version/` with lowercase owner and
+    /// name, worlds are stored under `worlds` instead of `models`
+    #[default]
+    GzFuelTools,
+    /// Used for both models and worlds
+    Custom(Arc<LayoutFn>),
+}
+
+impl fmt::Debug for DownloadLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DownloadLayout::Flat => write!(f, "Flat"),
+            DownloadLayout::GzFuelTools => write!(f, "GzFuelTools"),
+            DownloadLayout::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+impl DownloadLayout {
+    pub fn custom(f: impl Fn(&str, &str, u32) -> PathBuf + Send + Sync + 'static) -> Self {
+        DownloadLayout::Custom(Arc::new(f))
+    }
+
+    /// Directory a model is extracted to
+    pub fn model_path(
+        &self,
+        root: &Path,
+        host: &str,
+        owner: &str,
+        name: &str,
+        version: u32,
+    ) -> Result<PathBuf, FuelError> {
+        self.asset_path(root, host, AssetKind::Model, owner, name, version)
+    }
+
+    /// Directory an asset is extracted to, fails with `FuelError::UnsafePath` unless it is
+    /// strictly under `root`
+    pub fn asset_path(
+        &self,
+        root: &Path,
+        host: &str,
+        kind: AssetKind,
+        owner: &str,
+        name: &str,
+        version: u32,
+    ) -> Result<PathBuf, FuelError> {
+        let unsafe_path = |path: PathBuf| FuelError::UnsafePath {
+            owner: owner.to_owned(),
+            name: name.to_owned(),
+            path,
+        };
+        // Every name is a single path segment, i.e. a name can't be `..` nor contain `/`
+        let segments = match self {
+            DownloadLayout::Flat => vec![name.to_owned()],
+            DownloadLayout::GzFuelTools => vec![
+                host.to_owned(),
+                owner.to_lowercase(),
+                kind.path_segment().to_owned(),
+                name.to_lowercase(),
+                version.to_string(),
+            ],
+            DownloadLayout::Custom(f) => {
+                let relative = f(owner, name, version);
+                return match is_relative_under(&relative) {
+                    true => Ok(root.join(relative)),
+                    false => Err(unsafe_path(root.join(relative))),
+                };
+            }
+        };
+        let path = segments.iter().fold(root.to_path_buf(), |p, s| p.join(s));
+        match segments.iter().all(|s| is_single_segment(s)) {
+            true => Ok(path),
+            false => Err(unsafe_path(path)),
+        }
+    }
+}
+
+fn is_single_segment(segment: &str) -> bool {
+    let mut components = Path::new(segment).components();
+    matches!(components.next(), Some(Component::Normal(_))) && components.next().is_none()
+}
+
+/// Whether a path joined to a directory is strictly under it, without following symlinks
+fn is_relative_under(path: &Path) -> bool {
+    let mut depth = 0;
+    for component in path.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return false,
+        }
+    }
+    depth > 0
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadedModel {
+    pub owner: String,
+    pub name: String,
+    pub version: u32,
+    /// Directory the model was extracted to
+    pub path: PathBuf,
+    /// Parsed `model.config`, None if the archive doesn't contain a valid one. Always None for
+    /// worlds.
+    pub config: Option<ModelConfig>,
+}
+
+impl DownloadedModel {
+    fn new(kind: AssetKind, owner: &str, name: &str, version: u32, path: PathBuf) -> Self {
+        let config = match kind {
+            AssetKind::Model => ModelConfig::load(&path).ok(),
+            AssetKind::World => None,
+        };
+        Self {
+            owner: owner.to_owned(),
+            name: name.to_owned(),
+            version,
+            path,
+            config,
+        }
+    }
+}
+
+/// An asset to download, the latest version is downloaded if `version` is None
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DownloadRequest {
+    pub owner: String,
+    pub name: String,
+    pub version: Option<u32>,
+    pub kind: AssetKind,
+}
+
+impl DownloadRequest {
+    /// Request for a model
+    pub fn new(owner: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            owner: owner.into(),
+            name: name.into(),
+            version: None,
+            kind: AssetKind::Model,
+        }
+    }
+}
+
+impl<A: FuelAsset> From<&A> for DownloadRequest {
+    fn from(asset: &A) -> Self {
+        Self {
+            kind: asset.kind(),
+            ..Self::new(asset.owner(), asset.name())
+        }
+    }
+}
+
+impl From<&ModelRef> for DownloadRequest {
+    fn from(model: &ModelRef) -> Self {
+        Self {
+            version: model.version,
+            ..Self::new(&model.owner, &model.name)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadResult {
+    pub request: DownloadRequest,
+    pub result: Result<DownloadedModel, FuelError>,
+}
+
+/// Sent every time a download of a batch finishes, successfully or not
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadProgress {
+    pub request: DownloadRequest,
+    pub succeeded: bool,
+    /// Number of finished downloads in the batch, including this one
+    pub completed: usize,
+    pub total: usize,
+}
+
+impl FuelClient {
+    /// Downloaded assets are user data, so they live in the platform data dir (i.e.
+    /// `~/.local/share` on Linux) where cache cleaners don't wipe them. Downloads made before
+    /// this moved out of the cache dir keep being used if the data dir has none yet.
+    fn default_download_dir() -> Option<PathBuf> {
+        let dir = |base: PathBuf| base.join("open-robotics").join("gz-fuel").join("models");
+        let data = dirs::data_dir().map(dir);
+        let legacy = dirs::cache_dir().map(dir);
+        match (data, legacy) {
+            (Some(data), Some(legacy)) if !data.exists() && legacy.exists() => Some(legacy),
+            (data, legacy) => data.or(legacy),
+        }
+    }
+
+    /// Directory assets are downloaded to, following the client layout. Separate from the
+    /// metadata cache, see `FuelClientBuilder::download_dir`.
+    pub fn download_dir(&self) -> Option<PathBuf> {
+        self.download_dir
+            .clone()
+            .or_else(Self::default_download_dir)
+    }
+
+    pub(crate) fn require_download_dir(&self) -> Result<PathBuf, FuelError> {
+        self.download_dir()
+            .ok_or_else(|| FuelError::Io("no download directory available".into()))
+    }
+
+    /// Url of an asset archive, `tip` is used for the latest version
+    pub(crate) fn archive_url(
+        &self,
+        kind: AssetKind,
+        owner: &str,
+        name: &str,
+        version: Option<u32>,
+    ) -> String {
+        self.endpoint(&uri::archive_path(kind, owner, name, version))
+    }
+
+    /// Error of an unsuccessful request for an asset or one of its files, 403 is reported as
+    /// `FuelError::PrivateAsset` unless the asset is cached as public, as is 404 for assets
+    /// cached as private. 401 means the credentials themselves were rejected and stays
+    /// `FuelError::Unauthorized`.
+    pub(crate) fn asset_error(
+        &self,
+        kind: AssetKind,
+        owner: &str,
+        name: &str,
+        status: u16,
+    ) -> FuelError {
+        let private = match kind {
+            AssetKind::Model => self
+                .models()
+                .as_ref()
+                .and_then(|models| find_asset(models, owner, name).map(|m| m.private)),
+            AssetKind::World => self
+                .worlds()
+                .as_ref()
+                .and_then(|worlds| find_asset(worlds, owner, name).map(|w| w.private)),
+        };
+        match (status, private) {
+            (403, None | Some(true)) | (404, Some(true)) => FuelError::PrivateAsset {
+                owner: owner.to_owned(),
+                name: name.to_owned(),
+                token: self.token().is_some(),
+            },
+            (status, _) => FuelError::from_status(status),
+        }
+    }
+
+    /// Latest version of an asset, resolved with a HEAD request on its archive
+    pub(crate) async fn latest_version(
+        &self,
+        kind: AssetKind,
+        owner: &str,
+        name: &str,
+    ) -> Result<u32, FuelError> {
+        let mut req = self.get_request(self.archive_url(kind, owner, name, None));
+        req.method = "HEAD".to_owned();
+        let res = self.fetch(req).await?;
+        if !res.ok {
+            return Err(self.asset_error(kind, owner, name, res.status));
+        }
+        Ok(resolved_version(&res.headers, None))
+    }
+
+    /// Host of the server url, used to separate models of different servers on disk
+    pub(crate) fn host(&self) -> String {
+        let url = uri::normalize_url(&self.url);
+        let rest = url.split("://").last().unwrap_or_default();
+        rest.split('/').next().unwrap_or_default().to_owned()
+    }
+
+    /// Downloads and extracts a model archive, the latest version is downloaded if `version` is
+    /// None. An existing download of the same model version is replaced.
+    pub async fn download_model(
+        &self,
+        owner: &str,
+        name: &str,
+        version: Option<u32>,
+    ) -> Result<DownloadedModel, FuelError> {
+        self.preflight(
+            &[DownloadRequest::new(owner, name)],
+            &self.require_download_dir()?,
+        )?;
+        self.download_asset(AssetKind::Model, owner, name, version)
+            .await
+    }
+
+    /// Downloads and extracts an asset archive and runs the download hooks on models. The
+    /// download is recorded in the audit log once the hooks are done, so hook failures are
+    /// logged with it.
+    pub(crate) async fn download_asset(
+        &self,
+        kind: AssetKind,
+        owner: &str,
+        name: &str,
+        version: Option<u32>,
+    ) -> Result<DownloadedModel, FuelError> {
+        let started = SystemTime::now();
+        let mut size = 0;
+        let res = self
+            .download_archive(kind, owner, name, version, &mut size)
+            .await
+            .and_then(|(version, path)| {
+                let downloaded = DownloadedModel::new(kind, owner, name, version, path);
+                self.run_download_hooks(kind, downloaded)
+            });
+        let request = DownloadRequest {
+            kind,
+            version,
+            ..DownloadRequest::new(owner, name)
+        };
+        self.audit_download(request, started, size, &res);
+        res
+    }
+
+    /// Same as `download_asset` without the audit log, `size` is set to the archive size once
+    /// it is downloaded
+    async fn download_archive(
+        &self,
+        kind: AssetKind,
+        owner: &str,
+        name: &str,
+        version: Option<u32>,
+        size: &mut u64,
+    ) -> Result<(u32, PathBuf), FuelError> {
+        let root = self.require_download_dir()?;
+        let url = self.archive_url(kind, owner, name, version);
+        // The archive is streamed to disk so memory usage doesn't depend on its size, the final
+        // path is only known once the server resolved the version
+        fs::create_dir_all(&root).map_err(|e| FuelError::Io(e.to_string()))?;
+        let archive = root.join(format!(
+            ".{}-{}.zip.part",
+            fastrand::u64(..),
+            name.to_lowercase()
+        ));
+        let res = self.fetch_to_file(self.get_request(url), &archive).await;
+        *size = fs::metadata(&archive).map_or(0, |metadata| metadata.len());
+        let extracted = res.and_then(|res| {
+            if !res.ok {
+                return Err(self.asset_error(kind, owner, name, res.status));
+            }
+            let version = resolved_version(&res.headers, version);
+            let path = self
+                .layout
+                .asset_path(&root, &self.host(), kind, owner, name, version)?;
+            extract_archive(&archive, &path, self.content_store.as_ref())?;
+            Ok((version, path))
+        });
+        fs::remove_file(&archive).ok();
+        let (version, path) = extracted?;
+        if kind == AssetKind::Model {
+            if let Ok(xml) = fs::read(path.join(MODEL_CONFIG_FILE)) {
+                self.store_model_config(owner, name, version, &xml);
+            }
+        }
+        Ok((version, path))
+    }
+
+    pub fn download_model_blocking(
+        &self,
+        owner: &str,
+        name: &str,
+        version: Option<u32>,
+    ) -> Result<DownloadedModel, FuelError> {
+        future::block_on(self.download_model(owner, name, version))
+    }
+}
+
+impl FuelClient {
+    /// Downloads all the requested assets using up to `concurrency` parallel downloads, results
+    /// are returned in the same order as the requests. Worlds are downloaded without their
+    /// included models, see `download_world` for that.
+    pub async fn download_models(
+        &self,
+        requests: impl IntoIterator<Item = DownloadRequest>,
+        concurrency: usize,
+    ) -> Vec<DownloadResult> {
+        self.download_models_with_progress(requests, concurrency, None)
+            .await
+    }
+
+    pub async fn download_models_with_progress(
+        &self,
+        requests: impl IntoIterator<Item = DownloadRequest>,
+        concurrency: usize,
+        progress: Option<Sender<DownloadProgress>>,
+    ) -> Vec<DownloadResult> {
+        let progress = progress.or_else(|| self.download_progress.clone());
+        let requests = requests.into_iter().collect::<Vec<_>>();
+        let preflight = self
+            .require_download_dir()
+            .and_then(|dir| self.preflight(&requests, &dir));
+        if let Err(e) = preflight {
+            return requests
+                .into_iter()
+                .map(|request| DownloadResult {
+                    request,
+                    result: Err(e.clone()),
+                })
+                .collect();
+        }
+        let total = requests.len();
+        self.download_batch(requests, concurrency, progress, 0, total)
+            .await
+    }
+
+    /// Downloads a batch of already preflighted requests out of `total`, progress counts the
+    /// downloads of the batch from `offset`
+    pub(crate) async fn download_batch(
+        &self,
+        requests: Vec<DownloadRequest>,
+        concurrency: usize,
+        progress: Option<Sender<DownloadProgress>>,
+        offset: usize,
+        total: usize,
+    ) -> Vec<DownloadResult> {
+        let client = self.clone();
+        let (tx, rx) = async_channel::bounded(1);
+        let pool_requests = requests.clone();
+        let spawned = thread::Builder::new()
+            .name("gz-fuel-downloads".to_owned())
+            .spawn(move || {
+                let results =
+                    client.run_download_pool(pool_requests, concurrency, progress, offset, total);
+                tx.send_blocking(results).ok();
+            });
+        let error = match spawned {
+            // The channel is only closed without results if the pool panicked
+            Ok(_) => match rx.recv().await {
+                Ok(results) => return results,
+                Err(_) => "the download pool stopped unexpectedly".to_owned(),
+            },
+            Err(e) => e.to_string(),
+        };
+        requests
+            .into_iter()
+            .map(|request| DownloadResult {
+                request,
+                result: Err(FuelError::Io(error.clone())),
+            })
+            .collect()
+    }
+
+    pub fn download_models_blocking(
+        &self,
+        requests: impl IntoIterator<Item = DownloadRequest>,
+        concurrency: usize,
+    ) -> Vec<DownloadResult> {
+        future::block_on(self.download_models(requests, concurrency))
+    }
+
+    /// Downloads an asset without its included models, running the download hooks on models
+    pub(crate) async fn download_request(
+        &self,
+        request: &DownloadRequest,
+    ) -> Result<DownloadedModel, FuelError> {
+        self.download_asset(request.kind, &request.owner, &request.name, request.version)
+            .await
+    }
+
+    fn run_download_pool(
+        &self,
+        requests: Vec<DownloadRequest>,
+        concurrency: usize,
+        progress: Option<Sender<DownloadProgress>>,
+        offset: usize,
+        total: usize,
+    ) -> Vec<DownloadResult> {
+        let completed = AtomicUsize::new(offset);
+        run_pool(requests, concurrency, |request| {
+            let result = future::block_on(self.download_request(&request));
+            if let Some(progress) = &progress {
+                progress
+                    .send(DownloadProgress {
+                        request: request.clone(),
+                        succeeded: result.is_ok(),
+                        completed: completed.fetch_add(1, Ordering::Relaxed) + 1,
+                        total,
+                    })
+                    .ok();
+            }
+            DownloadResult { request, result }
+        })
+    }
+}
+
+/// Runs `f` on every job with up to `concurrency` worker threads, results are returned in the
+/// same order as the jobs
+pub(crate) fn run_pool<J, R>(jobs: Vec<J>, concurrency: usize, f: impl Fn(J) -> R + Sync) -> Vec<R>
+where
+    J: Send,
+    R: Send,
+{
+    let workers = concurrency.clamp(1, jobs.len().max(1));
+    let (job_tx, job_rx) = crossbeam_channel::unbounded();
+    for job in jobs.into_iter().enumerate() {
+        job_tx.send(job).ok();
+    }
+    drop(job_tx);
+    let (res_tx, res_rx) = crossbeam_channel::unbounded();
+    thread::scope(|s| {
+        for _ in 0..workers {
+            let job_rx = job_rx.clone();
+            let res_tx = res_tx.clone();
+            let f = &f;
+            s.spawn(move || {
+                for (idx, job) in job_rx {
+                    res_tx.send((idx, f(job))).ok();
+                }
+            });
+        }
+    });
+    drop(res_tx);
+    res_rx
+        .into_iter()
+        .sorted_by_key(|(idx, _)| *idx)
+        .map(|(_, result)| result)
+        .collect()
+}
+
+/// What a download would do, as resolved by a dry run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlannedAction {
+    /// The model version is not on disk yet
+    Fetch,
+    /// The model version is already on disk and would be replaced
+    Replace,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedDownload {
+    pub version: u32,
+    /// Archive size in bytes, as reported by the server or the cache
+    pub size: Option<u64>,
+    /// Directory the model would be extracted to
+    pub path: PathBuf,
+    pub action: PlannedAction,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadPlan {
+    pub request: DownloadRequest,
+    pub result: Result<PlannedDownload, FuelError>,
+}
+
+impl FuelClient {
+    /// Dry run of `download_model`, resolves the version, size and destination of the download
+    /// with a HEAD request, without fetching the archive or touching the disk.
+    pub async fn plan_download(
+        &self,
+        owner: &str,
+        name: &str,
+        version: Option<u32>,
+    ) -> Result<PlannedDownload, FuelError> {
+        self.plan_asset(AssetKind::Model, owner, name, version)
+            .await
+    }
+
+    async fn plan_asset(
+        &self,
+        kind: AssetKind,
+        owner: &str,
+        name: &str,
+        version: Option<u32>,
+    ) -> Result<PlannedDownload, FuelError> {
+        let root = self.require_download_dir()?;
+        let url = self.archive_url(kind, owner, name, version);
+        let mut req = self.get_request(url);
+        req.method = "HEAD".to_owned();
+        let res = self.fetch(req).await?;
+        if !res.ok {
+            return Err(self.asset_error(kind, owner, name, res.status));
+        }
+        let version = resolved_version(&res.headers, version);
+        let size = res
+            .headers
+            .get("content-length")
+            .and_then(|len| len.trim().parse().ok())
+            .or_else(|| self.cached_filesize(kind, owner, name));
+        let path = self
+            .layout
+            .asset_path(&root, &self.host(), kind, owner, name, version)?;
+        let action = if path.exists() {
+            PlannedAction::Replace
+        } else {
+            PlannedAction::Fetch
+        };
+        Ok(PlannedDownload {
+            version,
+            size,
+            path,
+            action,
+        })
+    }
+
+    pub async fn plan_downloads(
+        &self,
+        requests: impl IntoIterator<Item = DownloadRequest>,
+    ) -> Vec<DownloadPlan> {
+        let mut plans = Vec::new();
+        for request in requests {
+            let result = self
+                .plan_asset(request.kind, &request.owner, &request.name, request.version)
+                .await;
+            plans.push(DownloadPlan { request, result });
+        }
+        plans
+    }
+
+    pub fn plan_downloads_blocking(
+        &self,
+        requests: impl IntoIterator<Item = DownloadRequest>,
+    ) -> Vec<DownloadPlan> {
+        future::block_on(self.plan_downloads(requests))
+    }
+}
+
+/// The server reports the version it resolved `tip` to in a header
+pub(crate) fn header_version(headers: &ehttp::Headers) -> Option<u32> {
+    headers
+        .get("x-ign-resource-version")
+        .and_then(|v| v.trim().parse().ok())
+}
+
+/// Version of a download, the requested one or 1 if the server didn't report it
+pub(crate) fn resolved_version(headers: &ehttp::Headers, requested: Option<u32>) -> u32 {
+    header_version(headers).or(requested).unwrap_or(1)
+}
+
+/// Extracts the zip archive at `archive` to `dest`, going through a temporary directory so `dest`
+/// is never left half extracted. Files are deduplicated in `store` if given.
+///
+/// Entries are decompressed one at a time from the file. The archive isn't decoded while it is
+/// received because entries can store their size after their data, which is only known from the
+/// central directory at the end of the archive.
+fn extract_archive(
+    archive: &Path,
+    dest: &Path,
+    store: Option<&ContentStore>,
+) -> Result<(), FuelError> {
+    let io_err = |e: std::io::Error| FuelError::Io(e.to_string());
+    let file = fs::File::open(archive).map_err(io_err)?;
+    let mut archive = zip::ZipArchive::new(io::BufReader::new(file))
+        .map_err(|e| FuelError::Parse(e.to_string()))?;
+    let mut tmp = dest.as_os_str().to_owned();
+    tmp.push(".part");
+    let tmp = PathBuf::from(tmp);
+    if tmp.exists() {
+        fs::remove_dir_all(&tmp).map_err(io_err)?;
+    }
+    fs::create_dir_all(&tmp).map_err(io_err)?;
+    archive
+        .extract(&tmp)
+        .map_err(|e| FuelError::Io(e.to_string()))?;
+    if let Some(store) = store {
+        store.deduplicate(&tmp)?;
+    }
+    if dest.exists() {
+        fs::remove_dir_all(dest).map_err(io_err)?;
+    }
+    fs::rename(&tmp, dest).map_err(io_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TempDir};
+
+    fn path(layout: &DownloadLayout, owner: &str, name: &str) -> Result<PathBuf, FuelError> {
+        layout.model_path(Path::new("/root"), "fuel.test", owner, name, 2)
+    }
+
+    #[test]
+    fn hooks_run_on_private_copies_before_the_audit() {
+        let dir = TempDir::new();
+        let archive = testing::zip_archive(&[("model.sdf", "<sdf/>")]);
+        let hook = |downloaded: &DownloadedModel, _: Option<&crate::FuelModel>| match downloaded
+            .name
+            .as_str()
+        {
+            "b" => fs::write(downloaded.path.join("model.sdf"), "<sdf>b</sdf>")
+                .map_err(|e| e.to_string()),
+            "c" => Err("no collisions".to_owned()),
+            _ => Ok(()),
+        };
+        let client = testing::builder(&dir, move |req| {
+            Ok(testing::response(&req.url, 200, archive.clone()))
+        })
+        .download_dir(dir.0.join("downloads"))
+        .content_store(dir.0.join("store"))
+        .audit_log(dir.0.join("audit.jsonl"))
+        .download_hook(Arc::new(hook))
+        .build();
+        let results = client.download_models_blocking(
+            ["a", "b", "c"].map(|name| DownloadRequest::new("o", name)),
+            1,
+        );
+        let sdf = |idx: usize| {
+            let path = &results[idx].result.as_ref().unwrap().path;
+            fs::read_to_string(path.join("model.sdf")).unwrap()
+        };
+        assert_eq!(sdf(0), "<sdf/>");
+        assert_eq!(sdf(1), "<sdf>b</sdf>");
+        assert!(matches!(results[2].result, Err(FuelError::Hook { .. })));
+        let entries = client.audit_entries(None).unwrap();
+        assert_eq!(entries.iter().filter(|entry| entry.succeeded()).count(), 2);
+        assert!(entries[2].error.as_ref().unwrap().contains("no collisions"));
+    }
+
+    #[test]
+    fn panicked_pools_report_every_request() {
+        let dir = TempDir::new();
+        let archive = testing::zip_archive(&[("model.sdf", "<sdf/>")]);
+        let hook = |_: &DownloadedModel, _: Option<&crate::FuelModel>| -> Result<(), String> {
+            panic!("hook panicked")
+        };
+        let client = testing::builder(&dir, move |req| {
+            Ok(testing::response(&req.url, 200, archive.clone()))
+        })
+        .download_dir(dir.0.join("downloads"))
+        .download_hook(Arc::new(hook))
+        .build();
+        let results = client
+            .download_models_blocking(["a", "b"].map(|name| DownloadRequest::new("o", name)), 2);
+        assert_eq!(results.len(), 2);
+        assert!(results
+            .iter()
+            .all(|result| matches!(result.result, Err(FuelError::Io(_)))));
+    }
+
+    #[test]
+    fn pool_results_keep_the_job_order() {
+        let jobs = (0..20).collect::<Vec<u64>>();
+        let results = run_pool(jobs, 4, |job| {
+            thread::sleep(std::time::Duration::from_millis(20 - job));
+            job * 2
+        });
+        assert_eq!(results, (0..20).map(|job| job * 2).collect::<Vec<_>>());
+        assert_eq!(run_pool(Vec::<u64>::new(), 0, |job| job), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn layouts_stay_under_root() {
+        assert_eq!(
+            path(&DownloadLayout::Flat, "o", "Table"),
+            Ok(PathBuf::from("/root/Table"))
+        );
+        assert_eq!(
+            path(&DownloadLayout::GzFuelTools, "Open", "Table"),
+            Ok(PathBuf::from("/root/fuel.test/open/models/table/2"))
+        );
+        let custom = DownloadLayout::custom(|owner, name, _| format!("{owner}/./{name}").into());
+        assert_eq!(path(&custom, "o", "m"), Ok(PathBuf::from("/root/o/./m")));
+    }
+
+    #[test]
+    fn layouts_reject_escaping_names() {
+        for name in ["..", "../x", "a/b", "/etc", "", "."] {
+            assert!(matches!(
+                path(&DownloadLayout::Flat, "o", name),
+                Err(FuelError::UnsafePath { .. })
+            ));
+        }
+        assert!(path(&DownloadLayout::GzFuelTools, "..", "m").is_err());
+        assert!(path(&DownloadLayout::GzFuelTools, "o", "..").is_err());
+    }
+
+    #[test]
+    fn custom_layouts_reject_absolute_paths() {
+        let absolute = DownloadLayout::custom(|_, name, _| Path::new("/tmp").join(name));
+        assert!(path(&absolute, "o", "m").is_err());
+        let parent = DownloadLayout::custom(|_, name, _| Path::new("..").join(name));
+        assert!(path(&parent, "o", "m").is_err());
+        let empty = DownloadLayout::custom(|_, _, _| PathBuf::new());
+        assert!(path(&empty, "o", "m").is_err());
+    }
+
+    #[test]
+    fn asset_errors() {
+        let dir = TempDir::new();
+        let client = crate::testing::builder(&dir, |_| Err("offline".into()))
+            .token("secret")
+            .build();
+        let error = |name, status| client.asset_error(AssetKind::Model, "o", name, status);
+        let private = |name: &str| FuelError::PrivateAsset {
+            owner: "o".into(),
+            name: name.into(),
+            token: true,
+        };
+        // Not cached
+        assert_eq!(error("m", 401), FuelError::Unauthorized);
+        assert_eq!(error("m", 403), private("m"));
+        assert_eq!(error("m", 404), FuelError::Http(404));
+        assert_eq!(error("m", 500), FuelError::Http(500));
+        let mut hidden = crate::testing::model("o", "hidden", "");
+        hidden.private = true;
+        let public = crate::testing::model("o", "public", "");
+        client.set_model_cache(crate::ModelCache::new(vec![hidden, public]));
+        assert_eq!(error("hidden", 401), FuelError::Unauthorized);
+        assert_eq!(error("hidden", 403), private("hidden"));
+        assert_eq!(error("hidden", 404), private("hidden"));
+        assert_eq!(error("public", 403), FuelError::Forbidden);
+        assert_eq!(error("public", 404), FuelError::Http(404));
+    }
+}
@@ -0,0 +1,153 @@
+use itertools::Itertools;
+
+use crate::{FuelClient, FuelModel};
+
+/// Further criteria for two models with the same name to be reported as duplicates, by default
+/// the name is enough
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DuplicateCriteria {
+    /// Maximum relative difference of filesizes, i.e. `0.05` for 5%
+    pub filesize_tolerance: Option<f64>,
+    /// Descriptions must be equal, ignoring case and whitespace
+    pub same_description: bool,
+}
+
+impl DuplicateCriteria {
+    fn matches(&self, a: &FuelModel, b: &FuelModel) -> bool {
+        let normalize = |description: &str| {
+            description
+                .split_whitespace()
+                .map(str::to_lowercase)
+                .join(" ")
+        };
+        let sizes_match = self.filesize_tolerance.is_none_or(|tolerance| {
+            let (a, b) = (a.filesize as f64, b.filesize as f64);
+            (a - b).abs() <= tolerance * a.max(b)
+        });
+        sizes_match
+            && (!self.same_description || normalize(&a.description) == normalize(&b.description))
+    }
+}
+
+/// Models of different owners sharing the same name, compared case insensitively
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateGroup {
+    /// Lowercase name shared by the models
+    pub name: String,
+    /// Most downloaded first
+    pub models: Vec<FuelModel>,
+}
+
+/// Groups of duplicated models, largest groups first. Within a name, models are grouped with the
+/// first model of a group they match `criteria` with, groups of a single owner are left out.
+pub fn find_duplicates(models: &[FuelModel], criteria: &DuplicateCriteria) -> Vec<DuplicateGroup> {
+    let by_name = models
+        .iter()
+        .into_group_map_by(|model| model.name.to_lowercase());
+    let mut duplicates = Vec::new();
+    for (name, same_name) in by_name {
+        let mut groups: Vec<Vec<&FuelModel>> = Vec::new();
+        for model in same_name
+            .into_iter()
+            .sorted_by(|a, b| b.downloads.cmp(&a.downloads))
+        {
+            match groups
+                .iter_mut()
+                .find(|group| criteria.matches(group[0], model))
+            {
+                Some(group) => group.push(model),
+                None => groups.push(vec![model]),
+            }
+        }
+        for group in groups {
+            if group
+                .iter()
+                .map(|model| model.owner.to_lowercase())
+                .all_equal()
+            {
+                continue;
+            }
+            duplicates.push(DuplicateGroup {
+                name: name.clone(),
+                models: group.into_iter().cloned().collect(),
+            });
+        }
+    }
+    duplicates.sort_by(|a, b| {
+        b.models
+            .len()
+            .cmp(&a.models.len())
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    duplicates
+}
+
+impl FuelClient {
+    /// Duplicated cached models, i.e. the many forks of common assets like "Table", see
+    /// `find_duplicates`. Returns None if there is no cache.
+    pub fn duplicate_models(&self, criteria: &DuplicateCriteria) -> Option<Vec<DuplicateGroup>> {
+        let models = self.models();
+        Some(find_duplicates(models.as_ref()?, criteria))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TempDir};
+
+    fn asset(owner: &str, name: &str, downloads: u32, filesize: usize) -> FuelModel {
+        FuelModel {
+            downloads,
+            filesize,
+            ..testing::model(owner, name, "1")
+        }
+    }
+
+    fn groups(duplicates: &[DuplicateGroup]) -> Vec<(&str, Vec<&str>)> {
+        duplicates
+            .iter()
+            .map(|group| {
+                let owners = group.models.iter().map(|model| &*model.owner).collect();
+                (group.name.as_str(), owners)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn duplicate_models() {
+        let dir = TempDir::new();
+        let catalog = vec![
+            asset("o1", "Table", 5, 100),
+            asset("o2", "table", 9, 102),
+            asset("o3", "TABLE", 1, 500),
+            asset("o1", "chair", 0, 0),
+            asset("O1", "Chair", 0, 0),
+            asset("o1", "lamp", 0, 0),
+            asset("o2", "lamp", 0, 0),
+        ];
+        let client = testing::builder(&dir, move |req| Ok(testing::listing(req, &catalog))).build();
+        assert_eq!(client.duplicate_models(&DuplicateCriteria::default()), None);
+        client.update_cache_blocking(false).unwrap();
+
+        let duplicates = client
+            .duplicate_models(&DuplicateCriteria::default())
+            .unwrap();
+        assert_eq!(
+            groups(&duplicates),
+            [
+                ("table", vec!["o2", "o1", "o3"]),
+                ("lamp", vec!["o1", "o2"])
+            ]
+        );
+        let criteria = DuplicateCriteria {
+            filesize_tolerance: Some(0.05),
+            ..Default::default()
+        };
+        let duplicates = client.duplicate_models(&criteria).unwrap();
+        assert_eq!(
+            groups(&duplicates),
+            [("lamp", vec!["o1", "o2"]), ("table", vec!["o2", "o1"])]
+        );
+    }
+}
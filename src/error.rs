@@ -0,0 +1,102 @@
+use std::{fmt, path::PathBuf};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FuelError {
+    /// The client is in offline mode, no request was made
+    Offline,
+    /// The server could not be reached, contains the underlying transport error
+    Network(String),
+    /// The server rejected the provided token, or a token is required (401)
+    Unauthorized,
+    /// The token was accepted but doesn't grant access to the resource (403)
+    Forbidden,
+    /// The server answered with an unexpected status code
+    Http(u16),
+    /// A server response or local file could not be parsed
+    Parse(String),
+    /// Reading or writing local files failed
+    Io(String),
+    /// The client configuration is invalid or incomplete
+    Config(String),
+    /// The downloads need more disk space than available, in bytes
+    InsufficientSpace { needed: u64, available: u64 },
+    /// A private asset could not be accessed, `token` tells whether a token was sent. Servers
+    /// answer 403, or 404 to hide private assets from users without access.
+    PrivateAsset {
+        owner: String,
+        name: String,
+        token: bool,
+    },
+    /// A redirect led back to an url that was already visited
+    RedirectLoop { url: String },
+    /// More redirects than allowed by the client `RedirectPolicy`, `url` is the next one
+    TooManyRedirects { url: String, max: u32 },
+    /// The download layout placed an asset outside of the download directory, i.e. because its
+    /// name contains `..` or a custom layout returned an absolute path
+    UnsafePath {
+        owner: String,
+        name: String,
+        path: PathBuf,
+    },
+    /// A download hook failed on a downloaded model, its files are still on disk
+    Hook {
+        owner: String,
+        name: String,
+        error: String,
+    },
+}
+
+impl FuelError {
+    /// Maps an unsuccessful HTTP status code to the matching error
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            401 => FuelError::Unauthorized,
+            403 => FuelError::Forbidden,
+            status => FuelError::Http(status),
+        }
+    }
+}
+
+impl fmt::Display for FuelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FuelError::Offline => write!(f, "the client is offline"),
+            FuelError::Network(e) => write!(f, "network error: {e}"),
+            FuelError::Unauthorized => write!(f, "unauthorized, the token is invalid or expired"),
+            FuelError::Forbidden => write!(f, "forbidden, the token doesn't grant access"),
+            FuelError::Http(status) => write!(f, "unexpected HTTP status {status}"),
+            FuelError::Parse(e) => write!(f, "parse error: {e}"),
+            FuelError::Io(e) => write!(f, "io error: {e}"),
+            FuelError::Config(e) => write!(f, "invalid configuration: {e}"),
+            FuelError::InsufficientSpace { needed, available } => write!(
+                f,
+                "not enough disk space, {needed} bytes needed but {available} available"
+            ),
+            FuelError::PrivateAsset {
+                owner,
+                name,
+                token: true,
+            } => write!(
+                f,
+                "the token doesn't grant access to the private asset {owner}/{name}"
+            ),
+            FuelError::PrivateAsset { owner, name, .. } => {
+                write!(f, "{owner}/{name} is private, a token is required")
+            }
+            FuelError::RedirectLoop { url } => write!(f, "redirect loop through {url}"),
+            FuelError::TooManyRedirects { url, max } => {
+                write!(f, "more than {max} redirects, the last one to {url}")
+            }
+            FuelError::UnsafePath { owner, name, path } => write!(
+                f,
+                "{owner}/{name} would be extracted to {}, outside of the download directory",
+                path.display()
+            ),
+            FuelError::Hook { owner, name, error } => {
+                write!(f, "download hook failed on {owner}/{name}: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FuelError {}
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{FuelClient, FuelError, FuelModel};
+
+/// Extension of the favorites file, stored next to the cache file
+const FAVORITES_FILE: &str = "favorites.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PinnedModel {
+    pub owner: String,
+    pub name: String,
+}
+
+/// Pinned models keyed by lowercase `owner/name`, stored next to the cache file
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Favorites {
+    pub models: BTreeMap<String, PinnedModel>,
+}
+
+fn favorite_key(owner: &str, name: &str) -> String {
+    format!("{}/{}", owner.to_lowercase(), name.to_lowercase())
+}
+
+impl Favorites {
+    pub fn contains(&self, owner: &str, name: &str) -> bool {
+        self.models.contains_key(&favorite_key(owner, name))
+    }
+}
+
+impl FuelClient {
+    /// Empty if there are none or the favorites file can't be parsed, pinning and unpinning
+    /// models report the parse error instead
+    pub fn favorites(&self) -> Favorites {
+        self.read_sidecar(FAVORITES_FILE).unwrap_or_default()
+    }
+
+    /// Adds a model to the favorites, it doesn't need to be cached. Returns false if it was
+    /// already pinned.
+    pub fn pin_model(&self, owner: &str, name: &str) -> Result<bool, FuelError> {
+        self.update_sidecar(FAVORITES_FILE, |favorites: &mut Favorites| {
+            let key = favorite_key(owner, name);
+            if favorites.models.contains_key(&key) {
+                return false;
+            }
+            favorites.models.insert(
+                key,
+                PinnedModel {
+                    owner: owner.to_owned(),
+                    name: name.to_owned(),
+                },
+            );
+            true
+        })
+    }
+
+    /// Returns false if the model wasn't pinned
+    pub fn unpin_model(&self, owner: &str, name: &str) -> Result<bool, FuelError> {
+        self.update_sidecar(FAVORITES_FILE, |favorites: &mut Favorites| {
+            favorites
+                .models
+                .remove(&favorite_key(owner, name))
+                .is_some()
+        })
+    }
+
+    /// Only the pinned models, pinned models missing from the cache are left out
+    pub fn favorites_only(&self, models: Option<&Vec<FuelModel>>) -> Option<Vec<FuelModel>> {
+        let favorites = self.favorites();
+        let cached = self.models();
+        let models = models.map(Vec::as_slice).or(cached.as_deref())?;
+        Some(
+            models
+                .iter()
+                .filter(|model| favorites.contains(&model.owner, &model.name))
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TempDir};
+    use std::fs;
+
+    #[test]
+    fn pin_and_unpin() {
+        let dir = TempDir::new();
+        let client = testing::builder(&dir, |_| Err("offline".into())).build();
+        assert_eq!(client.pin_model("Owner", "Table"), Ok(true));
+        assert_eq!(client.pin_model("owner", "table"), Ok(false));
+        assert!(client.favorites().contains("owner", "table"));
+        assert_eq!(client.unpin_model("owner", "TABLE"), Ok(true));
+        assert_eq!(client.favorites(), Default::default());
+    }
+
+    #[test]
+    fn unparsable_favorites_are_kept() {
+        let dir = TempDir::new();
+        let client = testing::builder(&dir, |_| Err("offline".into())).build();
+        let path = dir.0.join("model_cache.favorites.json");
+        fs::write(&path, "{ \"models\": ").unwrap();
+        assert!(matches!(
+            client.pin_model("owner", "table"),
+            Err(FuelError::Parse(_))
+        ));
+        assert!(client.unpin_model("owner", "table").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{ \"models\": ");
+    }
+}
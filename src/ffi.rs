@@ -0,0 +1,229 @@
+//! C API, built with the `ffi` feature, see `include/gz_fuel.h`. Functions returning a pointer
+//! return NULL on failure and functions returning an int return 0 on success and -1 on failure,
+//! the error message of the last failure on the calling thread is returned by
+//! `gz_fuel_last_error`. Everything allocated by the library must be released with the matching
+//! `_free` function.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_int, CStr, CString},
+    ptr,
+};
+
+use crate::{FuelClient, FuelError, FuelModel};
+
+/// Opaque client handle
+pub struct GzFuelClient {
+    client: FuelClient,
+}
+
+#[repr(C)]
+pub struct GzFuelModel {
+    pub owner: *mut c_char,
+    pub name: *mut c_char,
+    pub description: *mut c_char,
+    pub downloads: u32,
+    pub likes: u32,
+    /// Archive size in bytes
+    pub filesize: u64,
+    pub is_private: bool,
+}
+
+#[repr(C)]
+pub struct GzFuelModelList {
+    pub models: *mut GzFuelModel,
+    pub len: usize,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(msg: impl Into<String>) {
+    let msg = CString::new(msg.into().replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(msg));
+}
+
+fn to_c_string(s: &str) -> *mut c_char {
+    CString::new(s.replace('\0', " "))
+        .unwrap_or_default()
+        .into_raw()
+}
+
+/// None for NULL pointers, errors out on invalid UTF-8
+unsafe fn from_c_str<'a>(s: *const c_char) -> Result<Option<&'a str>, FuelError> {
+    if s.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map(Some)
+        .map_err(|e| FuelError::Parse(e.to_string()))
+}
+
+/// Client handle or an error if the pointer is NULL
+unsafe fn client<'a>(client: *const GzFuelClient) -> Result<&'a FuelClient, FuelError> {
+    client
+        .as_ref()
+        .map(|handle| &handle.client)
+        .ok_or_else(|| FuelError::Parse("NULL client".into()))
+}
+
+/// Message of the last error on the calling thread, NULL if there was none. The string is owned
+/// by the library and valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn gz_fuel_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+/// Creates a client, `url` and `token` can be NULL to use the defaults
+///
+/// # Safety
+/// `url` and `token` must be NULL or valid NUL terminated strings
+#[no_mangle]
+pub unsafe extern "C" fn gz_fuel_client_new(
+    url: *const c_char,
+    token: *const c_char,
+) -> *mut GzFuelClient {
+    let (url, token) = match (from_c_str(url), from_c_str(token)) {
+        (Ok(url), Ok(token)) => (url, token),
+        (Err(e), _) | (_, Err(e)) => {
+            set_error(e.to_string());
+            return ptr::null_mut();
+        }
+    };
+    let mut builder = FuelClient::builder();
+    if let Some(url) = url {
+        builder = builder.url(url);
+    }
+    if let Some(token) = token {
+        builder = builder.token(token);
+    }
+    Box::into_raw(Box::new(GzFuelClient {
+        client: builder.build(),
+    }))
+}
+
+/// # Safety
+/// `client` must be NULL or returned by `gz_fuel_client_new` and not freed yet
+#[no_mangle]
+pub unsafe extern "C" fn gz_fuel_client_free(client: *mut GzFuelClient) {
+    if !client.is_null() {
+        drop(Box::from_raw(client));
+    }
+}
+
+/// Crawls the server catalog and replaces the cache, `write_to_disk` also replaces the cache file
+///
+/// # Safety
+/// `client` must be returned by `gz_fuel_client_new` and not freed yet
+#[no_mangle]
+pub unsafe extern "C" fn gz_fuel_update_cache(
+    client: *const GzFuelClient,
+    write_to_disk: bool,
+) -> c_int {
+    match self::client(client).and_then(|c| c.update_cache_blocking(write_to_disk)) {
+        Ok(_) => 0,
+        Err(e) => {
+            set_error(e.to_string());
+            -1
+        }
+    }
+}
+
+/// Cached models, only the ones of `owner` if it isn't NULL. An empty list is returned if there
+/// is no cache.
+///
+/// # Safety
+/// `client` must be returned by `gz_fuel_client_new` and not freed yet, `owner` must be NULL or a
+/// valid NUL terminated string
+#[no_mangle]
+pub unsafe extern "C" fn gz_fuel_list_models(
+    client: *const GzFuelClient,
+    owner: *const c_char,
+) -> *mut GzFuelModelList {
+    let (client, owner) = match (self::client(client), from_c_str(owner)) {
+        (Ok(client), Ok(owner)) => (client, owner),
+        (Err(e), _) | (_, Err(e)) => {
+            set_error(e.to_string());
+            return ptr::null_mut();
+        }
+    };
+    let models = match owner {
+        Some(owner) => client.models_by_owner(None, owner),
+        None => client.model_cache().map(|cache| cache.models),
+    }
+    .unwrap_or_default();
+    let models = models
+        .iter()
+        .map(|model: &FuelModel| GzFuelModel {
+            owner: to_c_string(&model.owner),
+            name: to_c_string(&model.name),
+            description: to_c_string(&model.description),
+            downloads: model.downloads,
+            likes: model.likes,
+            filesize: model.filesize as u64,
+            is_private: model.private,
+        })
+        .collect::<Box<[_]>>();
+    let len = models.len();
+    Box::into_raw(Box::new(GzFuelModelList {
+        models: Box::into_raw(models) as *mut GzFuelModel,
+        len,
+    }))
+}
+
+/// # Safety
+/// `list` must be NULL or returned by `gz_fuel_list_models` and not freed yet
+#[no_mangle]
+pub unsafe extern "C" fn gz_fuel_model_list_free(list: *mut GzFuelModelList) {
+    if list.is_null() {
+        return;
+    }
+    let list = Box::from_raw(list);
+    let models = Box::from_raw(ptr::slice_from_raw_parts_mut(list.models, list.len));
+    for model in models.iter() {
+        gz_fuel_string_free(model.owner);
+        gz_fuel_string_free(model.name);
+        gz_fuel_string_free(model.description);
+    }
+}
+
+/// Downloads and extracts a model, `version` 0 is the latest version. Returns the directory the
+/// model was extracted to, to be freed with `gz_fuel_string_free`.
+///
+/// # Safety
+/// `client` must be returned by `gz_fuel_client_new` and not freed yet, `owner` and `name` must
+/// be valid NUL terminated strings
+#[no_mangle]
+pub unsafe extern "C" fn gz_fuel_download_model(
+    client: *const GzFuelClient,
+    owner: *const c_char,
+    name: *const c_char,
+    version: u32,
+) -> *mut c_char {
+    let downloaded = (|| {
+        let client = self::client(client)?;
+        let (Some(owner), Some(name)) = (from_c_str(owner)?, from_c_str(name)?) else {
+            return Err(FuelError::Parse("NULL owner or name".into()));
+        };
+        let version = (version != 0).then_some(version);
+        client.download_model_blocking(owner, name, version)
+    })();
+    match downloaded {
+        Ok(model) => to_c_string(&model.path.to_string_lossy()),
+        Err(e) => {
+            set_error(e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// # Safety
+/// `s` must be NULL or a string returned by the library and not freed yet
+#[no_mangle]
+pub unsafe extern "C" fn gz_fuel_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
@@ -1,88 +1,558 @@
+use crossbeam_channel::Sender;
 use futures_lite::future;
 use itertools::Itertools;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
-    fs,
-    path::PathBuf,
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
     time::{Duration, SystemTime},
 };
-use crossbeam_channel::Sender;
 
-// TODO(luca) clone can be unsafe if two instances try to write to the same file
+use crate::{
+    asset::{self, CacheEntry},
+    checkpoint::{Checkpoint, CheckpointWriter},
+    http,
+    intern::intern_assets,
+    model_cache,
+    response_cache::REVALIDATION_HEADER,
+    uri, AssetKind, AuthStatus, BasicAuth, ContentStore, DownloadHook, DownloadLayout,
+    DownloadProgress, FuelClientBuilder, FuelError, FuelModel, FuelWorld, HttpBackend, Metrics,
+    Middleware, ModelCache, RateLimiter, RedirectPolicy, RetryPolicy, Sort,
+};
+
+/// Endpoint answering with the profile of the user the credentials belong to, it fails with 401
+/// unless the server accepts the credentials
+pub(crate) const CREDENTIALS_ENDPOINT: &str = "login";
+
+/// Returns a new token, or None if no new token could be obtained
+pub type TokenRefreshFn = dyn Fn() -> Option<String> + Send + Sync;
+
+/// Clones of a client share the same models and cache file lock, so one clone can refresh the
+/// cache in a background thread while others keep reading from it.
 #[derive(Clone)]
 pub struct FuelClient {
     pub url: String,
     pub cache_path: Option<PathBuf>,
-    pub models: Option<Vec<FuelModel>>,
-    pub token: Option<String>,
+    pub(crate) models: Arc<RwLock<Option<ModelCache>>>,
+    /// Worlds are cached separately as they are only crawled on request
+    pub(crate) worlds: Arc<RwLock<Option<Vec<FuelWorld>>>>,
+    /// User defined entries, merged into the models after every cache update
+    pub(crate) local_models: Arc<RwLock<Vec<FuelModel>>>,
+    /// Held while writing the cache files, to avoid clones racing on them
+    pub(crate) cache_file_lock: Arc<Mutex<()>>,
+    /// Shared between clones so a refreshed token is seen by all of them
+    pub(crate) token: Arc<RwLock<Option<String>>>,
+    /// Sent with every request, alongside the token
+    pub basic_auth: Option<BasicAuth>,
+    /// Called to get a new token when the server rejects the current one
+    pub(crate) token_refresh: Option<Arc<TokenRefreshFn>>,
+    /// Number of models requested per page when listing models
+    pub per_page: u32,
+    /// When set, network methods fail immediately with `FuelError::Offline` and only cached data
+    /// is served
+    pub offline: bool,
+    pub retry: RetryPolicy,
+    pub redirect: RedirectPolicy,
+    /// Applied to every request, shared between clones
+    pub(crate) rate_limiter: Option<Arc<RateLimiter>>,
+    pub(crate) metrics: Option<Arc<dyn Metrics>>,
+    /// Run in order before every request and in reverse order after every response
+    pub(crate) middlewares: Vec<Arc<dyn Middleware>>,
+    /// Run in order on every downloaded model
+    pub(crate) download_hooks: Vec<Arc<dyn DownloadHook>>,
+    /// Cache age after which `should_refresh` returns true
+    pub refresh_threshold: Option<Duration>,
+    /// Used by cache updates when no progress sink is passed explicitly
+    pub progress: Option<Sender<FuelModel>>,
+    /// Page level progress of every listing crawl, i.e. to show "page 42/198"
+    pub page_progress: Option<Sender<PageProgress>>,
+    /// Used by batch downloads and mirror syncs when no progress sink is passed explicitly
+    pub download_progress: Option<Sender<DownloadProgress>>,
+    pub(crate) backend: Arc<dyn HttpBackend>,
+    /// Where downloaded models are extracted, a directory in the platform data dir if None
+    pub download_dir: Option<PathBuf>,
+    pub layout: DownloadLayout,
+    /// When set, the files of downloaded assets are deduplicated in this store
+    pub content_store: Option<ContentStore>,
+    /// Record the downloads and likes of every model on full cache refreshes written to disk,
+    /// see `stats_history`
+    pub track_stats: bool,
+    /// Number of copies of the cache file kept after full cache refreshes written to disk, oldest
+    /// ones are removed first. No snapshots are taken if 0, see `snapshots`.
+    pub keep_snapshots: usize,
+    /// When a cache refresh fails midway, update the cached entries of the models fetched so far
+    /// instead of keeping the previous cache as is. Models that weren't fetched are kept either
+    /// way, and the refresh still returns the error.
+    pub commit_partial_crawls: bool,
+    /// Fail downloads and mirror syncs early when the cached sizes of the assets exceed the
+    /// available disk space, see `check_disk_space`. Enabled by default.
+    pub disk_space_check: bool,
+    /// Pretty print the models and worlds cache files, they are written compact by default as
+    /// pretty printing roughly doubles their size
+    pub pretty_cache: bool,
+    pub(crate) load_status: CacheLoadStatus,
+    /// File the query presets are saved to, a file in the platform config directory if None
+    pub presets_path: Option<PathBuf>,
+    /// Append only log every download is recorded to, see `audit_entries`. Nothing is recorded if
+    /// None.
+    pub audit_log: Option<PathBuf>,
+}
+
+/// Outcome of loading the cache file
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum CacheLoadStatus {
+    /// There is no cache file yet, or no cache path is available
+    #[default]
+    NotFound,
+    /// The cache file exists but couldn't be read or parsed, the client starts without models
+    Corrupt(FuelError),
+    /// Number of models read from the cache file, local entries excluded
+    Loaded(usize),
 }
 
 impl Default for FuelClient {
     fn default() -> Self {
-        let client = Self {
-            url: "https://fuel.gazebosim.org/1.0/".into(),
-            cache_path: None,
-            models: None,
-            token: None,
-        };
-        client.with_cache(None)
+        FuelClientBuilder::default().build()
     }
 }
 
 impl FuelClient {
+    pub fn builder() -> FuelClientBuilder {
+        FuelClientBuilder::default()
+    }
+
+    /// See `cache_load_status` for the outcome
+    #[deprecated(note = "use FuelClientBuilder::cache_path instead")]
     pub fn with_cache(mut self, path: Option<PathBuf>) -> Self {
+        self.load_cache(path);
+        self
+    }
+
+    /// Sets the page size used for listing requests, larger pages mean fewer but heavier requests
+    #[deprecated(note = "use FuelClientBuilder::per_page instead")]
+    pub fn with_per_page(mut self, per_page: u32) -> Self {
+        self.per_page = per_page.max(1);
+        self
+    }
+
+    #[deprecated(note = "use FuelClientBuilder::offline instead")]
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Loads the cache at the given path, or the default path if None
+    pub(crate) fn load_cache(&mut self, path: Option<PathBuf>) {
         if let Some(path) = path.or_else(Self::default_cache_path) {
-            self.models = fs::read(&path)
-                .ok()
-                .and_then(|b| serde_json::de::from_slice::<Vec<FuelModel>>(&b).ok());
+            let read = ModelCache::load(&path);
+            self.load_status = match &read {
+                Ok(cache) => CacheLoadStatus::Loaded(cache.len()),
+                Err(_) if !path.exists() => CacheLoadStatus::NotFound,
+                Err(e) => CacheLoadStatus::Corrupt(e.clone()),
+            };
+            let models = read.ok().map(|cache| cache.models);
             self.cache_path = Some(path);
+            *self
+                .local_models
+                .write()
+                .unwrap_or_else(PoisonError::into_inner) = self.read_local_models();
+            *self.write_models() = match models {
+                Some(models) => Some(self.merge_local_models(models)),
+                None => Some(intern_assets(self.local_models())).filter(|local| !local.is_empty()),
+            }
+            .map(|models| ModelCache { models });
+            if let Some(path) = self.worlds_cache_path() {
+                *self.write_worlds() = fs::read(path)
+                    .ok()
+                    .and_then(|b| serde_json::de::from_slice::<Vec<FuelWorld>>(&b).ok())
+                    .map(intern_assets);
+            }
         }
-        self
     }
 
-    async fn build_cache(
+    /// Outcome of loading the cache file when the client was built, i.e. to tell users why their
+    /// cache is empty and rebuild it
+    pub fn cache_load_status(&self) -> &CacheLoadStatus {
+        &self.load_status
+    }
+
+    /// Replaces the cached models with an already loaded cache, merged with the local models. The
+    /// cache file is left as is.
+    pub fn set_model_cache(&self, cache: ModelCache) {
+        *self.write_models() = Some(ModelCache {
+            models: self.merge_local_models(cache.models),
+        });
+    }
+
+    /// Read access to the cached models, the lock should be released before refreshing the cache
+    /// from the same thread
+    pub fn models(&self) -> RwLockReadGuard<'_, Option<ModelCache>> {
+        self.models.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn write_models(&self) -> RwLockWriteGuard<'_, Option<ModelCache>> {
+        self.models.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Read access to the cached worlds, None until `update_world_cache` succeeds once
+    pub fn worlds(&self) -> RwLockReadGuard<'_, Option<Vec<FuelWorld>>> {
+        self.worlds.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn write_worlds(&self) -> RwLockWriteGuard<'_, Option<Vec<FuelWorld>>> {
+        self.worlds.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn lock_cache_file(&self) -> MutexGuard<'_, ()> {
+        self.cache_file_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn token(&self) -> Option<String> {
+        self.token
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    pub fn set_token(&self, token: Option<String>) {
+        *self.token.write().unwrap_or_else(PoisonError::into_inner) = token;
+    }
+
+    /// Builds a GET request to the given url, authenticated with the client token if present
+    pub(crate) fn get_request(&self, url: String) -> ehttp::Request {
+        let mut req = ehttp::Request::get(url);
+        if let Some(token) = self.token() {
+            req.headers
+                .headers
+                .push(("Private-token".to_owned(), token));
+        }
+        req
+    }
+
+    /// Same as `get_request`, bypassing the response cache. Used by listings, refreshes and
+    /// freshness checks, which must see the current state of the server.
+    pub(crate) fn uncached_get_request(&self, url: String) -> ehttp::Request {
+        let mut req = self.get_request(url);
+        req.headers.insert("Cache-Control", "no-cache");
+        req
+    }
+
+    fn transport(&self) -> http::Transport {
+        http::Transport {
+            backend: self.backend.clone(),
+            retry: self.retry.clone(),
+            redirect: self.redirect.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+
+    pub(crate) fn record_cache_hit(&self, hit: bool) {
+        if let Some(metrics) = &self.metrics {
+            if hit {
+                metrics.on_cache_hit();
+            } else {
+                metrics.on_cache_miss();
+            }
+        }
+    }
+
+    /// Sends a request, all network access goes through here
+    pub(crate) async fn fetch(&self, req: ehttp::Request) -> Result<ehttp::Response, FuelError> {
+        self.fetch_with_body(req, None).await
+    }
+
+    /// Same as `fetch` but the body is streamed to the file at `body_path` instead of being kept
+    /// in memory, for large downloads
+    pub(crate) async fn fetch_to_file(
         &self,
-        progress: Option<Sender<FuelModel>>,
-    ) -> Option<Vec<FuelModel>> {
-        let mut page = 1;
-        let mut models = Vec::new();
-        let models = loop {
-            let url = format!("{}models?page={page}&per_page=100", self.url);
-            let mut req = ehttp::Request::get(url.clone());
-            if let Some(token) = &self.token {
-                req.headers
-                    .headers
-                    .push(("Private-token".to_owned(), token.clone()));
-            }
-            let Some(res) = ehttp::fetch_async(req)
-                .await
-                .ok()
-                .and_then(|res| String::from_utf8(res.bytes).ok())
-            else {
-                break models;
+        req: ehttp::Request,
+        body_path: &Path,
+    ) -> Result<ehttp::Response, FuelError> {
+        self.fetch_with_body(req, Some(body_path)).await
+    }
+
+    async fn fetch_with_body(
+        &self,
+        mut req: ehttp::Request,
+        body_path: Option<&Path>,
+    ) -> Result<ehttp::Response, FuelError> {
+        if self.offline {
+            return Err(FuelError::Offline);
+        }
+        if let Some(auth) = &self.basic_auth {
+            req.headers.insert("Authorization", auth.header_value());
+        }
+        let res = self.send(req.clone(), body_path).await?;
+        let Some(refresh) = self.token_refresh.as_ref().filter(|_| res.status == 401) else {
+            return Ok(res);
+        };
+        // Retry once with a fresh token
+        let Some(token) = refresh() else {
+            return Ok(res);
+        };
+        self.set_token(Some(token.clone()));
+        req.headers
+            .headers
+            .retain(|(k, _)| !k.eq_ignore_ascii_case("Private-token"));
+        req.headers.insert("Private-token", token);
+        self.send(req, body_path).await
+    }
+
+    /// Sends a request through the middlewares. Middlewares don't see the body of streamed
+    /// responses.
+    async fn send(
+        &self,
+        mut req: ehttp::Request,
+        body_path: Option<&Path>,
+    ) -> Result<ehttp::Response, FuelError> {
+        for (idx, middleware) in self.middlewares.iter().enumerate() {
+            if let Some(mut res) = middleware.before_request(&mut req) {
+                // Short-circuited responses are only seen by the middlewares that ran before
+                for middleware in self.middlewares[..idx].iter().rev() {
+                    middleware.after_response(&req, &mut res);
+                }
+                if let Some(path) = body_path {
+                    fs::write(path, std::mem::take(&mut res.bytes))
+                        .map_err(|e| FuelError::Io(e.to_string()))?;
+                }
+                return Ok(res);
+            }
+        }
+        // Internal headers are only meant for the middlewares, never for the server
+        let mut sent = req.clone();
+        sent.headers
+            .headers
+            .retain(|(k, _)| !k.eq_ignore_ascii_case(REVALIDATION_HEADER));
+        let mut res = http::fetch(self.transport(), sent, body_path.map(Path::to_path_buf)).await?;
+        for middleware in self.middlewares.iter().rev() {
+            middleware.after_response(&req, &mut res);
+        }
+        Ok(res)
+    }
+
+    /// Crawls a listing endpoint (i.e. `models`, `{owner}/models` or `worlds`), returns None if
+    /// conditional `validators` were given and the server reported the first page as not modified.
+    /// If a `checkpoint` path is given completed pages are saved there and a previously
+    /// interrupted crawl of the same listing is resumed from its last completed page. Fails if any
+    /// page can't be fetched, after retries, rather than returning a truncated listing.
+    pub(crate) async fn crawl_listing<A: CacheEntry>(
+        &self,
+        listing: &str,
+        progress: Option<Sender<A>>,
+        validators: Option<&CacheValidators>,
+        checkpoint: Option<PathBuf>,
+    ) -> Result<Option<Crawl<A>>, FuelError> {
+        match self
+            .crawl_listing_partial(listing, progress, validators, checkpoint)
+            .await?
+        {
+            Some(Crawl {
+                failure: Some(e), ..
+            }) => Err(e),
+            crawl => Ok(crawl),
+        }
+    }
+
+    /// Same as `crawl_listing`, but a failure after the first page returns the pages fetched so
+    /// far with the error in `Crawl::failure`. The checkpoint is kept in that case.
+    pub(crate) async fn crawl_listing_partial<A: CacheEntry>(
+        &self,
+        listing: &str,
+        progress: Option<Sender<A>>,
+        validators: Option<&CacheValidators>,
+        checkpoint: Option<PathBuf>,
+    ) -> Result<Option<Crawl<A>>, FuelError> {
+        let resumed = checkpoint
+            .as_ref()
+            .and_then(|path| Checkpoint::<A>::load(path, listing, self.per_page));
+        let is_resumed = resumed.is_some();
+        let mut writer = None;
+        let (mut page, mut models, mut new_validators) = match resumed {
+            Some(resumed) => {
+                writer = checkpoint.clone().map(CheckpointWriter::resume);
+                if let Some(progress) = &progress {
+                    for model in &resumed.models {
+                        progress.send(model.clone()).ok();
+                    }
+                }
+                (resumed.last_page + 1, resumed.models, resumed.validators)
+            }
+            None => (1, Vec::new(), CacheValidators::default()),
+        };
+        let mut failure = None;
+        loop {
+            let url = format!(
+                "{}?page={page}&per_page={}",
+                self.endpoint(listing),
+                self.per_page
+            );
+            let mut req = self.uncached_get_request(url);
+            if let (1, Some(validators)) = (page, validators) {
+                if let Some(etag) = &validators.etag {
+                    req.headers.insert("If-None-Match", etag);
+                }
+                if let Some(last_modified) = &validators.last_modified {
+                    req.headers.insert("If-Modified-Since", last_modified);
+                }
+            }
+            let res = match self.fetch(req).await {
+                Ok(res) => res,
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
             };
-            let Ok(mut fetched_models) = serde_json::de::from_str::<Vec<FuelModel>>(&res) else {
-                break models;
+            if page == 1 {
+                if res.status == 304 {
+                    if let Some(writer) = writer {
+                        writer.finish();
+                    }
+                    return Ok(None);
+                }
+                new_validators = CacheValidators::from_headers(&res.headers);
+                if let Some(path) = checkpoint.clone() {
+                    writer = Some(CheckpointWriter::create(
+                        path,
+                        listing,
+                        self.per_page,
+                        &new_validators,
+                    ));
+                }
+            }
+            let pagination = Pagination::from_headers(&res.headers, self.per_page);
+            if !res.ok {
+                // Without pagination headers running past the last page is the only way to find
+                // it, a resumed crawl might also have stopped right after the last page
+                if res.status == 404
+                    && page > 1
+                    && pagination.last_page.is_none_or(|last| page > last)
+                {
+                    break;
+                }
+                failure = Some(FuelError::from_status(res.status));
+                break;
+            }
+            let mut fetched_models = match serde_json::de::from_slice::<Vec<A>>(&res.bytes) {
+                Ok(models) => models,
+                Err(e) => {
+                    failure = Some(FuelError::Parse(format!("page {page}: {e}")));
+                    break;
+                }
             };
+            if fetched_models.is_empty() {
+                if let Some(last) = pagination.last_page.filter(|last| page < *last) {
+                    failure = Some(FuelError::Parse(format!("page {page} of {last} is empty")));
+                    break;
+                }
+            }
             if let Some(progress) = &progress {
                 for model in &fetched_models {
                     progress.send(model.clone()).ok();
                 }
             }
+            if let Some(writer) = &mut writer {
+                writer.append_page(page, &fetched_models);
+            }
+            let fetched = fetched_models.len();
             models.append(&mut fetched_models);
+            if let Some(pages) = &self.page_progress {
+                pages
+                    .send(PageProgress {
+                        listing: listing.to_owned(),
+                        page,
+                        last_page: pagination.last_page,
+                        fetched: models.len(),
+                    })
+                    .ok();
+            }
+            if fetched == 0 || pagination.is_last(page, fetched) {
+                break;
+            }
             page += 1;
-        };
-        if !models.is_empty() {
-            Some(models)
-        } else {
-            None
         }
+        if let Some(e) = failure.take() {
+            if models.is_empty() {
+                return Err(e);
+            }
+            failure = Some(e);
+        } else if let Some(writer) = writer {
+            writer.finish();
+        }
+        if is_resumed {
+            // The catalog might have shifted between pages while the crawl was interrupted
+            models = models
+                .into_iter()
+                .rev()
+                .unique_by(|model| (model.owner().to_owned(), model.name().to_owned()))
+                .collect::<Vec<_>>();
+            models.reverse();
+        }
+        Ok(Some(Crawl {
+            models: intern_assets(models),
+            validators: new_validators,
+            failure,
+        }))
     }
 
-    fn default_cache_path() -> Option<PathBuf> {
-        let mut p = dirs::cache_dir()?;
-        p.push("open-robotics");
-        p.push("gz-fuel");
+    /// Fetches the full category tree from the server, including categories that no cached model
+    /// uses yet.
+    pub async fn fetch_categories(&self) -> Result<Vec<FuelCategory>, FuelError> {
+        let url = self.endpoint("categories");
+        let res = self.fetch(self.get_request(url)).await?;
+        if !res.ok {
+            return Err(FuelError::from_status(res.status));
+        }
+        serde_json::de::from_slice::<Vec<FuelCategory>>(&res.bytes)
+            .map_err(|e| FuelError::Parse(e.to_string()))
+    }
+
+    pub fn fetch_categories_blocking(&self) -> Result<Vec<FuelCategory>, FuelError> {
+        future::block_on(self.fetch_categories())
+    }
+
+    /// Checks that the server answers successfully and, if credentials are set, that the server
+    /// accepts them, see `check` for how the credentials are checked. Returns the API version and
+    /// any version information the server reports.
+    pub async fn ping(&self) -> Result<ServerInfo, FuelError> {
+        let res = self.fetch(self.get_request(self.url.clone())).await?;
+        if !res.ok {
+            return Err(FuelError::from_status(res.status));
+        }
+        let server_version = serde_json::de::from_slice::<serde_json::Value>(&res.bytes)
+            .ok()
+            .and_then(|v| v.get("version")?.as_str().map(String::from));
+        let check = self.check().await;
+        match (check.reachable, check.auth, check.error) {
+            (false, _, Some(e)) | (_, AuthStatus::Rejected | AuthStatus::Forbidden, Some(e)) => {
+                Err(e)
+            }
+            // Other error statuses don't tell whether the server accepts the credentials
+            (_, auth, _) => Ok(ServerInfo {
+                api_version: check.api_version.unwrap_or_default(),
+                server_version,
+                authenticated: auth == AuthStatus::Accepted,
+            }),
+        }
+    }
+
+    pub fn ping_blocking(&self) -> Result<ServerInfo, FuelError> {
+        future::block_on(self.ping())
+    }
+
+    pub(crate) fn default_cache_path() -> Option<PathBuf> {
+        model_cache::default_cache_path()
+    }
+
+    /// Cache path for non default servers, in a directory named after the server host
+    pub(crate) fn host_cache_path(url: &str) -> Option<PathBuf> {
+        let host = url.split("://").last()?.split('/').next()?;
+        let mut p = Self::default_cache_path()?;
+        p.set_file_name(host.replace(':', "_"));
         p.push("model_cache.json");
         Some(p)
     }
@@ -95,8 +565,15 @@ impl FuelClient {
     }
 
     /// If threshold is None, only update if cache is not found, otherwise update if cache is older
-    /// than threshold Duration
+    /// than threshold Duration. Always true if the cache file couldn't be loaded when the client
+    /// was built, whatever its age, and always false in offline mode.
     pub fn should_update_cache(&self, threshold: &Option<Duration>) -> bool {
+        if self.offline {
+            return false;
+        }
+        if matches!(self.load_status, CacheLoadStatus::Corrupt(_)) {
+            return true;
+        }
         let Some(last_updated) = self.last_updated() else {
             return true;
         };
@@ -108,31 +585,435 @@ impl FuelClient {
         }
     }
 
-    /// Returns Some if cache writing was successful, None otherwise
-    pub async fn update_cache(&mut self, write_to_disk: bool) -> Option<Vec<FuelModel>> {
+    /// Same as `should_update_cache` using the client refresh threshold
+    pub fn should_refresh(&self) -> bool {
+        self.should_update_cache(&self.refresh_threshold)
+    }
+
+    /// Server-aware variant of `should_update_cache`, independent of the cache file mtime.
+    /// Compares the most recently updated model on the server with the most recently updated
+    /// cached model, returns true if the server has newer data or there is no cache.
+    pub async fn should_update_cache_from_server(&self) -> Result<bool, FuelError> {
+        let Some(cached) = self
+            .models()
+            .as_ref()
+            .and_then(|models| models.iter().map(|model| model.updated_at.clone()).max())
+        else {
+            return Ok(true);
+        };
+        let newest = self.list_models(Some(Sort::most_recent()), 1).await?;
+        // Timestamps are RFC 3339 strings in UTC so they can be compared lexicographically
+        Ok(newest.iter().any(|model| model.updated_at > cached))
+    }
+
+    pub fn should_update_cache_from_server_blocking(&self) -> Result<bool, FuelError> {
+        future::block_on(self.should_update_cache_from_server())
+    }
+
+    /// Cached models that were updated on the server since they were cached, with their current
+    /// server metadata. Uploading a new version updates a model, so this also covers models whose
+    /// assets changed. The catalog is fetched newest first and only down to the most recently
+    /// updated model of the last full cache refresh written to disk, every model updated since
+    /// then is compared with its cached copy. Without such a refresh the whole catalog is fetched.
+    pub async fn outdated_models(&self) -> Result<Vec<FuelModel>, FuelError> {
+        let cached = {
+            let models = self.models();
+            let Some(models) = models.as_ref() else {
+                return Ok(Vec::new());
+            };
+            models
+                .iter()
+                .map(|model| {
+                    (
+                        (model.owner.to_lowercase(), model.name.to_lowercase()),
+                        model.updated_at.clone(),
+                    )
+                })
+                .collect::<HashMap<_, _>>()
+        };
+        if cached.is_empty() {
+            return Ok(Vec::new());
+        }
+        // Single model refreshes and local entries don't tell anything about the other models
+        let cutoff = self
+            .read_validators()
+            .and_then(|validators| validators.newest_updated_at);
+        let sort = Sort::most_recent().query();
+        let mut outdated = Vec::new();
+        let mut page = 1;
+        loop {
+            let url = format!(
+                "{}?page={page}&per_page={}&{sort}",
+                self.endpoint("models"),
+                self.per_page
+            );
+            let res = self.fetch(self.uncached_get_request(url)).await?;
+            if !res.ok {
+                if res.status == 404 && page > 1 {
+                    break;
+                }
+                return Err(FuelError::from_status(res.status));
+            }
+            let fetched = serde_json::de::from_slice::<Vec<FuelModel>>(&res.bytes)
+                .map_err(|e| FuelError::Parse(e.to_string()))?;
+            let is_last = fetched.len() < self.per_page as usize;
+            let mut reached_cached = false;
+            for model in fetched {
+                // Timestamps are RFC 3339 strings in UTC so they can be compared lexicographically
+                if cutoff
+                    .as_ref()
+                    .is_some_and(|cutoff| model.updated_at <= *cutoff)
+                {
+                    reached_cached = true;
+                    break;
+                }
+                let key = (model.owner.to_lowercase(), model.name.to_lowercase());
+                if cached
+                    .get(&key)
+                    .is_some_and(|updated_at| *updated_at < model.updated_at)
+                {
+                    outdated.push(model);
+                }
+            }
+            if reached_cached || is_last {
+                break;
+            }
+            page += 1;
+        }
+        Ok(intern_assets(outdated))
+    }
+
+    pub fn outdated_models_blocking(&self) -> Result<Vec<FuelModel>, FuelError> {
+        future::block_on(self.outdated_models())
+    }
+
+    /// Returns the updated models, or an error if the server could not be fully crawled or the
+    /// cache could not be written
+    pub async fn update_cache(&self, write_to_disk: bool) -> Result<Vec<FuelModel>, FuelError> {
         self.update_cache_with_progress(write_to_disk, None).await
     }
 
+    /// Conditional headers are sent based on the previous refresh, if the server reports that
+    /// nothing changed the current models are returned without crawling the whole catalog.
+    /// Previously cached models missing from the catalog are recorded as `tombstones` when
+    /// writing to disk. If the crawl fails midway the previous cache is kept, unless
+    /// `commit_partial_crawls` is set, and an interrupted crawl written to disk is resumed by the
+    /// next refresh.
     pub async fn update_cache_with_progress(
-        &mut self,
+        &self,
         write_to_disk: bool,
         progress: Option<Sender<FuelModel>>,
-    ) -> Option<Vec<FuelModel>> {
-        if let Some(models) = self.build_cache(progress).await {
-            self.models = Some(models);
+    ) -> Result<Vec<FuelModel>, FuelError> {
+        let progress = progress.or_else(|| self.progress.clone());
+        let validators = self
+            .models()
+            .is_some()
+            .then(|| self.read_validators())
+            .flatten();
+        let checkpoint = write_to_disk
+            .then(|| self.cache_sidecar_path("checkpoint.jsonl"))
+            .flatten();
+        let Some(mut crawl) = self
+            .crawl_listing_partial("models", progress, validators.as_ref(), checkpoint)
+            .await?
+        else {
+            self.record_cache_hit(true);
             if write_to_disk {
-                let path = self.cache_path.clone().or_else(Self::default_cache_path)?;
-                fs::create_dir_all(path.parent()?).ok()?;
-                let bytes = serde_json::ser::to_string_pretty(&self.models).ok()?;
-                fs::write(path, bytes).ok()?;
+                // Mark the cache as fresh for should_update_cache, best effort
+                if let Some(path) = &self.cache_path {
+                    let _lock = self.lock_cache_file();
+                    fs::File::options()
+                        .append(true)
+                        .open(path)
+                        .and_then(|f| f.set_modified(SystemTime::now()))
+                        .ok();
+                }
             }
-            self.models.clone()
-        } else {
-            None
+            return Ok(self
+                .models()
+                .clone()
+                .map(|cache| cache.models)
+                .unwrap_or_default());
+        };
+        self.record_cache_hit(false);
+        if let Some(e) = crawl.failure.take() {
+            return self.commit_partial_crawl(&crawl.models, e, write_to_disk);
+        }
+        let merged = self.merge_local_models(crawl.models.clone());
+        let previous = self.write_models().replace(ModelCache {
+            models: merged.clone(),
+        });
+        if write_to_disk {
+            self.write_cache()?;
+            if let Some(previous) = previous {
+                self.record_tombstones(&previous, &merged)?;
+            }
+            crawl.validators.newest_updated_at = crawl
+                .models
+                .iter()
+                .map(|model| &model.updated_at)
+                .max()
+                .cloned();
+            self.write_validators(&crawl.validators)?;
+            if self.track_stats {
+                self.record_stats(&crawl.models)?;
+            }
+            if self.keep_snapshots > 0 {
+                self.take_snapshot()?;
+            }
+        }
+        Ok(crawl.models)
+    }
+
+    /// Refreshes only the models of the given owner through the `{owner}/models` endpoint, the
+    /// cached models of that owner are replaced by the fetched ones while all other cached models
+    /// are kept. Models of the owner missing from the listing are recorded as `tombstones` when
+    /// writing to disk.
+    pub async fn update_cache_for_owner(
+        &self,
+        owner: &str,
+        write_to_disk: bool,
+    ) -> Result<Vec<FuelModel>, FuelError> {
+        let listing = format!("{}/models", uri::encode_segment(owner));
+        let Some(mut crawl) = self
+            .crawl_listing_partial(&listing, self.progress.clone(), None, None)
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+        if let Some(e) = crawl.failure.take() {
+            return self.commit_partial_crawl(&crawl.models, e, write_to_disk);
+        }
+        let previous = {
+            let mut models = self.write_models();
+            let models = &mut models.get_or_insert_with(ModelCache::default).models;
+            let (previous, others) = std::mem::take(models)
+                .into_iter()
+                .partition::<Vec<_>, _>(|model| model.owner.eq_ignore_ascii_case(owner));
+            *models = others;
+            models.extend(crawl.models.iter().cloned());
+            // Also shares strings between the fetched and the previously cached models
+            *models = self.merge_local_models(std::mem::take(models));
+            previous
+        };
+        if write_to_disk {
+            self.write_cache()?;
+            // Local entries of the owner are merged back in and mustn't be reported as removed
+            let local = self.local_models();
+            let after = crawl
+                .models
+                .iter()
+                .chain(&local)
+                .cloned()
+                .collect::<Vec<_>>();
+            self.record_tombstones(&previous, &after)?;
+        }
+        Ok(crawl.models)
+    }
+
+    /// Handles a crawl interrupted by `failure`, the cache is left untouched unless
+    /// `commit_partial_crawls` is set. The failure is returned either way.
+    fn commit_partial_crawl(
+        &self,
+        fetched: &[FuelModel],
+        failure: FuelError,
+        write_to_disk: bool,
+    ) -> Result<Vec<FuelModel>, FuelError> {
+        if !self.commit_partial_crawls {
+            return Err(failure);
+        }
+        {
+            let mut models = self.write_models();
+            let models = &mut models.get_or_insert_with(ModelCache::default).models;
+            let index = models
+                .iter()
+                .enumerate()
+                .map(|(i, model)| ((model.owner.to_lowercase(), model.name.to_lowercase()), i))
+                .collect::<HashMap<_, _>>();
+            for model in fetched {
+                let key = (model.owner.to_lowercase(), model.name.to_lowercase());
+                match index.get(&key) {
+                    Some(&i) => models[i] = model.clone(),
+                    None => models.push(model.clone()),
+                }
+            }
+            *models = intern_assets(std::mem::take(models));
+        }
+        if write_to_disk {
+            self.write_cache()?;
+        }
+        Err(failure)
+    }
+
+    /// Re-fetches the metadata of a single model and replaces its cache entry, the model is
+    /// added to the cache if it wasn't cached yet
+    pub async fn refresh_model(
+        &self,
+        owner: &str,
+        name: &str,
+        write_to_disk: bool,
+    ) -> Result<FuelModel, FuelError> {
+        let model = self.fetch_model(owner, name).await?;
+        {
+            let mut models = self.write_models();
+            let models = &mut models.get_or_insert_with(ModelCache::default).models;
+            match models.iter_mut().find(|cached| {
+                cached.owner.eq_ignore_ascii_case(&model.owner)
+                    && cached.name.eq_ignore_ascii_case(&model.name)
+            }) {
+                Some(cached) => *cached = model.clone(),
+                None => models.push(model.clone()),
+            }
+            *models = intern_assets(std::mem::take(models));
+        }
+        if write_to_disk {
+            self.write_cache()?;
+        }
+        Ok(model)
+    }
+
+    /// Metadata of a single model, straight from the server
+    pub(crate) async fn fetch_model(
+        &self,
+        owner: &str,
+        name: &str,
+    ) -> Result<FuelModel, FuelError> {
+        let url = self.endpoint(&uri::asset_path(AssetKind::Model, owner, name));
+        let res = self.fetch(self.uncached_get_request(url)).await?;
+        if !res.ok {
+            return Err(self.asset_error(AssetKind::Model, owner, name, res.status));
         }
+        serde_json::de::from_slice::<FuelModel>(&res.bytes)
+            .map_err(|e| FuelError::Parse(e.to_string()))
     }
 
-    pub fn update_cache_blocking(&mut self, write_to_disk: bool) -> Option<Vec<FuelModel>> {
+    pub fn refresh_model_blocking(
+        &self,
+        owner: &str,
+        name: &str,
+        write_to_disk: bool,
+    ) -> Result<FuelModel, FuelError> {
+        future::block_on(self.refresh_model(owner, name, write_to_disk))
+    }
+
+    pub fn update_cache_for_owner_blocking(
+        &self,
+        owner: &str,
+        write_to_disk: bool,
+    ) -> Result<Vec<FuelModel>, FuelError> {
+        future::block_on(self.update_cache_for_owner(owner, write_to_disk))
+    }
+
+    pub(crate) fn write_cache(&self) -> Result<(), FuelError> {
+        let path = self
+            .cache_path
+            .clone()
+            .or_else(Self::default_cache_path)
+            .ok_or_else(|| FuelError::Io("no cache path available".into()))?;
+        let bytes = self.serialize_cache(&self.models().as_deref())?;
+        self.write_cache_file(&path, bytes)
+    }
+
+    /// Serializes the content of a models or worlds cache file, see `pretty_cache`
+    pub(crate) fn serialize_cache<T: Serialize + ?Sized>(
+        &self,
+        value: &T,
+    ) -> Result<String, FuelError> {
+        let res = match self.pretty_cache {
+            true => serde_json::ser::to_string_pretty(value),
+            false => serde_json::ser::to_string(value),
+        };
+        res.map_err(|e| FuelError::Io(e.to_string()))
+    }
+
+    /// Writes one of the cache files with the cache file lock held, see `write_cache_file`
+    pub(crate) fn write_cache_file(&self, path: &Path, bytes: String) -> Result<(), FuelError> {
+        let _lock = self.lock_cache_file();
+        model_cache::write_cache_file(path, bytes.as_bytes())
+    }
+
+    /// Applies `update` to the JSON file at `path` and writes it back pretty printed if it
+    /// changed, with the cache file lock held from the read to the write so updates from clones
+    /// aren't lost. A missing file is read as the default value, a file that can't be parsed
+    /// fails with `FuelError::Parse` and is left as is.
+    pub(crate) fn update_json_file<T, R>(
+        &self,
+        path: &Path,
+        update: impl FnOnce(&mut T) -> R,
+    ) -> Result<R, FuelError>
+    where
+        T: Serialize + DeserializeOwned + Default + PartialEq + Clone,
+    {
+        let _lock = self.lock_cache_file();
+        let previous = read_json_file::<T>(path)?.unwrap_or_default();
+        let mut value = previous.clone();
+        let res = update(&mut value);
+        if value != previous {
+            let bytes = serde_json::ser::to_string_pretty(&value)
+                .map_err(|e| FuelError::Io(e.to_string()))?;
+            model_cache::write_cache_file(path, bytes.as_bytes())?;
+        }
+        Ok(res)
+    }
+
+    /// Reads a JSON file stored next to the cache file, see `cache_sidecar_path`. The default
+    /// value if there is no such file.
+    pub(crate) fn read_sidecar<T: DeserializeOwned + Default>(
+        &self,
+        extension: &str,
+    ) -> Result<T, FuelError> {
+        let Some(path) = self.cache_sidecar_path(extension) else {
+            return Ok(T::default());
+        };
+        Ok(read_json_file(&path)?.unwrap_or_default())
+    }
+
+    /// `update_json_file` on a file stored next to the cache file, see `cache_sidecar_path`
+    pub(crate) fn update_sidecar<T, R>(
+        &self,
+        extension: &str,
+        update: impl FnOnce(&mut T) -> R,
+    ) -> Result<R, FuelError>
+    where
+        T: Serialize + DeserializeOwned + Default + PartialEq + Clone,
+    {
+        let path = self
+            .cache_sidecar_path(extension)
+            .ok_or_else(|| FuelError::Io("no cache path available".into()))?;
+        self.update_json_file(&path, update)
+    }
+
+    /// Path of a file stored next to the cache file, i.e. `model_cache.validators.json` for the
+    /// `validators.json` extension
+    pub(crate) fn cache_sidecar_path(&self, extension: &str) -> Option<PathBuf> {
+        let path = self.cache_path.clone().or_else(Self::default_cache_path)?;
+        Some(path.with_extension(extension))
+    }
+
+    pub(crate) fn worlds_cache_path(&self) -> Option<PathBuf> {
+        self.cache_sidecar_path("worlds.json")
+    }
+
+    fn validators_path(&self) -> Option<PathBuf> {
+        self.cache_sidecar_path("validators.json")
+    }
+
+    fn read_validators(&self) -> Option<CacheValidators> {
+        let bytes = fs::read(self.validators_path()?).ok()?;
+        serde_json::de::from_slice(&bytes).ok()
+    }
+
+    fn write_validators(&self, validators: &CacheValidators) -> Result<(), FuelError> {
+        let Some(path) = self.validators_path() else {
+            return Ok(());
+        };
+        let bytes =
+            serde_json::ser::to_string(validators).map_err(|e| FuelError::Io(e.to_string()))?;
+        let _lock = self.lock_cache_file();
+        fs::write(path, bytes).map_err(|e| FuelError::Io(e.to_string()))
+    }
+
+    pub fn update_cache_blocking(&self, write_to_disk: bool) -> Result<Vec<FuelModel>, FuelError> {
         future::block_on(self.update_cache(write_to_disk))
     }
 
@@ -142,27 +1023,21 @@ impl FuelClient {
         models: Option<&Vec<FuelModel>>,
         owner: &str,
     ) -> Option<Vec<FuelModel>> {
-        let models = models.or(self.models.as_ref())?;
-        Some(
-            models
-                .iter()
-                .filter(|model| model.owner == owner)
-                .cloned()
-                .collect::<Vec<_>>(),
-        )
+        let cached = self.models();
+        let models = models.map(Vec::as_slice).or(cached.as_deref())?;
+        Some(asset::assets_by_owner(models, owner))
+    }
+
+    /// Finds the cached model a Fuel URI refers to, i.e.
+    /// `https://fuel.gazebosim.org/1.0/OpenRobotics/models/Table`. URIs referencing the legacy
+    /// `fuel.ignitionrobotics.org` host are treated as aliases of `fuel.gazebosim.org`.
+    pub fn model_from_uri(&self, uri: &str) -> Option<FuelModel> {
+        let (owner, name, _) = uri::parse_model_uri(&self.url, uri)?;
+        self.models().as_ref()?.find(&owner, &name).cloned()
     }
 
     pub fn get_owners(&self) -> Option<Vec<String>> {
-        let models = self.models.as_ref()?;
-        Some(
-            models
-                .iter()
-                .unique_by(|model| &model.owner)
-                .clone()
-                .map(|model| model.owner.clone())
-                .sorted_by(|a, b| a.to_lowercase().cmp(&b.to_lowercase()))
-                .collect::<Vec<_>>(),
-        )
+        Some(self.models().as_ref()?.owners())
     }
 
     pub fn models_by_private(
@@ -170,27 +1045,77 @@ impl FuelClient {
         models: Option<&Vec<FuelModel>>,
         private: bool,
     ) -> Option<Vec<FuelModel>> {
-        let models = models.or(self.models.as_ref())?;
-        Some(
-            models
-                .iter()
-                .filter(|model| model.private == private)
-                .cloned()
-                .collect::<Vec<_>>(),
-        )
+        let cached = self.models();
+        let models = models.map(Vec::as_slice).or(cached.as_deref())?;
+        Some(asset::assets_by_private(models, private))
     }
 
     pub fn get_tags(&self) -> Option<Vec<String>> {
-        let models = self.models.as_ref()?;
-        Some(
-            models
-                .iter()
-                .flat_map(|model| &model.tags)
-                .unique()
-                .cloned()
-                .sorted_by(|a, b| a.to_lowercase().cmp(&b.to_lowercase()))
-                .collect::<Vec<_>>(),
-        )
+        Some(self.models().as_ref()?.tags())
+    }
+
+    /// Random subset of up to `n` cached models matching `filter`, every matching model has the
+    /// same chance of being picked. Returns None if there is no cache.
+    pub fn sample_models(
+        &self,
+        n: usize,
+        filter: Option<&dyn Fn(&FuelModel) -> bool>,
+    ) -> Option<Vec<FuelModel>> {
+        self.sample_models_with_rng(n, filter, &mut fastrand::Rng::new())
+    }
+
+    /// Same as `sample_models` with a seed, the same seed and cache always give the same sample
+    pub fn sample_models_seeded(
+        &self,
+        n: usize,
+        filter: Option<&dyn Fn(&FuelModel) -> bool>,
+        seed: u64,
+    ) -> Option<Vec<FuelModel>> {
+        self.sample_models_with_rng(n, filter, &mut fastrand::Rng::with_seed(seed))
+    }
+
+    fn sample_models_with_rng(
+        &self,
+        n: usize,
+        filter: Option<&dyn Fn(&FuelModel) -> bool>,
+        rng: &mut fastrand::Rng,
+    ) -> Option<Vec<FuelModel>> {
+        let models = self.models();
+        let models = models.as_ref()?;
+        let matching = models
+            .iter()
+            .filter(|model| filter.is_none_or(|f| f(model)));
+        // Reservoir sampling, the matching models don't need to be collected first. `n` can be
+        // anything, the sample is never larger than the cache.
+        let mut sample = Vec::with_capacity(n.min(models.len()));
+        for (idx, model) in matching.enumerate() {
+            if idx < n {
+                sample.push(model.clone());
+            } else {
+                let slot = rng.usize(..=idx);
+                if slot < n {
+                    sample[slot] = model.clone();
+                }
+            }
+        }
+        rng.shuffle(&mut sample);
+        Some(sample)
+    }
+
+    /// Tags starting with `prefix`, compared case insensitively, ranked by the number of models
+    /// using them. Ties are sorted alphabetically, case variants of a tag are returned once.
+    pub fn complete_tag(&self, prefix: &str) -> Option<Vec<String>> {
+        let models = self.models();
+        let tags = models.as_ref()?.iter().flat_map(|model| &model.tags);
+        Some(rank_completions(tags.map(|tag| &**tag), prefix))
+    }
+
+    /// Owners starting with `prefix`, compared case insensitively, ranked by the number of models
+    /// they own. Ties are sorted alphabetically, case variants of an owner are returned once.
+    pub fn complete_owner(&self, prefix: &str) -> Option<Vec<String>> {
+        let models = self.models();
+        let owners = models.as_ref()?.iter().map(|model| &*model.owner);
+        Some(rank_completions(owners, prefix))
     }
 
     pub fn models_by_tag(
@@ -198,41 +1123,362 @@ impl FuelClient {
         models: Option<&Vec<FuelModel>>,
         tag: &str,
     ) -> Option<Vec<FuelModel>> {
-        let models = models.or(self.models.as_ref())?;
-        Some(
-            models
-                .iter()
-                .filter(|model| model.tags.contains(&tag.to_owned()))
-                .cloned()
-                .collect::<Vec<_>>(),
-        )
+        let cached = self.models();
+        let models = models.map(Vec::as_slice).or(cached.as_deref())?;
+        Some(asset::assets_by_tag(models, tag))
+    }
+
+    /// Models with a metadata entry matching both `key` and `value`
+    pub fn models_by_metadata(
+        &self,
+        models: Option<&Vec<FuelModel>>,
+        key: &str,
+        value: &str,
+    ) -> Option<Vec<FuelModel>> {
+        let cached = self.models();
+        let models = models.map(Vec::as_slice).or(cached.as_deref())?;
+        Some(model_cache::models_by_metadata(models, key, value))
+    }
+}
+
+/// Reads a JSON file stored alongside the cache, None if it doesn't exist
+pub(crate) fn read_json_file<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, FuelError> {
+    match fs::read(path) {
+        Ok(bytes) => serde_json::de::from_slice(&bytes)
+            .map(Some)
+            .map_err(|e| FuelError::Parse(format!("{}: {e}", path.display()))),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(FuelError::Io(e.to_string())),
     }
 }
 
-// TODO(luca) decide which fields we should skip to save on memory footprint
+/// Values starting with `prefix`, most frequent first. Values that only differ by case are
+/// counted together, the same way `find_asset` compares names, and returned with their most
+/// frequent spelling.
+fn rank_completions<'a>(values: impl Iterator<Item = &'a str>, prefix: &str) -> Vec<String> {
+    let mut spellings = HashMap::<String, HashMap<&str, usize>>::new();
+    let matching = values.filter(|value| {
+        value
+            .get(..prefix.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+    });
+    for value in matching {
+        *spellings
+            .entry(value.to_ascii_lowercase())
+            .or_default()
+            .entry(value)
+            .or_default() += 1;
+    }
+    spellings
+        .into_iter()
+        .map(|(key, spellings)| {
+            let count = spellings.values().sum::<usize>();
+            // Ties between spellings are broken alphabetically so the result is deterministic
+            let spelling = spellings
+                .into_iter()
+                .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then_with(|| b.cmp(a)))
+                .map(|(spelling, _)| spelling.to_owned())
+                .unwrap_or_default();
+            (key, spelling, count)
+        })
+        .sorted_by(|(a, _, a_count), (b, _, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)))
+        .map(|(_, spelling, _)| spelling)
+        .collect()
+}
+
+/// Result of a full crawl of a listing
+pub(crate) struct Crawl<A> {
+    pub(crate) models: Vec<A>,
+    pub(crate) validators: CacheValidators,
+    /// Error that interrupted the crawl, `models` only holds the pages fetched before it
+    pub(crate) failure: Option<FuelError>,
+}
+
+/// Sent every time a page of a listing crawl was fetched
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageProgress {
+    /// Crawled listing, i.e. `models` or `{owner}/models`
+    pub listing: String,
+    pub page: u32,
+    /// None if the server doesn't send pagination headers
+    pub last_page: Option<u32>,
+    /// Number of entries fetched so far, including the ones of a resumed crawl
+    pub fetched: usize,
+}
+
+/// Validators of the first page of the models listing, sent on the next refresh to let the server
+/// answer with 304 Not Modified
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub(crate) struct CacheValidators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// Most recent `updatedAt` of the crawled models, models updated after it on the server
+    /// changed since the crawl. Only set for completed full crawls.
+    #[serde(default)]
+    newest_updated_at: Option<String>,
+}
+
+impl CacheValidators {
+    fn from_headers(headers: &ehttp::Headers) -> Self {
+        Self {
+            etag: headers.get("etag").map(String::from),
+            last_modified: headers.get("last-modified").map(String::from),
+            newest_updated_at: None,
+        }
+    }
+}
+
+/// Pagination information sent by the server alongside a listing page
+#[derive(Debug, Default)]
+struct Pagination {
+    /// Whether the Link header was present
+    has_link: bool,
+    /// Whether the Link header contains a `rel="next"` entry
+    has_next: bool,
+    last_page: Option<u32>,
+    per_page: u32,
+}
+
+impl Pagination {
+    fn from_headers(headers: &ehttp::Headers, per_page: u32) -> Self {
+        let mut pagination = Pagination {
+            per_page,
+            ..Default::default()
+        };
+        if let Some(link) = headers.get("link") {
+            pagination.has_link = true;
+            for entry in link.split(',') {
+                let Some((target, rel)) = entry.split_once(';') else {
+                    continue;
+                };
+                if rel.contains("rel=\"next\"") {
+                    pagination.has_next = true;
+                } else if rel.contains("rel=\"last\"") {
+                    pagination.last_page = target
+                        .trim()
+                        .trim_matches(|c| c == '<' || c == '>')
+                        .split(['?', '&'])
+                        .find_map(|param| param.strip_prefix("page="))
+                        .and_then(|page| page.parse().ok());
+                }
+            }
+        }
+        if pagination.last_page.is_none() {
+            pagination.last_page = headers
+                .get("x-total-count")
+                .and_then(|total| total.trim().parse::<u32>().ok())
+                .map(|total| total.div_ceil(per_page.max(1)));
+        }
+        pagination
+    }
+
+    /// Whether `page` is the last one. Without pagination headers a page that isn't full is
+    /// assumed to be the last one.
+    fn is_last(&self, page: u32, fetched: usize) -> bool {
+        if let Some(last_page) = self.last_page {
+            page >= last_page
+        } else if self.has_link {
+            !self.has_next
+        } else {
+            fetched < self.per_page as usize
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerInfo {
+    /// API version of the client url, i.e. "1.0"
+    pub api_version: String,
+    /// Version reported by the server, if it reports one
+    pub server_version: Option<String>,
+    /// Whether credentials were sent and the server accepted them
+    pub authenticated: bool,
+}
+
+/// A category as defined by the server, categories can be nested through `parent_id`.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
-pub struct FuelModel {
-    #[serde(rename = "createdAt")]
-    pub created_at: String,
-    #[serde(rename = "updatedAt")]
-    pub updated_at: String,
+pub struct FuelCategory {
     pub name: String,
-    pub owner: String,
-    pub description: String,
-    pub likes: u32,
-    pub downloads: u32,
-    pub filesize: usize,
-    pub upload_date: String,
-    pub modify_date: String,
-    pub license_id: u32,
-    pub license_name: String,
-    pub license_url: String,
-    pub license_image: String,
-    pub permission: u32,
-    pub url_name: String,
-    pub private: bool,
     #[serde(default)]
-    pub tags: Vec<String>,
+    pub slug: String,
     #[serde(default)]
-    pub categories: Vec<String>,
+    pub parent_id: Option<u32>,
+    #[serde(default)]
+    pub children: Vec<FuelCategory>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, response, TempDir};
+
+    fn ping(token: Option<&str>, login_status: u16) -> Result<ServerInfo, FuelError> {
+        let dir = TempDir::new();
+        let mut builder = testing::builder(&dir, move |req| {
+            Ok(match req.url.ends_with("/login") {
+                true => response(&req.url, login_status, "{}"),
+                false => response(&req.url, 200, r#"{"version": "0.9"}"#),
+            })
+        });
+        if let Some(token) = token {
+            builder = builder.token(token);
+        }
+        builder.build().ping_blocking()
+    }
+
+    #[test]
+    fn fetch_categories() {
+        let dir = TempDir::new();
+        let client = testing::builder(&dir, |req| {
+            Ok(match req.url.ends_with("/1.0/categories") {
+                true => response(
+                    &req.url,
+                    200,
+                    r#"[{"name": "Furniture", "slug": "furniture",
+                        "children": [{"name": "Chairs", "parent_id": 1}]}]"#,
+                ),
+                false => response(&req.url, 404, ""),
+            })
+        })
+        .build();
+        let categories = client.fetch_categories_blocking().unwrap();
+        assert_eq!(categories.len(), 1);
+        assert_eq!(categories[0].slug, "furniture");
+        let chairs = &categories[0].children[0];
+        assert_eq!(
+            (chairs.name.as_str(), chairs.parent_id),
+            ("Chairs", Some(1))
+        );
+        assert!(chairs.slug.is_empty() && chairs.children.is_empty());
+
+        let client = testing::builder(&dir, |req| Ok(response(&req.url, 500, ""))).build();
+        assert_eq!(
+            client.fetch_categories_blocking(),
+            Err(FuelError::from_status(500))
+        );
+    }
+
+    #[test]
+    fn ping_reports_accepted_credentials() {
+        let info = ping(Some("token"), 200).unwrap();
+        assert!(info.authenticated);
+        assert_eq!(info.api_version, "1.0");
+        assert_eq!(info.server_version.as_deref(), Some("0.9"));
+    }
+
+    #[test]
+    fn ping_rejects_invalid_credentials() {
+        assert_eq!(ping(Some("token"), 401), Err(FuelError::Unauthorized));
+        assert_eq!(ping(Some("token"), 403), Err(FuelError::Forbidden));
+        // The server doesn't tell whether it accepts the token
+        assert!(!ping(Some("token"), 404).unwrap().authenticated);
+    }
+
+    #[test]
+    fn ping_without_credentials_is_anonymous() {
+        // Refusing anonymous clients, or failing, doesn't say anything about credentials
+        assert!(!ping(None, 500).unwrap().authenticated);
+    }
+
+    #[test]
+    fn rank_completions_merges_case_variants() {
+        let values = ["Table", "table", "TABLE", "tab", "Tab", "chair", "taco"];
+        assert_eq!(
+            rank_completions(values.into_iter(), "TA"),
+            ["TABLE", "Tab", "taco"]
+        );
+        assert_eq!(
+            rank_completions(values.into_iter(), "é"),
+            Vec::<String>::new()
+        );
+        assert_eq!(rank_completions(["Rémy"].into_iter(), "R\u{e9}"), ["Rémy"]);
+    }
+
+    #[test]
+    fn sample_models_larger_than_cache() {
+        let dir = TempDir::new();
+        let client = testing::builder(&dir, |req| Ok(response(&req.url, 404, ""))).build();
+        client.set_model_cache(
+            vec![testing::model("o", "a", ""), testing::model("o", "b", "")].into(),
+        );
+        let sample = client.sample_models_seeded(usize::MAX, None, 1).unwrap();
+        assert_eq!(sample.len(), 2);
+        let sample = client
+            .sample_models_seeded(1, Some(&|m| m.name == "b"), 1)
+            .unwrap();
+        assert_eq!(sample, [testing::model("o", "b", "")]);
+    }
+
+    #[test]
+    fn outdated_models_after_single_refresh() {
+        let dir = TempDir::new();
+        let catalog = Arc::new(Mutex::new(vec![
+            testing::model("o", "b", "2024-01-05"),
+            testing::model("o", "a", "2024-01-01"),
+        ]));
+        let server = catalog.clone();
+        let client = testing::builder(&dir, move |req| {
+            Ok(testing::listing(req, &server.lock().unwrap()))
+        })
+        .build();
+        client.update_cache_blocking(true).unwrap();
+        // `a` is updated on the server, then `b` is updated and refreshed alone
+        let updated = vec![
+            testing::model("o", "b", "2024-01-10"),
+            testing::model("o", "a", "2024-01-07"),
+        ];
+        *catalog.lock().unwrap() = updated.clone();
+        client.set_model_cache(
+            vec![updated[0].clone(), testing::model("o", "a", "2024-01-01")].into(),
+        );
+        assert_eq!(
+            client.outdated_models_blocking().unwrap(),
+            [updated[1].clone()]
+        );
+    }
+
+    #[test]
+    fn outdated_models_without_full_refresh() {
+        let dir = TempDir::new();
+        let catalog = vec![
+            testing::model("o", "b", "2024-01-05"),
+            testing::model("o", "a", "2024-01-02"),
+        ];
+        let client = testing::builder(&dir, move |req| Ok(testing::listing(req, &catalog))).build();
+        // A local entry far in the future doesn't hide the older changes
+        client.set_model_cache(
+            vec![
+                testing::model("local", "x", "9999-01-01"),
+                testing::model("o", "a", "2024-01-01"),
+            ]
+            .into(),
+        );
+        let outdated = client.outdated_models_blocking().unwrap();
+        assert_eq!(outdated, [testing::model("o", "a", "2024-01-02")]);
+    }
+
+    #[test]
+    fn ping_fails_on_error_status() {
+        let dir = TempDir::new();
+        let client = testing::builder(&dir, |req| Ok(response(&req.url, 404, ""))).build();
+        assert_eq!(client.ping_blocking(), Err(FuelError::Http(404)));
+    }
+
+    #[test]
+    fn corrupt_caches_are_updated() {
+        let dir = TempDir::new();
+        fs::write(dir.0.join("model_cache.json"), "{ not json").unwrap();
+        let client = testing::builder(&dir, |req| Ok(response(&req.url, 200, "[]"))).build();
+        assert!(matches!(
+            client.cache_load_status(),
+            CacheLoadStatus::Corrupt(_)
+        ));
+        // The file was just written, but it's unreadable
+        assert!(client.should_update_cache(&Some(Duration::from_secs(3600))));
+        assert!(client.should_update_cache(&None));
+        let offline = testing::builder(&dir, |req| Ok(response(&req.url, 200, "[]")))
+            .offline(true)
+            .build();
+        assert!(!offline.should_update_cache(&None));
+    }
 }
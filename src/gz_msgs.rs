@@ -0,0 +1,305 @@
+//! Conversions between Fuel assets and the `gz.msgs.FuelMetadata` protobuf message of gz-msgs,
+//! built with the `gz-msgs` feature. The message types mirror `fuel_metadata.proto` and
+//! `version.proto`, encode them with `prost::Message`.
+
+use prost::{Message, Oneof};
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{uri, FuelError, FuelModel, FuelWorld, ModelMetadata};
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Version {
+    #[prost(int32, tag = "1")]
+    pub major: i32,
+    #[prost(int32, tag = "2")]
+    pub minor: i32,
+    #[prost(int32, tag = "3")]
+    pub patch: i32,
+    #[prost(string, tag = "4")]
+    pub prerelease: String,
+    #[prost(string, tag = "5")]
+    pub build: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Contact {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(string, tag = "2")]
+    pub email: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Legal {
+    #[prost(string, tag = "1")]
+    pub copyright: String,
+    #[prost(string, tag = "2")]
+    pub license: String,
+}
+
+/// Main file of a model or world, i.e. `model.sdf`
+#[derive(Clone, PartialEq, Message)]
+pub struct ResourceFile {
+    #[prost(message, optional, tag = "1")]
+    pub file_format: Option<Version>,
+    #[prost(string, tag = "2")]
+    pub file: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Dependency {
+    #[prost(string, tag = "1")]
+    pub uri: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Compatibility {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(message, optional, tag = "2")]
+    pub version: Option<Version>,
+}
+
+#[derive(Clone, PartialEq, Oneof)]
+pub enum ResourceType {
+    #[prost(message, tag = "1")]
+    Model(ResourceFile),
+    #[prost(message, tag = "2")]
+    World(ResourceFile),
+}
+
+/// `gz.msgs.FuelMetadata`
+#[derive(Clone, PartialEq, Message)]
+pub struct FuelMetadata {
+    #[prost(oneof = "ResourceType", tags = "1, 2")]
+    pub resource_type: Option<ResourceType>,
+    #[prost(string, tag = "3")]
+    pub name: String,
+    #[prost(string, tag = "4")]
+    pub description: String,
+    #[prost(int32, tag = "5")]
+    pub version: i32,
+    #[prost(message, repeated, tag = "6")]
+    pub authors: Vec<Contact>,
+    #[prost(message, optional, tag = "7")]
+    pub legal: Option<Legal>,
+    #[prost(string, repeated, tag = "8")]
+    pub tags: Vec<String>,
+    #[prost(map = "string, string", tag = "9")]
+    pub annotations: HashMap<String, String>,
+    #[prost(message, repeated, tag = "10")]
+    pub dependencies: Vec<Dependency>,
+    #[prost(message, repeated, tag = "11")]
+    pub compatibilities: Vec<Compatibility>,
+    #[prost(string, repeated, tag = "12")]
+    pub categories: Vec<String>,
+}
+
+/// The owner isn't part of the message, it is stored as the only author
+fn authors(owner: &str) -> Vec<Contact> {
+    vec![Contact {
+        name: owner.to_owned(),
+        email: String::new(),
+    }]
+}
+
+fn strings(values: &[Arc<str>]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+impl FuelMetadata {
+    pub fn decode_bytes(bytes: &[u8]) -> Result<Self, FuelError> {
+        Self::decode(bytes).map_err(|e| FuelError::Parse(e.to_string()))
+    }
+
+    /// Owner of the asset, the first author
+    pub fn owner(&self) -> Option<&str> {
+        self.authors.first().map(|author| author.name.as_str())
+    }
+}
+
+impl From<&FuelModel> for FuelMetadata {
+    /// Metadata entries are stored as annotations, `version` is left to 0 as the cache doesn't
+    /// know the latest version of the model
+    fn from(model: &FuelModel) -> Self {
+        Self {
+            resource_type: Some(ResourceType::Model(ResourceFile::default())),
+            name: model.name.clone(),
+            description: model.description.clone(),
+            authors: authors(&model.owner),
+            legal: Some(Legal {
+                copyright: String::new(),
+                license: model.license_name.to_string(),
+            }),
+            tags: strings(&model.tags),
+            annotations: model
+                .metadata
+                .iter()
+                .map(|entry| (entry.key.to_string(), entry.value.clone()))
+                .collect(),
+            categories: strings(&model.categories),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&FuelWorld> for FuelMetadata {
+    fn from(world: &FuelWorld) -> Self {
+        Self {
+            resource_type: Some(ResourceType::World(ResourceFile::default())),
+            name: world.name.clone(),
+            description: world.description.clone(),
+            authors: authors(&world.owner),
+            legal: Some(Legal {
+                copyright: String::new(),
+                license: world.license_name.to_string(),
+            }),
+            tags: strings(&world.tags),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<&FuelMetadata> for FuelModel {
+    type Error = FuelError;
+
+    /// Fails if the message describes a world or has no author to use as the owner. Fields the
+    /// message doesn't carry, like the download counters, are left to their defaults.
+    fn try_from(metadata: &FuelMetadata) -> Result<Self, FuelError> {
+        if let Some(ResourceType::World(_)) = metadata.resource_type {
+            return Err(FuelError::Parse("the metadata describes a world".into()));
+        }
+        let owner = metadata
+            .owner()
+            .ok_or_else(|| FuelError::Parse("the metadata has no author".into()))?;
+        let mut annotations = metadata.annotations.iter().collect::<Vec<_>>();
+        // Map order is random, keep the entries in a stable order
+        annotations.sort();
+        Ok(FuelModel {
+            name: metadata.name.clone(),
+            url_name: uri::encode_segment(&metadata.name),
+            owner: owner.into(),
+            description: metadata.description.clone(),
+            license_name: metadata
+                .legal
+                .as_ref()
+                .map(|legal| legal.license.as_str())
+                .unwrap_or_default()
+                .into(),
+            tags: metadata
+                .tags
+                .iter()
+                .map(|tag| tag.as_str().into())
+                .collect(),
+            categories: metadata
+                .categories
+                .iter()
+                .map(|category| category.as_str().into())
+                .collect(),
+            metadata: annotations
+                .into_iter()
+                .map(|(key, value)| ModelMetadata {
+                    key: key.as_str().into(),
+                    value: value.clone(),
+                })
+                .collect(),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `gz.msgs.FuelMetadata` of a model in wire format, written field by field from the numbers
+    /// of `fuel_metadata.proto` and `version.proto` rather than produced by the types above
+    fn table_bytes() -> Vec<u8> {
+        [
+            // model { file_format { major: 1 minor: 9 } file: "model.sdf" }
+            &[0x0a, 0x11, 0x0a, 0x04, 0x08, 0x01, 0x10, 0x09, 0x12, 0x09][..],
+            b"model.sdf",
+            // name: "Table"
+            &[0x1a, 0x05],
+            b"Table",
+            // description: "A table"
+            &[0x22, 0x07],
+            b"A table",
+            // version: 2
+            &[0x28, 0x02],
+            // authors { name: "openrobotics" }
+            &[0x32, 0x0e, 0x0a, 0x0c],
+            b"openrobotics",
+            // legal { license: "CC-BY-4.0" }
+            &[0x3a, 0x0b, 0x12, 0x09],
+            b"CC-BY-4.0",
+            // tags: "kitchen"
+            &[0x42, 0x07],
+            b"kitchen",
+            // annotations { key: "color" value: "brown" }
+            &[0x4a, 0x0e, 0x0a, 0x05],
+            b"color",
+            &[0x12, 0x05],
+            b"brown",
+            // categories: "Furniture"
+            &[0x62, 0x09],
+            b"Furniture",
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn decode_upstream_encoding() {
+        let metadata = FuelMetadata::decode_bytes(&table_bytes()).unwrap();
+        let expected = FuelMetadata {
+            resource_type: Some(ResourceType::Model(ResourceFile {
+                file_format: Some(Version {
+                    major: 1,
+                    minor: 9,
+                    ..Default::default()
+                }),
+                file: "model.sdf".to_owned(),
+            })),
+            name: "Table".to_owned(),
+            description: "A table".to_owned(),
+            version: 2,
+            authors: authors("openrobotics"),
+            legal: Some(Legal {
+                copyright: String::new(),
+                license: "CC-BY-4.0".to_owned(),
+            }),
+            tags: vec!["kitchen".to_owned()],
+            annotations: [("color".to_owned(), "brown".to_owned())].into(),
+            categories: vec!["Furniture".to_owned()],
+            ..Default::default()
+        };
+        assert_eq!(metadata, expected);
+        assert_eq!(metadata.encode_to_vec(), table_bytes());
+        assert!(FuelMetadata::decode_bytes(&table_bytes()[..20]).is_err());
+    }
+
+    #[test]
+    fn model_round_trip() {
+        let metadata = FuelMetadata::decode_bytes(&table_bytes()).unwrap();
+        let model = FuelModel::try_from(&metadata).unwrap();
+        assert_eq!(
+            (&*model.owner, model.name.as_str()),
+            ("openrobotics", "Table")
+        );
+        assert_eq!(&*model.license_name, "CC-BY-4.0");
+        assert_eq!(model.tags, [Arc::from("kitchen")]);
+        assert_eq!(model.categories, [Arc::from("Furniture")]);
+        assert_eq!(&*model.metadata[0].key, "color");
+        assert_eq!(model.metadata[0].value, "brown");
+
+        let encoded = FuelMetadata::from(&model).encode_to_vec();
+        let decoded = FuelMetadata::decode_bytes(&encoded).unwrap();
+        assert_eq!(FuelModel::try_from(&decoded).unwrap(), model);
+
+        let world = FuelMetadata {
+            resource_type: Some(ResourceType::World(ResourceFile::default())),
+            ..decoded
+        };
+        assert!(FuelModel::try_from(&world).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+use crate::{find_asset, AssetKind, DownloadedModel, FuelClient, FuelError, FuelModel};
+
+/// Custom processing of downloaded models, i.e. mesh decimation or collision generation, run in
+/// the download pipeline once the model is extracted. Added with
+/// `FuelClientBuilder::download_hook`, hooks run in the order they were added. With a
+/// `ContentStore` the files of the model are copied out of the store before the hooks run.
+pub trait DownloadHook: Send + Sync {
+    /// `model` is the cached metadata of the model, None if it isn't cached. An error makes the
+    /// download of this model fail with `FuelError::Hook`, the extracted files are kept and the
+    /// remaining hooks are skipped.
+    fn after_download(
+        &self,
+        downloaded: &DownloadedModel,
+        model: Option<&FuelModel>,
+    ) -> Result<(), String>;
+}
+
+impl<F> DownloadHook for F
+where
+    F: Fn(&DownloadedModel, Option<&FuelModel>) -> Result<(), String> + Send + Sync,
+{
+    fn after_download(
+        &self,
+        downloaded: &DownloadedModel,
+        model: Option<&FuelModel>,
+    ) -> Result<(), String> {
+        self(downloaded, model)
+    }
+}
+
+impl FuelClient {
+    /// Runs the download hooks on a downloaded asset, worlds are left as is
+    pub(crate) fn run_download_hooks(
+        &self,
+        kind: AssetKind,
+        downloaded: DownloadedModel,
+    ) -> Result<DownloadedModel, FuelError> {
+        if kind != AssetKind::Model || self.download_hooks.is_empty() {
+            return Ok(downloaded);
+        }
+        // Hooks may modify the files in place, they can't be shared with other models
+        if let Some(store) = &self.content_store {
+            store.detach(&downloaded.path)?;
+        }
+        let model = self
+            .models()
+            .as_ref()
+            .and_then(|models| find_asset(models, &downloaded.owner, &downloaded.name).cloned());
+        for hook in &self.download_hooks {
+            hook.after_download(&downloaded, model.as_ref())
+                .map_err(|error| FuelError::Hook {
+                    owner: downloaded.owner.clone(),
+                    name: downloaded.name.clone(),
+                    error,
+                })?;
+        }
+        Ok(downloaded)
+    }
+}
@@ -0,0 +1,523 @@
+use base64::prelude::*;
+use std::{
+    fmt, fs,
+    io::{self, Read, Seek, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use url::Url;
+
+use crate::{FuelError, Metrics};
+
+/// A blocking HTTP implementation used by the client for all requests, they are run in a
+/// dedicated thread so implementations are free to block.
+pub trait HttpBackend: Send + Sync {
+    /// `Ok` is returned for any response, including error statuses, `Err` means no response was
+    /// received.
+    fn fetch_blocking(&self, request: &ehttp::Request) -> Result<ehttp::Response, String>;
+
+    /// Same as `fetch_blocking` but the body is written to `body` while it is received instead of
+    /// being buffered, the returned response has no bytes. Used for archive downloads, the default
+    /// implementation buffers the whole body.
+    fn fetch_to_writer(
+        &self,
+        request: &ehttp::Request,
+        body: &mut dyn Write,
+    ) -> Result<ehttp::Response, String> {
+        let mut res = self.fetch_blocking(request)?;
+        body.write_all(&res.bytes).map_err(|e| e.to_string())?;
+        res.bytes.clear();
+        Ok(res)
+    }
+}
+
+/// Hooks around every request the client sends, i.e. to implement custom auth schemes, logging
+/// or caching. Requests already contain the client token and basic auth headers when
+/// `before_request` is called.
+pub trait Middleware: Send + Sync {
+    /// Can modify the request, returning a response skips sending it and the remaining
+    /// middlewares
+    fn before_request(&self, _request: &mut ehttp::Request) -> Option<ehttp::Response> {
+        None
+    }
+
+    /// Called with the final response once retries are exhausted, error statuses included
+    fn after_response(&self, _request: &ehttp::Request, _response: &mut ehttp::Response) {}
+}
+
+/// Backend using ehttp with its default settings, it doesn't support timeouts
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EhttpBackend;
+
+impl HttpBackend for EhttpBackend {
+    fn fetch_blocking(&self, request: &ehttp::Request) -> Result<ehttp::Response, String> {
+        ehttp::fetch_blocking(request)
+    }
+}
+
+/// Backend built on a configurable ureq agent, used by default
+#[derive(Debug, Clone)]
+pub struct UreqBackend {
+    agent: ureq::Agent,
+}
+
+impl Default for UreqBackend {
+    fn default() -> Self {
+        Self::new(ureq::AgentBuilder::new().redirects(0))
+    }
+}
+
+impl UreqBackend {
+    /// Redirects should be disabled on the agent with `redirects(0)`, so the client follows them
+    /// according to its `RedirectPolicy`
+    pub fn new(builder: ureq::AgentBuilder) -> Self {
+        Self {
+            agent: builder.build(),
+        }
+    }
+}
+
+impl UreqBackend {
+    /// Sends the request, returns the response without its body and a reader for the body
+    fn call(
+        &self,
+        request: &ehttp::Request,
+    ) -> Result<(ehttp::Response, Box<dyn Read + Send + Sync>), String> {
+        let mut req = self.agent.request(&request.method, &request.url);
+        for (k, v) in &request.headers {
+            req = req.set(k, v);
+        }
+        let res = if request.body.is_empty() {
+            req.call()
+        } else {
+            req.send_bytes(&request.body)
+        };
+        let res = match res {
+            Ok(res) | Err(ureq::Error::Status(_, res)) => res,
+            Err(ureq::Error::Transport(e)) => return Err(e.to_string()),
+        };
+        let url = res.get_url().to_owned();
+        let status = res.status();
+        // Redirects that weren't followed by the agent are not successful either
+        let ok = (200..300).contains(&status);
+        let status_text = res.status_text().to_owned();
+        let mut headers = ehttp::Headers::default();
+        for key in res.headers_names() {
+            if let Some(value) = res.header(&key) {
+                headers.insert(&key, value);
+            }
+        }
+        let res_without_body = ehttp::Response {
+            url,
+            ok,
+            status,
+            status_text,
+            headers,
+            bytes: Vec::new(),
+        };
+        Ok((res_without_body, res.into_reader()))
+    }
+}
+
+impl HttpBackend for UreqBackend {
+    fn fetch_blocking(&self, request: &ehttp::Request) -> Result<ehttp::Response, String> {
+        let (mut res, mut reader) = self.call(request)?;
+        if let Err(e) = reader.read_to_end(&mut res.bytes) {
+            // HEAD responses have no body to read
+            if request.method != "HEAD" {
+                return Err(format!("failed reading response body: {e}"));
+            }
+        }
+        Ok(res)
+    }
+
+    fn fetch_to_writer(
+        &self,
+        request: &ehttp::Request,
+        body: &mut dyn Write,
+    ) -> Result<ehttp::Response, String> {
+        let (res, mut reader) = self.call(request)?;
+        io::copy(&mut reader, body).map_err(|e| format!("failed reading response body: {e}"))?;
+        Ok(res)
+    }
+}
+
+/// Credentials for servers behind a reverse proxy requiring HTTP basic auth
+#[derive(Clone, PartialEq, Eq)]
+pub struct BasicAuth {
+    pub username: String,
+    pub password: String,
+}
+
+impl fmt::Debug for BasicAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BasicAuth")
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+impl BasicAuth {
+    pub fn new(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    /// Value of the `Authorization` header
+    pub fn header_value(&self) -> String {
+        let credentials = format!("{}:{}", self.username, self.password);
+        format!("Basic {}", BASE64_STANDARD.encode(credentials))
+    }
+}
+
+/// How failed requests are retried, only transport errors and 429 / 5xx statuses are retried
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on every following one
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            backoff: Duration::ZERO,
+        }
+    }
+
+    /// Requests that might have been processed by the server are only retried if repeating them
+    /// is harmless, 429 responses are always retried
+    fn should_retry(
+        &self,
+        attempt: u32,
+        request: &ehttp::Request,
+        res: &Result<ehttp::Response, String>,
+    ) -> bool {
+        let idempotent = !matches!(request.method.as_str(), "POST" | "PATCH");
+        attempt < self.max_retries
+            && match res {
+                Ok(res) => res.status == 429 || (idempotent && res.status >= 500),
+                Err(_) => idempotent,
+            }
+    }
+
+    fn delay(&self, attempt: u32) -> Duration {
+        self.backoff.saturating_mul(2u32.saturating_pow(attempt))
+    }
+}
+
+/// How redirects are followed, i.e. downloads redirected to a CDN or an object storage
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedirectPolicy {
+    /// Redirect responses are returned as is if 0
+    pub max_redirects: u32,
+    /// Keep sending the token and basic auth headers when redirected to another origin, i.e.
+    /// another scheme, host or port. They are dropped by default, so credentials aren't leaked to
+    /// third party storage, and always dropped when redirected from https to http.
+    pub forward_auth_cross_host: bool,
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        Self {
+            max_redirects: 5,
+            forward_auth_cross_host: false,
+        }
+    }
+}
+
+impl RedirectPolicy {
+    pub fn none() -> Self {
+        Self {
+            max_redirects: 0,
+            ..Default::default()
+        }
+    }
+
+    /// Request to send to `url` after `request` was answered with the redirect `status`
+    fn follow(&self, mut request: ehttp::Request, status: u16, url: String) -> ehttp::Request {
+        let (from, to) = (Url::parse(&request.url).ok(), Url::parse(&url).ok());
+        let same_origin =
+            matches!((&from, &to), (Some(from), Some(to)) if from.origin() == to.origin());
+        let downgrade = from.is_some_and(|from| from.scheme() == "https")
+            && to.is_none_or(|to| to.scheme() != "https");
+        if downgrade || (!same_origin && !self.forward_auth_cross_host) {
+            request.headers.headers.retain(|(k, _)| {
+                !k.eq_ignore_ascii_case("Private-token") && !k.eq_ignore_ascii_case("Authorization")
+            });
+        }
+        // Like browsers, only 307 and 308 keep the method and body of POST requests
+        if status == 303 || (matches!(status, 301 | 302) && request.method == "POST") {
+            request.method = "GET".to_owned();
+            request.body.clear();
+        }
+        request.url = url;
+        request
+    }
+}
+
+/// Target of a redirect response, resolved against the url of the request
+fn redirect_url(request_url: &str, res: &ehttp::Response) -> Option<String> {
+    if !matches!(res.status, 301 | 302 | 303 | 307 | 308) {
+        return None;
+    }
+    let location = res.headers.get("Location")?;
+    let url = Url::parse(request_url).ok()?.join(location).ok()?;
+    Some(url.into())
+}
+
+/// Spaces out requests so that no more than `requests_per_sec` are sent, retries included. Clones
+/// of a client share the same limiter.
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    /// Earliest time the next request can be sent
+    next: Mutex<Instant>,
+}
+
+/// Longest interval between two requests, tiny rates are clamped to it
+const MAX_RATE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+impl RateLimiter {
+    /// Rates that aren't positive, including NaN, disable the limit
+    pub fn new(requests_per_sec: f64) -> Self {
+        let interval = match requests_per_sec > 0.0 {
+            true => Duration::try_from_secs_f64(1.0 / requests_per_sec)
+                .map_or(MAX_RATE_INTERVAL, |interval| {
+                    interval.min(MAX_RATE_INTERVAL)
+                }),
+            false => Duration::ZERO,
+        };
+        Self {
+            interval,
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Blocks until the next request can be sent
+    fn wait(&self) {
+        let now = Instant::now();
+        let at = {
+            let mut next = self.next.lock().unwrap_or_else(PoisonError::into_inner);
+            let at = (*next).max(now);
+            *next = at.checked_add(self.interval).unwrap_or(at);
+            at
+        };
+        thread::sleep(at - now);
+    }
+}
+
+/// Everything needed to send requests, moved to the request thread
+#[derive(Clone)]
+pub(crate) struct Transport {
+    pub(crate) backend: Arc<dyn HttpBackend>,
+    pub(crate) retry: RetryPolicy,
+    pub(crate) redirect: RedirectPolicy,
+    pub(crate) rate_limiter: Option<Arc<RateLimiter>>,
+    pub(crate) metrics: Option<Arc<dyn Metrics>>,
+}
+
+/// Runs the request on a separate thread, retrying and following redirects according to the
+/// policies. If `body_path` is given the body is streamed to that file instead of being returned
+/// in the response. Transport errors are returned as `FuelError::Network`.
+pub(crate) async fn fetch(
+    transport: Transport,
+    request: ehttp::Request,
+    body_path: Option<PathBuf>,
+) -> Result<ehttp::Response, FuelError> {
+    let (tx, rx) = async_channel::bounded(1);
+    thread::Builder::new()
+        .name("gz-fuel-http".to_owned())
+        .spawn(move || {
+            let mut request = request;
+            let mut visited = vec![request.url.clone()];
+            let res = loop {
+                let res = match transport.send(&request, body_path.as_deref()) {
+                    Ok(res) => res,
+                    Err(e) => break Err(FuelError::Network(e)),
+                };
+                let redirect = &transport.redirect;
+                let next = redirect_url(&request.url, &res).filter(|_| redirect.max_redirects > 0);
+                let Some(next) = next else {
+                    break Ok(res);
+                };
+                if visited.contains(&next) {
+                    break Err(FuelError::RedirectLoop { url: next });
+                }
+                if visited.len() > redirect.max_redirects as usize {
+                    break Err(FuelError::TooManyRedirects {
+                        url: next,
+                        max: redirect.max_redirects,
+                    });
+                }
+                visited.push(next.clone());
+                request = redirect.follow(request, res.status, next);
+            };
+            tx.send_blocking(res).ok();
+        })
+        .map_err(|e| FuelError::Network(e.to_string()))?;
+    rx.recv()
+        .await
+        .map_err(|e| FuelError::Network(e.to_string()))?
+}
+
+impl Transport {
+    /// Sends a single request, retrying according to the policy
+    fn send(
+        &self,
+        request: &ehttp::Request,
+        body_path: Option<&Path>,
+    ) -> Result<ehttp::Response, String> {
+        let mut attempt = 0;
+        loop {
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.wait();
+            }
+            let (res, streamed) = match body_path {
+                Some(path) => stream_to_file(self.backend.as_ref(), request, path),
+                None => (self.backend.fetch_blocking(request), 0),
+            };
+            if let Some(metrics) = &self.metrics {
+                let (status, received) = match &res {
+                    Ok(res) => (Some(res.status), res.bytes.len() + streamed),
+                    Err(_) => (None, 0),
+                };
+                metrics.on_request(
+                    &request.method,
+                    &request.url,
+                    status,
+                    request.body.len(),
+                    received,
+                );
+            }
+            if !self.retry.should_retry(attempt, request, &res) {
+                return res;
+            }
+            if let Some(metrics) = &self.metrics {
+                metrics.on_retry(&request.url);
+            }
+            thread::sleep(self.retry.delay(attempt));
+            attempt += 1;
+        }
+    }
+}
+
+/// Returns the response and the number of bytes written to `path`, which is truncated first so
+/// retries don't append to the body of a failed attempt
+fn stream_to_file(
+    backend: &dyn HttpBackend,
+    request: &ehttp::Request,
+    path: &Path,
+) -> (Result<ehttp::Response, String>, usize) {
+    let mut file = match fs::File::create(path) {
+        Ok(file) => io::BufWriter::new(file),
+        Err(e) => return (Err(e.to_string()), 0),
+    };
+    let res = backend
+        .fetch_to_writer(request, &mut file)
+        .and_then(|res| file.flush().map(|_| res).map_err(|e| e.to_string()));
+    let written = file.get_mut().stream_position().unwrap_or(0) as usize;
+    (res, written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redirect(request_url: &str, location: &str) -> Option<String> {
+        let mut res = crate::testing::response(request_url, 302, "");
+        res.headers.insert("Location", location);
+        redirect_url(request_url, &res)
+    }
+
+    #[test]
+    fn redirect_urls() {
+        let base = "https://fuel.test/1.0/o/models/m/tip/m.zip?token=1";
+        for (location, expected) in [
+            ("https://cdn.test/m.zip", "https://cdn.test/m.zip"),
+            ("//cdn.test/m.zip", "https://cdn.test/m.zip"),
+            ("/files/m.zip", "https://fuel.test/files/m.zip"),
+            ("m2.zip", "https://fuel.test/1.0/o/models/m/tip/m2.zip"),
+            ("../2/m.zip", "https://fuel.test/1.0/o/models/m/2/m.zip"),
+            (
+                "?token=2",
+                "https://fuel.test/1.0/o/models/m/tip/m.zip?token=2",
+            ),
+        ] {
+            assert_eq!(redirect(base, location).as_deref(), Some(expected));
+        }
+        let res = crate::testing::response(base, 200, "");
+        assert_eq!(redirect_url(base, &res), None);
+    }
+
+    #[test]
+    fn redirects_drop_credentials_across_origins() {
+        let forwarded = |policy: &RedirectPolicy, from: &str, to: &str| {
+            let mut request = ehttp::Request::get(from);
+            request.headers.insert("Private-token", "secret");
+            let request = policy.follow(request, 302, to.to_owned());
+            request.headers.get("Private-token").is_some()
+        };
+        let policy = RedirectPolicy::default();
+        assert!(forwarded(
+            &policy,
+            "https://fuel.test/a",
+            "https://FUEL.test:443/b"
+        ));
+        assert!(!forwarded(
+            &policy,
+            "https://fuel.test/a",
+            "https://cdn.test/b"
+        ));
+        assert!(!forwarded(
+            &policy,
+            "https://fuel.test/a",
+            "http://fuel.test/b"
+        ));
+        assert!(!forwarded(
+            &policy,
+            "https://fuel.test/a",
+            "https://fuel.test:8443/b"
+        ));
+        let policy = RedirectPolicy {
+            forward_auth_cross_host: true,
+            ..Default::default()
+        };
+        assert!(forwarded(
+            &policy,
+            "https://fuel.test/a",
+            "https://cdn.test/b"
+        ));
+        assert!(!forwarded(
+            &policy,
+            "https://fuel.test/a",
+            "http://cdn.test/b"
+        ));
+    }
+
+    #[test]
+    fn rate_limiter_intervals() {
+        assert_eq!(RateLimiter::new(4.0).interval, Duration::from_millis(250));
+        for disabled in [0.0, -1.0, f64::NAN, f64::NEG_INFINITY, f64::INFINITY] {
+            assert_eq!(RateLimiter::new(disabled).interval, Duration::ZERO);
+        }
+        for tiny in [1e-300, f64::MIN_POSITIVE, 1e-6] {
+            assert_eq!(RateLimiter::new(tiny).interval, MAX_RATE_INTERVAL);
+        }
+    }
+}
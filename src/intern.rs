@@ -0,0 +1,28 @@
+use std::{collections::HashSet, sync::Arc};
+
+use crate::asset::CacheEntry;
+
+/// Deduplicates strings that repeat across many models
+#[derive(Default)]
+pub(crate) struct Interner(HashSet<Arc<str>>);
+
+impl Interner {
+    pub(crate) fn intern(&mut self, s: &mut Arc<str>) {
+        match self.0.get(&**s) {
+            Some(interned) => *s = interned.clone(),
+            None => {
+                self.0.insert(s.clone());
+            }
+        }
+    }
+}
+
+/// Makes assets share a single allocation for owners, licenses, tags and categories, which are
+/// repeated across thousands of assets in large caches
+pub(crate) fn intern_assets<A: CacheEntry>(mut assets: Vec<A>) -> Vec<A> {
+    let mut interner = Interner::default();
+    for asset in &mut assets {
+        asset.intern(&mut interner);
+    }
+    assets
+}
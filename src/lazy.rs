@@ -0,0 +1,142 @@
+use memmap2::Mmap;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use std::{borrow::Cow, fs, ops::Range, path::Path};
+
+#[cfg(feature = "client")]
+use crate::FuelClient;
+use crate::{FuelError, FuelModel};
+
+/// Owner and name of a cache entry, all that is parsed when building the index. Borrowed unless
+/// they contain escape sequences.
+#[derive(Deserialize)]
+struct EntryKey<'a> {
+    #[serde(borrow)]
+    owner: Cow<'a, str>,
+    #[serde(borrow)]
+    name: Cow<'a, str>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LazyEntry {
+    pub owner: String,
+    pub name: String,
+    /// Byte range of the entry in the cache file
+    range: Range<usize>,
+}
+
+/// Read-only view of a cache file that is memory mapped instead of loaded. Only an index of
+/// owners and names is kept in memory and models are deserialized on demand, so opening it
+/// doesn't scale with the catalog size.
+///
+/// The cache file must not be modified in place while it is open, the client always replaces it
+/// atomically when writing.
+pub struct LazyModelCache {
+    mmap: Mmap,
+    index: Vec<LazyEntry>,
+}
+
+impl LazyModelCache {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, FuelError> {
+        let file = fs::File::open(path).map_err(|e| FuelError::Io(e.to_string()))?;
+        // SAFETY: cache files are only ever replaced through a rename, never modified in place
+        let mmap = unsafe { Mmap::map(&file) }.map_err(|e| FuelError::Io(e.to_string()))?;
+        let base = mmap.as_ptr() as usize;
+        let entries = serde_json::from_slice::<Vec<&RawValue>>(&mmap)
+            .map_err(|e| FuelError::Parse(e.to_string()))?;
+        let index = entries
+            .into_iter()
+            .map(|raw| {
+                let key = serde_json::from_str::<EntryKey>(raw.get())
+                    .map_err(|e| FuelError::Parse(e.to_string()))?;
+                let start = raw.get().as_ptr() as usize - base;
+                Ok(LazyEntry {
+                    owner: key.owner.into_owned(),
+                    name: key.name.into_owned(),
+                    range: start..start + raw.get().len(),
+                })
+            })
+            .collect::<Result<Vec<_>, FuelError>>()?;
+        Ok(Self { mmap, index })
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Owners and names of all the cached models, in cache order
+    pub fn index(&self) -> &[LazyEntry] {
+        &self.index
+    }
+
+    /// Deserializes the model at position `idx` of the index
+    pub fn model(&self, idx: usize) -> Option<FuelModel> {
+        let entry = self.index.get(idx)?;
+        serde_json::from_slice(&self.mmap[entry.range.clone()]).ok()
+    }
+
+    pub fn get(&self, owner: &str, name: &str) -> Option<FuelModel> {
+        let idx = self.index.iter().position(|entry| {
+            entry.owner.eq_ignore_ascii_case(owner) && entry.name.eq_ignore_ascii_case(name)
+        })?;
+        self.model(idx)
+    }
+
+    /// Deserializes models one by one while iterating
+    pub fn iter(&self) -> impl Iterator<Item = FuelModel> + '_ {
+        (0..self.index.len()).filter_map(|idx| self.model(idx))
+    }
+}
+
+#[cfg(feature = "client")]
+impl FuelClient {
+    /// Opens the client cache file lazily, see `LazyModelCache`
+    pub fn open_lazy_cache(&self) -> Result<LazyModelCache, FuelError> {
+        let path = self
+            .cache_path
+            .clone()
+            .or_else(Self::default_cache_path)
+            .ok_or_else(|| FuelError::Io("no cache path available".into()))?;
+        LazyModelCache::open(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{model, TempDir};
+
+    #[test]
+    fn entry_key_with_escapes() {
+        let key = serde_json::from_str::<EntryKey>(r#"{"owner": "R\u00e9my", "name": "a\"b"}"#);
+        let key = key.unwrap();
+        assert_eq!(key.owner, "Rémy");
+        assert_eq!(key.name, "a\"b");
+        let key = serde_json::from_str::<EntryKey>(r#"{"owner": "o", "name": "n"}"#).unwrap();
+        assert!(matches!(key.owner, Cow::Borrowed("o")));
+    }
+
+    #[test]
+    fn open_cache_with_escaped_names() {
+        let dir = TempDir::new();
+        let path = dir.0.join("model_cache.json");
+        let models = vec![
+            model("Rémy", "Table \"big\"", "1"),
+            model("o", "Chair", "2"),
+        ];
+        // serde_json doesn't escape non ASCII characters, escape them like other writers may
+        let json = serde_json::to_string(&models)
+            .unwrap()
+            .replace('é', "\\u00e9");
+        fs::write(&path, json).unwrap();
+        let cache = LazyModelCache::open(&path).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.index()[0].owner, "Rémy");
+        assert_eq!(cache.get("rémy", "table \"big\""), Some(models[0].clone()));
+        assert_eq!(cache.iter().collect::<Vec<_>>(), models);
+    }
+}
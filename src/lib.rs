@@ -1,2 +1,163 @@
+#[cfg(feature = "client")]
+pub mod api;
+pub mod asset;
+pub use asset::*;
+#[cfg(feature = "client")]
+pub mod audit;
+#[cfg(feature = "client")]
+pub use audit::*;
+#[cfg(feature = "client")]
+pub mod builder;
+#[cfg(feature = "client")]
+pub use builder::*;
+#[cfg(feature = "client")]
+pub mod check;
+#[cfg(feature = "client")]
+pub use check::*;
+#[cfg(feature = "client")]
+mod checkpoint;
+#[cfg(feature = "client")]
+pub mod collection;
+#[cfg(feature = "client")]
+pub use collection::*;
+#[cfg(feature = "client")]
+pub mod config;
+#[cfg(feature = "client")]
+pub use config::*;
+#[cfg(feature = "client")]
+pub mod download;
+#[cfg(feature = "client")]
+pub use download::*;
+#[cfg(feature = "client")]
+pub mod duplicates;
+#[cfg(feature = "client")]
+pub use duplicates::*;
+pub mod error;
+pub use error::*;
+#[cfg(feature = "client")]
+pub mod favorites;
+#[cfg(feature = "client")]
+pub use favorites::*;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "client")]
 pub mod fuel_client;
+#[cfg(feature = "client")]
 pub use fuel_client::*;
+#[cfg(feature = "gz-msgs")]
+pub mod gz_msgs;
+#[cfg(feature = "client")]
+pub mod hooks;
+#[cfg(feature = "client")]
+pub use hooks::*;
+#[cfg(feature = "client")]
+pub mod http;
+#[cfg(feature = "client")]
+pub use http::*;
+mod intern;
+pub mod lazy;
+pub use lazy::*;
+#[cfg(feature = "client")]
+pub mod local;
+#[cfg(feature = "client")]
+pub mod manage;
+#[cfg(feature = "client")]
+pub mod manifest;
+#[cfg(feature = "client")]
+pub use manifest::*;
+#[cfg(feature = "client")]
+pub mod metrics;
+#[cfg(feature = "client")]
+pub use metrics::*;
+#[cfg(feature = "client")]
+pub mod mirror;
+#[cfg(feature = "client")]
+pub use mirror::*;
+pub mod model_cache;
+pub use model_cache::*;
+#[cfg(feature = "client")]
+pub mod model_config;
+#[cfg(feature = "client")]
+pub use model_config::*;
+#[cfg(feature = "client")]
+pub mod model_files;
+#[cfg(feature = "client")]
+pub use model_files::*;
+#[cfg(feature = "client")]
+pub mod notes;
+#[cfg(feature = "client")]
+pub use notes::*;
+#[cfg(feature = "client")]
+pub mod preset;
+#[cfg(feature = "client")]
+pub use preset::*;
+#[cfg(feature = "indicatif")]
+pub mod progress_bars;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "client")]
+pub mod queue;
+#[cfg(feature = "client")]
+pub use queue::*;
+#[cfg(feature = "client")]
+pub mod response_cache;
+#[cfg(feature = "client")]
+pub use response_cache::*;
+#[cfg(feature = "client")]
+pub mod sdf;
+#[cfg(feature = "client")]
+pub use sdf::*;
+#[cfg(feature = "client")]
+pub mod search;
+#[cfg(feature = "client")]
+pub use search::*;
+#[cfg(feature = "client")]
+pub mod sizes;
+#[cfg(feature = "client")]
+pub use sizes::*;
+#[cfg(feature = "client")]
+pub mod snapshot;
+#[cfg(feature = "client")]
+pub use snapshot::*;
+#[cfg(feature = "client")]
+pub mod space;
+#[cfg(feature = "client")]
+pub use space::*;
+#[cfg(feature = "client")]
+pub mod stats;
+#[cfg(feature = "client")]
+pub use stats::*;
+#[cfg(feature = "client")]
+pub mod store;
+#[cfg(feature = "client")]
+pub use store::*;
+#[cfg(test)]
+mod testing;
+#[cfg(feature = "client")]
+pub mod thumbnail;
+#[cfg(feature = "client")]
+pub use thumbnail::*;
+#[cfg(feature = "client")]
+pub mod tls;
+#[cfg(feature = "client")]
+pub use tls::*;
+#[cfg(feature = "client")]
+pub mod tombstone;
+#[cfg(feature = "client")]
+pub use tombstone::*;
+#[cfg(feature = "client")]
+pub mod update;
+#[cfg(feature = "client")]
+pub use update::*;
+#[cfg(feature = "client")]
+pub mod upload;
+pub mod uri;
+pub use uri::*;
+#[cfg(feature = "client")]
+pub mod watch;
+#[cfg(feature = "client")]
+pub use watch::*;
+#[cfg(feature = "client")]
+pub mod world;
+#[cfg(feature = "client")]
+pub use world::*;
@@ -0,0 +1,108 @@
+use std::{fs, path::PathBuf, sync::PoisonError};
+
+use crate::{intern::intern_assets, FuelClient, FuelError, FuelModel, ModelCache};
+
+impl FuelClient {
+    /// Local entries are stored next to the cache file and survive cache updates
+    fn local_models_path(&self) -> Option<PathBuf> {
+        self.cache_sidecar_path("local.json")
+    }
+
+    pub(crate) fn read_local_models(&self) -> Vec<FuelModel> {
+        self.local_models_path()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|b| serde_json::de::from_slice(&b).ok())
+            .unwrap_or_default()
+    }
+
+    fn write_local_models(&self) -> Result<(), FuelError> {
+        let Some(path) = self.local_models_path() else {
+            return Ok(());
+        };
+        let bytes = serde_json::ser::to_string_pretty(&self.local_models())
+            .map_err(|e| FuelError::Io(e.to_string()))?;
+        self.write_cache_file(&path, bytes)
+    }
+
+    /// User defined entries that were added to the cache, i.e. models that only exist on disk
+    pub fn local_models(&self) -> Vec<FuelModel> {
+        self.local_models
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Adds a user defined entry to the cache, replacing any cached model with the same owner and
+    /// name. Local entries are served by the same filtering functions as Fuel models and are kept
+    /// across cache updates, unless the server has a model with the same owner and name.
+    pub fn add_local_model(&self, model: FuelModel, write_to_disk: bool) -> Result<(), FuelError> {
+        {
+            let mut local = self
+                .local_models
+                .write()
+                .unwrap_or_else(PoisonError::into_inner);
+            local.retain(|m| !same_model(m, &model));
+            local.push(model.clone());
+        }
+        {
+            let mut models = self.write_models();
+            let models = &mut models.get_or_insert_with(ModelCache::default).models;
+            models.retain(|m| !same_model(m, &model));
+            models.push(model);
+            *models = intern_assets(std::mem::take(models));
+        }
+        if write_to_disk {
+            self.write_local_models()?;
+            self.write_cache()?;
+        }
+        Ok(())
+    }
+
+    /// Removes a user defined entry from the cache, returns whether it was present. Fuel models
+    /// can't be removed this way.
+    pub fn remove_local_model(
+        &self,
+        owner: &str,
+        name: &str,
+        write_to_disk: bool,
+    ) -> Result<bool, FuelError> {
+        let is_match = |m: &FuelModel| {
+            m.owner.eq_ignore_ascii_case(owner) && m.name.eq_ignore_ascii_case(name)
+        };
+        let mut local = self
+            .local_models
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        let Some(idx) = local.iter().position(is_match) else {
+            return Ok(false);
+        };
+        let removed = local.remove(idx);
+        drop(local);
+        if let Some(models) = self.write_models().as_mut() {
+            models.models.retain(|m| *m != removed);
+        }
+        if write_to_disk {
+            self.write_local_models()?;
+            self.write_cache()?;
+        }
+        Ok(true)
+    }
+
+    /// Adds the local entries that are missing from freshly fetched models
+    pub(crate) fn merge_local_models(&self, mut models: Vec<FuelModel>) -> Vec<FuelModel> {
+        let local = self.local_models();
+        let missing = local
+            .into_iter()
+            .filter(|l| !models.iter().any(|m| same_model(m, l)))
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return models;
+        }
+        models.extend(missing);
+        intern_assets(models)
+    }
+}
+
+fn same_model(a: &FuelModel, b: &FuelModel) -> bool {
+    a.owner.eq_ignore_ascii_case(&b.owner) && a.name.eq_ignore_ascii_case(&b.name)
+}
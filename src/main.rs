@@ -1,12 +1,236 @@
-use gz_fuel::FuelClient;
-use std::time::Duration;
-
-fn main() {
-    let mut client = FuelClient::default();
-    dbg!(&client.cache_path);
-    let should_update = client.should_update_cache(&Some(Duration::from_secs(100000)));
-    dbg!(&should_update);
-    if should_update {
-        client.update_cache_blocking(true);
+use clap::{CommandFactory, Parser, Subcommand};
+use crossbeam_channel::Sender;
+use gz_fuel::{CacheDiff, CacheLoadStatus, FuelClient, ModelCache, PageProgress};
+use std::{
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+    process::ExitCode,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+mod completions;
+mod output;
+#[cfg(all(feature = "tui", unix))]
+mod tui;
+
+#[derive(Parser)]
+#[command(version, about = "Client for Gazebo Fuel servers")]
+struct Cli {
+    /// Base url of the server API
+    #[arg(long, global = true)]
+    url: Option<String>,
+    /// Private token used to authenticate requests
+    #[arg(long, global = true, env = "GZ_FUEL_TOKEN")]
+    token: Option<String>,
+    /// Directory downloaded models are extracted to, separate from the metadata cache
+    #[arg(long, global = true, env = "GZ_FUEL_DATA_DIR")]
+    data_dir: Option<PathBuf>,
+    /// Print results as JSON with a stable schema
+    #[arg(long, global = true)]
+    json: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Refresh the model cache if it is older than the threshold
+    Update {
+        #[arg(long, default_value_t = 100000)]
+        threshold_secs: u64,
+    },
+    /// Download all the models listed in a manifest, one `owner/model[@version]` per line
+    DownloadManifest {
+        path: PathBuf,
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+        /// Only resolve what would be downloaded, without downloading anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Print the completion script of a shell
+    Completions { shell: completions::Shell },
+    /// Browse the cached models interactively, with incremental search and downloads
+    #[cfg(all(feature = "tui", unix))]
+    Tui,
+}
+
+impl Cli {
+    fn client(&self) -> FuelClient {
+        let mut builder = FuelClient::builder();
+        if let Some(url) = &self.url {
+            builder = builder.url(url);
+        }
+        if let Some(token) = &self.token {
+            builder = builder.token(token);
+        }
+        if let Some(dir) = &self.data_dir {
+            builder = builder.download_dir(dir);
+        }
+        builder.build()
+    }
+}
+
+/// Prints the crawled pages on a single stderr line, the thread ends once the sender is dropped
+fn print_pages() -> (Sender<PageProgress>, JoinHandle<()>) {
+    let (tx, rx) = crossbeam_channel::unbounded::<PageProgress>();
+    let printer = thread::spawn(move || {
+        for progress in rx {
+            let last = progress
+                .last_page
+                .map_or_else(|| "?".to_owned(), |last| last.to_string());
+            eprint!(
+                "\rFetching page {}/{last}, {} models",
+                progress.page, progress.fetched
+            );
+            io::stderr().flush().ok();
+        }
+    });
+    (tx, printer)
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let client = cli.client();
+    let json = cli.json;
+    if let CacheLoadStatus::Corrupt(e) = client.cache_load_status() {
+        eprintln!("Ignoring unreadable cache, it will be rebuilt by the next update: {e}");
+    }
+    let command = cli.command.unwrap_or(Command::Update {
+        threshold_secs: 100000,
+    });
+    // Reports an error that prevented the command from running at all
+    let fail = |msg: String| {
+        if json {
+            output::print_json(&output::ErrorOutput { error: msg });
+        } else {
+            eprintln!("{msg}");
+        }
+        ExitCode::FAILURE
+    };
+    match command {
+        Command::Update { threshold_secs } => {
+            let updated = client.should_update_cache(&Some(Duration::from_secs(threshold_secs)));
+            let mut removed = Vec::new();
+            if updated {
+                let before = client.models().clone().unwrap_or_default();
+                let mut client = client.clone();
+                let printer = io::stderr().is_terminal().then(|| {
+                    let (tx, printer) = print_pages();
+                    client.page_progress = Some(tx);
+                    printer
+                });
+                let updated = client.update_cache_blocking(true);
+                client.page_progress = None;
+                if let Some(printer) = printer {
+                    printer.join().ok();
+                    eprintln!();
+                }
+                if let Err(e) = updated {
+                    return fail(format!("Failed updating cache: {e}"));
+                }
+                let after = client.models().clone().unwrap_or_default();
+                removed = CacheDiff::between(&before, &after)
+                    .removed
+                    .iter()
+                    .map(|model| format!("{}/{}", model.owner, model.name))
+                    .collect();
+            }
+            if json {
+                let models = client.models().as_ref().map_or(0, ModelCache::len);
+                output::print_json(&output::UpdateOutput {
+                    updated,
+                    models,
+                    removed,
+                });
+            } else {
+                for model in removed {
+                    println!("Removed from the server: {model}");
+                }
+            }
+        }
+        Command::DownloadManifest {
+            path,
+            dry_run: true,
+            ..
+        } => {
+            let plans = match client.plan_manifest_blocking(&path) {
+                Ok(plans) => plans,
+                Err(e) => return fail(format!("Failed reading manifest: {e}")),
+            };
+            let failed = plans.iter().any(|plan| plan.result.is_err());
+            if json {
+                let plans = plans
+                    .into_iter()
+                    .map(output::PlanOutput::from)
+                    .collect::<Vec<_>>();
+                output::print_json(&plans);
+            } else {
+                for plan in plans {
+                    let request = plan.request;
+                    match plan.result {
+                        Ok(planned) => println!(
+                            "{}/{}@{} {:?} {} bytes -> {}",
+                            request.owner,
+                            request.name,
+                            planned.version,
+                            planned.action,
+                            planned
+                                .size
+                                .map_or_else(|| "unknown".to_owned(), |s| s.to_string()),
+                            planned.path.display()
+                        ),
+                        Err(e) => eprintln!("{}/{} failed: {e}", request.owner, request.name),
+                    }
+                }
+            }
+            if failed {
+                return ExitCode::FAILURE;
+            }
+        }
+        Command::DownloadManifest {
+            path, concurrency, ..
+        } => {
+            let results = match client.download_manifest_blocking(&path, concurrency) {
+                Ok(results) => results,
+                Err(e) => return fail(format!("Failed reading manifest: {e}")),
+            };
+            let failed = results.iter().any(|res| res.result.is_err());
+            if json {
+                let results = results
+                    .into_iter()
+                    .map(output::DownloadOutput::from)
+                    .collect::<Vec<_>>();
+                output::print_json(&results);
+            } else {
+                for res in results {
+                    let request = res.request;
+                    match res.result {
+                        Ok(model) => println!(
+                            "{}/{}@{} -> {}",
+                            request.owner,
+                            request.name,
+                            model.version,
+                            model.path.display()
+                        ),
+                        Err(e) => eprintln!("{}/{} failed: {e}", request.owner, request.name),
+                    }
+                }
+            }
+            if failed {
+                return ExitCode::FAILURE;
+            }
+        }
+        Command::Completions { shell } => {
+            print!("{}", completions::generate(&mut Cli::command(), shell));
+        }
+        #[cfg(all(feature = "tui", unix))]
+        Command::Tui => {
+            if let Err(e) = tui::run(&client) {
+                return fail(format!("Failed running the browser: {e}"));
+            }
+        }
     }
+    ExitCode::SUCCESS
 }
@@ -0,0 +1,173 @@
+use futures_lite::future;
+
+use crate::{intern::intern_assets, upload::Multipart, uri, AssetKind, FuelClient, FuelError};
+
+impl FuelClient {
+    /// Renames a model, the cache entry is renamed as well on success. Requires a token with write
+    /// access to the model.
+    pub async fn rename_model(
+        &self,
+        owner: &str,
+        name: &str,
+        new_name: &str,
+        write_to_disk: bool,
+    ) -> Result<(), FuelError> {
+        let mut form = Multipart::new();
+        form.text("name", new_name);
+        let (content_type, body) = form.finish();
+        let url = self.endpoint(&uri::asset_path(AssetKind::Model, owner, name));
+        let mut req = self.get_request(url);
+        req.method = "PATCH".to_owned();
+        req.headers.insert("Content-Type", content_type);
+        req.body = body;
+        let res = self.fetch(req).await?;
+        if !res.ok {
+            return Err(FuelError::from_status(res.status));
+        }
+        self.update_cached_model(owner, name, None, Some(new_name), write_to_disk)
+    }
+
+    pub fn rename_model_blocking(
+        &self,
+        owner: &str,
+        name: &str,
+        new_name: &str,
+        write_to_disk: bool,
+    ) -> Result<(), FuelError> {
+        future::block_on(self.rename_model(owner, name, new_name, write_to_disk))
+    }
+
+    /// Transfers a model to another user or organization, the cache entry is moved to the new
+    /// owner on success. Requires a token with write access to the model and the new owner.
+    pub async fn transfer_model(
+        &self,
+        owner: &str,
+        name: &str,
+        new_owner: &str,
+        write_to_disk: bool,
+    ) -> Result<(), FuelError> {
+        let url = self.endpoint(&format!(
+            "{}/transfer",
+            uri::asset_path(AssetKind::Model, owner, name)
+        ));
+        let mut req = self.get_request(url);
+        req.method = "POST".to_owned();
+        req.headers.insert("Content-Type", "application/json");
+        req.body = serde_json::json!({ "destOwner": new_owner })
+            .to_string()
+            .into_bytes();
+        let res = self.fetch(req).await?;
+        if !res.ok {
+            return Err(FuelError::from_status(res.status));
+        }
+        self.update_cached_model(owner, name, Some(new_owner), None, write_to_disk)
+    }
+
+    pub fn transfer_model_blocking(
+        &self,
+        owner: &str,
+        name: &str,
+        new_owner: &str,
+        write_to_disk: bool,
+    ) -> Result<(), FuelError> {
+        future::block_on(self.transfer_model(owner, name, new_owner, write_to_disk))
+    }
+
+    /// Changes the owner and / or name of a cache entry, nothing is done if it isn't cached
+    fn update_cached_model(
+        &self,
+        owner: &str,
+        name: &str,
+        new_owner: Option<&str>,
+        new_name: Option<&str>,
+        write_to_disk: bool,
+    ) -> Result<(), FuelError> {
+        {
+            let mut models = self.write_models();
+            let Some(models) = models.as_mut().map(|cache| &mut cache.models) else {
+                return Ok(());
+            };
+            let Some(model) = models.iter_mut().find(|model| {
+                model.owner.eq_ignore_ascii_case(owner) && model.name.eq_ignore_ascii_case(name)
+            }) else {
+                return Ok(());
+            };
+            if let Some(new_owner) = new_owner {
+                model.owner = new_owner.into();
+            }
+            if let Some(new_name) = new_name {
+                model.name = new_name.to_owned();
+                model.url_name = uri::encode_segment(new_name);
+            }
+            *models = intern_assets(std::mem::take(models));
+        }
+        if write_to_disk {
+            self.write_cache()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TempDir};
+    use std::sync::{Arc, Mutex};
+
+    fn cached(client: &FuelClient) -> Vec<(String, String)> {
+        let models = client.models();
+        let models = &models.as_ref().unwrap().models;
+        models
+            .iter()
+            .map(|model| (model.owner.to_string(), model.name.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn rename_and_transfer_update_the_cache() {
+        let dir = TempDir::new();
+        let catalog = vec![testing::model("o", "a", "1")];
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let log = sent.clone();
+        let client = testing::builder(&dir, move |req| match req.method.as_str() {
+            "GET" => Ok(testing::listing(req, &catalog)),
+            _ => {
+                log.lock().unwrap().push(req.clone());
+                let status = if req.url.contains("/locked") {
+                    403
+                } else {
+                    200
+                };
+                Ok(testing::response(&req.url, status, ""))
+            }
+        })
+        .build();
+        client.update_cache_blocking(false).unwrap();
+
+        client
+            .rename_model_blocking("O", "A", "b c", false)
+            .unwrap();
+        assert_eq!(cached(&client), [("o".to_owned(), "b c".to_owned())]);
+        assert_eq!(
+            client.models().as_ref().unwrap().models[0].url_name,
+            "b%20c"
+        );
+        client
+            .transfer_model_blocking("o", "b c", "p", true)
+            .unwrap();
+        assert_eq!(cached(&client), [("p".to_owned(), "b c".to_owned())]);
+        let res = client.rename_model_blocking("p", "locked", "x", false);
+        assert_eq!(res, Err(FuelError::from_status(403)));
+
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent[0].method, "PATCH");
+        assert_eq!(sent[0].url, format!("{}O/models/A", testing::SERVER));
+        assert!(String::from_utf8_lossy(&sent[0].body).contains("\r\n\r\nb c\r\n"));
+        assert_eq!(sent[1].method, "POST");
+        assert_eq!(
+            sent[1].url,
+            format!("{}o/models/b%20c/transfer", testing::SERVER)
+        );
+        assert_eq!(sent[1].body, br#"{"destOwner":"p"}"#);
+    }
+}
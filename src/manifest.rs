@@ -0,0 +1,88 @@
+use std::{fs, path::Path};
+
+use crate::{DownloadPlan, DownloadRequest, DownloadResult, FuelClient, FuelError};
+
+/// A list of models to download, one `owner/model[@version]` entry per line. Empty lines and
+/// anything after a `#` are ignored, i.e.
+///
+/// ```text
+/// # Warehouse
+/// OpenRobotics/Shelf@2
+/// OpenRobotics/Pallet
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Manifest {
+    pub entries: Vec<DownloadRequest>,
+}
+
+impl Manifest {
+    pub fn parse(contents: &str) -> Result<Self, FuelError> {
+        let entries = contents
+            .lines()
+            .enumerate()
+            .filter_map(|(idx, line)| {
+                let line = line.split('#').next().unwrap_or_default().trim();
+                (!line.is_empty()).then(|| Self::parse_entry(line).ok_or(idx + 1))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|line| FuelError::Parse(format!("invalid manifest entry at line {line}")))?;
+        Ok(Self { entries })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, FuelError> {
+        let contents = fs::read_to_string(path).map_err(|e| FuelError::Io(e.to_string()))?;
+        Self::parse(&contents)
+    }
+
+    fn parse_entry(entry: &str) -> Option<DownloadRequest> {
+        let (model, version) = match entry.rsplit_once('@') {
+            Some((model, version)) => (model, Some(version.trim().parse().ok()?)),
+            None => (entry, None),
+        };
+        let (owner, name) = model.split_once('/')?;
+        let (owner, name) = (owner.trim(), name.trim());
+        if owner.is_empty() || name.is_empty() || name.contains('/') {
+            return None;
+        }
+        Some(DownloadRequest {
+            version,
+            ..DownloadRequest::new(owner, name)
+        })
+    }
+}
+
+impl FuelClient {
+    /// Downloads all the models listed in the manifest file at `path`, see `Manifest`
+    pub async fn download_manifest(
+        &self,
+        path: impl AsRef<Path>,
+        concurrency: usize,
+    ) -> Result<Vec<DownloadResult>, FuelError> {
+        let manifest = Manifest::load(path)?;
+        Ok(self.download_models(manifest.entries, concurrency).await)
+    }
+
+    /// Dry run of `download_manifest`, see `FuelClient::plan_download`
+    pub async fn plan_manifest(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<Vec<DownloadPlan>, FuelError> {
+        let manifest = Manifest::load(path)?;
+        Ok(self.plan_downloads(manifest.entries).await)
+    }
+
+    pub fn plan_manifest_blocking(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<Vec<DownloadPlan>, FuelError> {
+        futures_lite::future::block_on(self.plan_manifest(path))
+    }
+
+    pub fn download_manifest_blocking(
+        &self,
+        path: impl AsRef<Path>,
+        concurrency: usize,
+    ) -> Result<Vec<DownloadResult>, FuelError> {
+        futures_lite::future::block_on(self.download_manifest(path, concurrency))
+    }
+}
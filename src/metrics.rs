@@ -0,0 +1,98 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Hooks called by the client, meant to be forwarded to a metrics exporter. All methods default to
+/// doing nothing. Hooks are called from the request threads so implementations shouldn't block.
+pub trait Metrics: Send + Sync {
+    /// A request attempt finished, `status` is None if no response was received. Called once per
+    /// attempt, retries included.
+    fn on_request(
+        &self,
+        _method: &str,
+        _url: &str,
+        _status: Option<u16>,
+        _bytes_sent: usize,
+        _bytes_received: usize,
+    ) {
+    }
+
+    /// A failed request attempt is about to be retried
+    fn on_retry(&self, _url: &str) {}
+
+    /// Data was served from a local cache, or the server reported that the cached data is still
+    /// valid
+    fn on_cache_hit(&self) {}
+
+    /// Data had to be fetched because it wasn't cached or the cached copy was stale
+    fn on_cache_miss(&self) {}
+}
+
+/// `Metrics` implementation keeping totals in atomic counters
+#[derive(Debug, Default)]
+pub struct CountingMetrics {
+    requests: AtomicU64,
+    failed_requests: AtomicU64,
+    retries: AtomicU64,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+}
+
+/// Totals of a `CountingMetrics` at a given time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    pub requests: u64,
+    /// Requests without a response or with an error status
+    pub failed_requests: u64,
+    pub retries: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+}
+
+impl CountingMetrics {
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            requests: self.requests.load(Ordering::Relaxed),
+            failed_requests: self.failed_requests.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            cache_misses: self.cache_misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Metrics for CountingMetrics {
+    fn on_request(
+        &self,
+        _method: &str,
+        _url: &str,
+        status: Option<u16>,
+        bytes_sent: usize,
+        bytes_received: usize,
+    ) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if status.is_none_or(|status| status >= 400) {
+            self.failed_requests.fetch_add(1, Ordering::Relaxed);
+        }
+        self.bytes_sent
+            .fetch_add(bytes_sent as u64, Ordering::Relaxed);
+        self.bytes_received
+            .fetch_add(bytes_received as u64, Ordering::Relaxed);
+    }
+
+    fn on_retry(&self, _url: &str) {
+        self.retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_cache_hit(&self) {
+        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_cache_miss(&self) {
+        self.cache_misses.fetch_add(1, Ordering::Relaxed);
+    }
+}
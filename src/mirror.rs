@@ -0,0 +1,528 @@
+use futures_lite::future;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    fuel_client::read_json_file, uri, DownloadRequest, DownloadResult, DownloadedModel, FuelClient,
+    FuelError, FuelModel,
+};
+
+/// Name of the file keeping track of the mirrored models, in the mirror directory
+const STATE_FILE: &str = "mirror_state.json";
+/// Checkpoint of an interrupted listing crawl, in the mirror directory
+const CRAWL_CHECKPOINT_FILE: &str = "mirror_crawl.jsonl";
+/// Number of downloads after which the state file is saved
+const SAVE_EVERY: usize = 16;
+
+/// A model as last mirrored
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MirroredModel {
+    /// Empty in states written before it was recorded, `MirrorState::load` fills it from the key
+    #[serde(default)]
+    pub owner: String,
+    #[serde(default)]
+    pub name: String,
+    pub version: u32,
+    /// `updatedAt` of the model on the server when it was mirrored
+    pub updated_at: String,
+    pub path: PathBuf,
+}
+
+/// A model whose last download attempt failed
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MirrorFailure {
+    pub owner: String,
+    pub name: String,
+    pub error: String,
+}
+
+/// Content of the state file, keyed by lowercase `owner/name`. The state is saved while the sync
+/// progresses so an interrupted sync only downloads what is left when run again.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct MirrorState {
+    pub models: BTreeMap<String, MirroredModel>,
+    /// Models that failed in the last sync, they are retried by the next one
+    #[serde(default)]
+    pub failed: BTreeMap<String, MirrorFailure>,
+}
+
+impl MirrorState {
+    fn key(owner: &str, name: &str) -> String {
+        format!("{}/{}", owner.to_lowercase(), name.to_lowercase())
+    }
+
+    /// Loads the state of the mirror at `dest`, an empty state is returned if there is none. Fails
+    /// with `FuelError::Parse` if the state file can't be parsed, the file is left as is rather
+    /// than replaced by the next sync.
+    pub fn load(dest: &Path) -> Result<Self, FuelError> {
+        let mut state = read_json_file::<Self>(&dest.join(STATE_FILE))?.unwrap_or_default();
+        for (key, mirrored) in &mut state.models {
+            if mirrored.owner.is_empty() || mirrored.name.is_empty() {
+                if let Some((owner, name)) = key.split_once('/') {
+                    mirrored.owner = owner.to_owned();
+                    mirrored.name = name.to_owned();
+                }
+            }
+        }
+        Ok(state)
+    }
+
+    fn save(&self, dest: &Path) -> Result<(), FuelError> {
+        let io_err = |e: std::io::Error| FuelError::Io(e.to_string());
+        let bytes =
+            serde_json::ser::to_string_pretty(self).map_err(|e| FuelError::Io(e.to_string()))?;
+        fs::create_dir_all(dest).map_err(io_err)?;
+        let tmp = dest.join(format!("{STATE_FILE}.tmp"));
+        fs::write(&tmp, bytes).map_err(io_err)?;
+        fs::rename(tmp, dest.join(STATE_FILE)).map_err(io_err)
+    }
+
+    fn record(&mut self, model: &FuelModel, result: DownloadResult, report: &mut MirrorReport) {
+        let key = Self::key(&model.owner, &model.name);
+        match &result.result {
+            Ok(downloaded) => {
+                self.failed.remove(&key);
+                let previous = self.models.insert(
+                    key,
+                    MirroredModel {
+                        owner: model.owner.to_string(),
+                        name: model.name.clone(),
+                        version: downloaded.version,
+                        updated_at: model.updated_at.clone(),
+                        path: downloaded.path.clone(),
+                    },
+                );
+                // Only keep the latest version of every mirrored model
+                if let Some(previous) = previous.filter(|p| p.path != downloaded.path) {
+                    fs::remove_dir_all(previous.path).ok();
+                }
+                report.downloaded.push(downloaded.clone());
+            }
+            Err(e) => {
+                self.failed.insert(
+                    key,
+                    MirrorFailure {
+                        owner: model.owner.to_string(),
+                        name: model.name.clone(),
+                        error: e.to_string(),
+                    },
+                );
+                report.failed.push(result);
+            }
+        }
+    }
+
+    /// Whether the mirrored copy of `model` is still on disk and matches the server metadata
+    fn is_up_to_date(&self, model: &FuelModel) -> bool {
+        self.models
+            .get(&Self::key(&model.owner, &model.name))
+            .is_some_and(|mirrored| {
+                mirrored.updated_at == model.updated_at && mirrored.path.exists()
+            })
+    }
+
+    /// Splits `models` into the ones to download and the up to date ones
+    fn stale<'a>(&self, models: &'a [FuelModel]) -> (Vec<&'a FuelModel>, Vec<DownloadRequest>) {
+        let mut stale = Vec::new();
+        let mut up_to_date = Vec::new();
+        for model in models {
+            if self.is_up_to_date(model) {
+                up_to_date.push(DownloadRequest::from(model));
+            } else {
+                stale.push(model);
+            }
+        }
+        (stale, up_to_date)
+    }
+
+    /// Forgets the mirrored models that aren't in `models` and returns them, their files are left
+    /// on disk
+    fn retain_models(&mut self, models: &[FuelModel]) -> Vec<MirroredModel> {
+        let keep = models
+            .iter()
+            .map(|model| Self::key(&model.owner, &model.name))
+            .collect::<HashSet<_>>();
+        let (kept, pruned) = std::mem::take(&mut self.models)
+            .into_iter()
+            .partition::<BTreeMap<_, _>, _>(|(key, _)| keep.contains(key));
+        self.models = kept;
+        self.failed.retain(|key, _| keep.contains(key));
+        pruned.into_values().collect()
+    }
+}
+
+/// Outcome of a mirror sync
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MirrorReport {
+    /// Models that were new or changed on the server
+    pub downloaded: Vec<DownloadedModel>,
+    /// Models that were already mirrored and didn't change
+    pub up_to_date: Vec<DownloadRequest>,
+    pub failed: Vec<DownloadResult>,
+    /// Mirrored models that were removed because they stopped matching the mirror filter
+    pub pruned: Vec<MirroredModel>,
+}
+
+/// What a mirror sync would do, as resolved by a dry run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MirrorPlan {
+    /// Models that are new or changed on the server and would be downloaded
+    pub fetch: Vec<DownloadRequest>,
+    /// Total archive size of `fetch` in bytes, as listed by the server
+    pub fetch_size: u64,
+    pub up_to_date: Vec<DownloadRequest>,
+    /// Mirrored models that would be removed, always empty for owner mirrors
+    pub prune: Vec<MirroredModel>,
+}
+
+impl MirrorPlan {
+    fn new(state: &MirrorState, models: &[FuelModel]) -> Self {
+        let (stale, up_to_date) = state.stale(models);
+        Self {
+            fetch: stale
+                .iter()
+                .map(|model| DownloadRequest::from(*model))
+                .collect(),
+            fetch_size: stale.iter().map(|model| model.filesize as u64).sum(),
+            up_to_date,
+            prune: Vec::new(),
+        }
+    }
+}
+
+/// Selects the models of a catalog mirror, all the set criteria must match. Empty lists match
+/// every model.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct MirrorFilter {
+    /// The model must have at least one of these tags
+    pub tags: Vec<String>,
+    /// The model must be in at least one of these categories
+    pub categories: Vec<String>,
+    /// Maximum archive size in bytes
+    pub max_filesize: Option<usize>,
+    /// License names that are allowed, i.e. `Creative Commons - Attribution`
+    pub licenses: Vec<String>,
+}
+
+impl MirrorFilter {
+    pub fn matches(&self, model: &FuelModel) -> bool {
+        let any_of = |wanted: &[String], values: &[std::sync::Arc<str>]| {
+            wanted.is_empty()
+                || values
+                    .iter()
+                    .any(|v| wanted.iter().any(|w| w.eq_ignore_ascii_case(v)))
+        };
+        any_of(&self.tags, &model.tags)
+            && any_of(&self.categories, &model.categories)
+            && self.max_filesize.is_none_or(|max| model.filesize <= max)
+            && (self.licenses.is_empty()
+                || self
+                    .licenses
+                    .iter()
+                    .any(|l| l.eq_ignore_ascii_case(&model.license_name)))
+    }
+}
+
+impl FuelClient {
+    /// Downloads every model of `owner` to `dest`, following the client layout. On later runs only
+    /// the models that changed on the server since they were mirrored are downloaded again, the
+    /// mirrored versions are tracked in a `mirror_state.json` file in `dest`.
+    pub async fn mirror_owner(
+        &self,
+        owner: &str,
+        dest: impl AsRef<Path>,
+        concurrency: usize,
+    ) -> Result<MirrorReport, FuelError> {
+        let dest = dest.as_ref();
+        let listing = format!("{}/models", uri::encode_segment(owner));
+        let models = self.crawl_mirror_listing(&listing, Some(dest)).await?;
+        self.mirror_models(&models, dest, concurrency).await
+    }
+
+    pub fn mirror_owner_blocking(
+        &self,
+        owner: &str,
+        dest: impl AsRef<Path>,
+        concurrency: usize,
+    ) -> Result<MirrorReport, FuelError> {
+        future::block_on(self.mirror_owner(owner, dest, concurrency))
+    }
+
+    /// Dry run of `mirror_owner`, lists the models of `owner` and compares them with the mirror
+    /// state without downloading archives or touching the disk
+    pub async fn plan_mirror_owner(
+        &self,
+        owner: &str,
+        dest: impl AsRef<Path>,
+    ) -> Result<MirrorPlan, FuelError> {
+        let listing = format!("{}/models", uri::encode_segment(owner));
+        let models = self.crawl_mirror_listing(&listing, None).await?;
+        Ok(MirrorPlan::new(&MirrorState::load(dest.as_ref())?, &models))
+    }
+
+    pub fn plan_mirror_owner_blocking(
+        &self,
+        owner: &str,
+        dest: impl AsRef<Path>,
+    ) -> Result<MirrorPlan, FuelError> {
+        future::block_on(self.plan_mirror_owner(owner, dest))
+    }
+
+    /// Mirrors every model of the server catalog matching `filter` to `dest`, see `mirror_owner`.
+    /// Mirrored models that don't match the filter anymore, or were removed from the server, are
+    /// deleted from `dest`, so `dest` shouldn't be shared with other mirrors.
+    pub async fn mirror_catalog(
+        &self,
+        filter: &MirrorFilter,
+        dest: impl AsRef<Path>,
+        concurrency: usize,
+    ) -> Result<MirrorReport, FuelError> {
+        let dest = dest.as_ref();
+        let models = self.catalog_models(filter, Some(dest)).await?;
+        let mut report = self.mirror_models(&models, dest, concurrency).await?;
+        let mut state = MirrorState::load(dest)?;
+        for mirrored in state.retain_models(&models) {
+            fs::remove_dir_all(&mirrored.path).ok();
+            report.pruned.push(mirrored);
+        }
+        state.save(dest)?;
+        Ok(report)
+    }
+
+    pub fn mirror_catalog_blocking(
+        &self,
+        filter: &MirrorFilter,
+        dest: impl AsRef<Path>,
+        concurrency: usize,
+    ) -> Result<MirrorReport, FuelError> {
+        future::block_on(self.mirror_catalog(filter, dest, concurrency))
+    }
+
+    /// Dry run of `mirror_catalog`, lists the catalog and compares it with the mirror state
+    /// without downloading archives or touching the disk
+    pub async fn plan_mirror_catalog(
+        &self,
+        filter: &MirrorFilter,
+        dest: impl AsRef<Path>,
+    ) -> Result<MirrorPlan, FuelError> {
+        let models = self.catalog_models(filter, None).await?;
+        let mut state = MirrorState::load(dest.as_ref())?;
+        let mut plan = MirrorPlan::new(&state, &models);
+        plan.prune = state.retain_models(&models);
+        Ok(plan)
+    }
+
+    pub fn plan_mirror_catalog_blocking(
+        &self,
+        filter: &MirrorFilter,
+        dest: impl AsRef<Path>,
+    ) -> Result<MirrorPlan, FuelError> {
+        future::block_on(self.plan_mirror_catalog(filter, dest))
+    }
+
+    async fn catalog_models(
+        &self,
+        filter: &MirrorFilter,
+        checkpoint_dir: Option<&Path>,
+    ) -> Result<Vec<FuelModel>, FuelError> {
+        let mut models = self.crawl_mirror_listing("models", checkpoint_dir).await?;
+        models.retain(|model| filter.matches(model));
+        Ok(models)
+    }
+
+    /// Crawls a listing, if `checkpoint_dir` is given an interrupted crawl is resumed from its
+    /// last completed page
+    async fn crawl_mirror_listing(
+        &self,
+        listing: &str,
+        checkpoint_dir: Option<&Path>,
+    ) -> Result<Vec<FuelModel>, FuelError> {
+        let checkpoint = match checkpoint_dir {
+            Some(dir) => {
+                fs::create_dir_all(dir).map_err(|e| FuelError::Io(e.to_string()))?;
+                Some(dir.join(CRAWL_CHECKPOINT_FILE))
+            }
+            None => None,
+        };
+        Ok(self
+            .crawl_listing::<FuelModel>(listing, None, None, checkpoint)
+            .await?
+            .map(|crawl| crawl.models)
+            .unwrap_or_default())
+    }
+
+    /// Brings the mirror at `dest` up to date with `models`
+    pub(crate) async fn mirror_models(
+        &self,
+        models: &[FuelModel],
+        dest: &Path,
+        concurrency: usize,
+    ) -> Result<MirrorReport, FuelError> {
+        let mut state = MirrorState::load(dest)?;
+        let (stale, up_to_date) = state.stale(models);
+        let mut report = MirrorReport {
+            up_to_date,
+            ..Default::default()
+        };
+        let requests = stale
+            .iter()
+            .map(|model| DownloadRequest::from(*model))
+            .collect::<Vec<_>>();
+        self.preflight(&requests, dest)?;
+        let mut client = self.clone();
+        client.download_dir = Some(dest.to_path_buf());
+        // Progress is reported over the whole sync rather than per batch
+        let total = requests.len();
+        let batch_size = SAVE_EVERY.max(concurrency);
+        let mut saved = Ok(());
+        for (idx, batch) in requests.chunks(batch_size).enumerate() {
+            let offset = idx * batch_size;
+            let results = client
+                .download_batch(
+                    batch.to_vec(),
+                    concurrency,
+                    self.download_progress.clone(),
+                    offset,
+                    total,
+                )
+                .await;
+            for (model, result) in stale[offset..].iter().zip(results) {
+                state.record(model, result, &mut report);
+            }
+            saved = state.save(dest);
+            if saved.is_err() {
+                break;
+            }
+        }
+        saved.map(|_| report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, model, TempDir};
+
+    fn mirrored(dest: &Path, owner: &str, name: &str, updated_at: &str) -> MirroredModel {
+        let path = dest.join(name);
+        fs::create_dir_all(&path).unwrap();
+        MirroredModel {
+            owner: owner.to_owned(),
+            name: name.to_owned(),
+            version: 1,
+            updated_at: updated_at.to_owned(),
+            path,
+        }
+    }
+
+    #[test]
+    fn plan_mirror_catalog_leaves_disk_untouched() {
+        let dir = TempDir::new();
+        let dest = dir.0.join("mirror");
+        let mut state = MirrorState::default();
+        for (name, updated_at) in [("a", "1"), ("b", "1"), ("d", "1")] {
+            let mirrored = mirrored(&dest, "o", name, updated_at);
+            state.models.insert(MirrorState::key("o", name), mirrored);
+        }
+        state.save(&dest).unwrap();
+        let saved = fs::read(dest.join(STATE_FILE)).unwrap();
+        let mut c = model("o", "c", "1");
+        c.filesize = 10;
+        let mut b = model("o", "b", "2");
+        b.filesize = 5;
+        let catalog = vec![model("o", "a", "1"), b, c];
+        let client = testing::builder(&dir, move |req| Ok(testing::listing(req, &catalog))).build();
+        let plan = client
+            .plan_mirror_catalog_blocking(&MirrorFilter::default(), &dest)
+            .unwrap();
+        let names = |requests: &[DownloadRequest]| {
+            requests.iter().map(|r| r.name.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(names(&plan.fetch), ["b", "c"]);
+        assert_eq!(plan.fetch_size, 15);
+        assert_eq!(names(&plan.up_to_date), ["a"]);
+        assert_eq!(plan.prune.len(), 1);
+        assert_eq!(plan.prune[0].name, "d");
+        // Neither the state nor the mirrored models were touched, no checkpoint was written
+        assert_eq!(fs::read(dest.join(STATE_FILE)).unwrap(), saved);
+        assert!(dest.join("d").exists());
+        assert!(!dest.join(CRAWL_CHECKPOINT_FILE).exists());
+    }
+
+    #[test]
+    fn mirror_progress_spans_batches() {
+        let dir = TempDir::new();
+        let dest = dir.0.join("mirror");
+        let catalog = (0..SAVE_EVERY + 4)
+            .map(|idx| model("o", &format!("m{idx}"), "1"))
+            .collect::<Vec<_>>();
+        let archive = testing::zip_archive(&[("model.sdf", "<sdf/>")]);
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let client = testing::builder(&dir, move |req| {
+            Ok(match req.url.ends_with(".zip") {
+                true => testing::response(&req.url, 200, archive.clone()),
+                false => testing::listing(req, &catalog),
+            })
+        })
+        .download_progress(tx)
+        .build();
+        let report = client.mirror_owner_blocking("o", &dest, 2).unwrap();
+        assert_eq!(report.downloaded.len(), SAVE_EVERY + 4);
+        drop(client);
+        let mut completed = rx
+            .into_iter()
+            .inspect(|progress| assert_eq!(progress.total, SAVE_EVERY + 4))
+            .map(|progress| progress.completed)
+            .collect::<Vec<_>>();
+        completed.sort();
+        assert_eq!(completed, (1..=SAVE_EVERY + 4).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn load_state_without_names() {
+        let dir = TempDir::new();
+        let state = r#"{"models": {"openrobotics/table": {"version": 2,
+            "updated_at": "2024-01-01", "path": "/mirror/table"}}}"#;
+        fs::write(dir.0.join(STATE_FILE), state).unwrap();
+        let state = MirrorState::load(&dir.0).unwrap();
+        let mirrored = &state.models["openrobotics/table"];
+        assert_eq!(
+            (mirrored.owner.as_str(), mirrored.name.as_str()),
+            ("openrobotics", "table")
+        );
+        assert_eq!(mirrored.version, 2);
+    }
+
+    #[test]
+    fn unparsable_states_are_kept() {
+        let dir = TempDir::new();
+        fs::write(dir.0.join(STATE_FILE), "{ \"models\": ").unwrap();
+        assert!(matches!(
+            MirrorState::load(&dir.0),
+            Err(FuelError::Parse(_))
+        ));
+        let catalog = vec![model("o", "a", "1")];
+        let client = testing::builder(&dir, move |req| Ok(testing::listing(req, &catalog))).build();
+        let report = client.mirror_owner_blocking("o", &dir.0, 1);
+        assert!(matches!(report, Err(FuelError::Parse(_))));
+        let state = fs::read_to_string(dir.0.join(STATE_FILE)).unwrap();
+        assert_eq!(state, "{ \"models\": ");
+    }
+
+    #[test]
+    fn plan_mirror_owner_of_missing_mirror() {
+        let dir = TempDir::new();
+        let dest = dir.0.join("mirror");
+        let catalog = vec![model("o", "a", "1")];
+        let client = testing::builder(&dir, move |req| Ok(testing::listing(req, &catalog))).build();
+        let plan = client.plan_mirror_owner_blocking("o", &dest).unwrap();
+        assert_eq!(plan.fetch, [DownloadRequest::new("o", "a")]);
+        assert!(plan.prune.is_empty());
+        assert!(!dest.exists());
+    }
+}
@@ -0,0 +1,221 @@
+use itertools::Itertools;
+use std::{
+    fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+#[cfg(feature = "client")]
+use crate::FuelClient;
+use crate::{asset, intern::intern_assets, FuelError, FuelModel};
+
+/// Models of a cache file, loaded and queried without a client or any network access, i.e. for
+/// tools that only read a cache built by someone else. Available without the default `client`
+/// feature, which brings in the HTTP stack. Clients keep their models in one, see
+/// `FuelClient::models`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelCache {
+    pub models: Vec<FuelModel>,
+}
+
+impl ModelCache {
+    pub fn new(models: Vec<FuelModel>) -> Self {
+        Self {
+            models: intern_assets(models),
+        }
+    }
+
+    /// Reads a cache file written by a client, models or pretty printed
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, FuelError> {
+        let bytes = fs::read(path).map_err(|e| FuelError::Io(e.to_string()))?;
+        let models = serde_json::de::from_slice::<Vec<FuelModel>>(&bytes)
+            .map_err(|e| FuelError::Parse(e.to_string()))?;
+        Ok(Self::new(models))
+    }
+
+    /// Reads the cache file of the default server at its default location
+    pub fn load_default() -> Result<Self, FuelError> {
+        let path =
+            default_cache_path().ok_or_else(|| FuelError::Io("no cache path available".into()))?;
+        Self::load(path)
+    }
+
+    /// Writes the models to `path` atomically, creating its directory if needed. A file that
+    /// already has the same content is only marked as modified.
+    pub fn save(&self, path: impl AsRef<Path>, pretty: bool) -> Result<(), FuelError> {
+        let bytes = match pretty {
+            true => serde_json::ser::to_string_pretty(&self.models),
+            false => serde_json::ser::to_string(&self.models),
+        }
+        .map_err(|e| FuelError::Io(e.to_string()))?;
+        write_cache_file(path.as_ref(), bytes.as_bytes())
+    }
+
+    pub fn len(&self) -> usize {
+        self.models.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
+    }
+
+    /// Finds a model by owner and name, both compared case insensitively
+    pub fn find(&self, owner: &str, name: &str) -> Option<&FuelModel> {
+        asset::find_asset(&self.models, owner, name)
+    }
+
+    /// Models matching `filter`, in cache order
+    pub fn filter(&self, filter: impl Fn(&FuelModel) -> bool) -> Vec<FuelModel> {
+        self.models
+            .iter()
+            .filter(|model| filter(model))
+            .cloned()
+            .collect()
+    }
+
+    pub fn by_owner(&self, owner: &str) -> Vec<FuelModel> {
+        asset::assets_by_owner(&self.models, owner)
+    }
+
+    pub fn by_private(&self, private: bool) -> Vec<FuelModel> {
+        asset::assets_by_private(&self.models, private)
+    }
+
+    pub fn by_tag(&self, tag: &str) -> Vec<FuelModel> {
+        asset::assets_by_tag(&self.models, tag)
+    }
+
+    /// Models with a metadata entry matching both `key` and `value`
+    pub fn by_metadata(&self, key: &str, value: &str) -> Vec<FuelModel> {
+        models_by_metadata(&self.models, key, value)
+    }
+
+    /// Owners of the models, sorted case insensitively
+    pub fn owners(&self) -> Vec<String> {
+        self.models
+            .iter()
+            .unique_by(|model| &model.owner)
+            .map(|model| model.owner.to_string())
+            .sorted_by(|a, b| a.to_lowercase().cmp(&b.to_lowercase()))
+            .collect()
+    }
+
+    /// Tags of the models, sorted case insensitively
+    pub fn tags(&self) -> Vec<String> {
+        self.models
+            .iter()
+            .flat_map(|model| &model.tags)
+            .unique()
+            .map(|tag| tag.to_string())
+            .sorted_by(|a, b| a.to_lowercase().cmp(&b.to_lowercase()))
+            .collect()
+    }
+}
+
+impl Deref for ModelCache {
+    type Target = [FuelModel];
+
+    fn deref(&self) -> &[FuelModel] {
+        &self.models
+    }
+}
+
+impl From<Vec<FuelModel>> for ModelCache {
+    fn from(models: Vec<FuelModel>) -> Self {
+        Self::new(models)
+    }
+}
+
+/// Cache file of the default server, in the platform cache directory
+pub(crate) fn default_cache_path() -> Option<PathBuf> {
+    let mut p = dirs::cache_dir()?;
+    p.push("open-robotics");
+    p.push("gz-fuel");
+    p.push("model_cache.json");
+    Some(p)
+}
+
+/// Writes one of the cache files, creating its directory if needed. A file that already has the
+/// same content is only marked as modified, so `should_update_cache` still sees the refresh
+/// without the file being rewritten.
+pub(crate) fn write_cache_file(path: &Path, bytes: &[u8]) -> Result<(), FuelError> {
+    let io_err = |e: std::io::Error| FuelError::Io(e.to_string());
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(io_err)?;
+    }
+    if fs::read(path).is_ok_and(|existing| existing == bytes) {
+        let touched = fs::File::options()
+            .append(true)
+            .open(path)
+            .and_then(|f| f.set_modified(SystemTime::now()));
+        if touched.is_ok() {
+            return Ok(());
+        }
+    }
+    // Replace the file atomically, memory mapped readers keep seeing the previous version
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, bytes).map_err(io_err)?;
+    fs::rename(tmp, path).map_err(io_err)
+}
+
+/// Models of `models` with a metadata entry matching both `key` and `value`
+pub(crate) fn models_by_metadata(models: &[FuelModel], key: &str, value: &str) -> Vec<FuelModel> {
+    models
+        .iter()
+        .filter(|model| model.has_metadata(key, value))
+        .cloned()
+        .collect()
+}
+
+#[cfg(feature = "client")]
+impl FuelClient {
+    /// Copy of the cached models, None if there is no cache
+    pub fn model_cache(&self) -> Option<ModelCache> {
+        self.models().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{model, TempDir},
+        ModelMetadata,
+    };
+
+    #[test]
+    fn metadata_keys_can_repeat() {
+        let entry = |key: &str, value: &str| ModelMetadata {
+            key: key.into(),
+            value: value.to_owned(),
+        };
+        let mut labeled = model("o", "labeled", "");
+        labeled.metadata = vec![entry("label", "chair"), entry("label", "furniture")];
+        let cache = ModelCache::new(vec![labeled, model("o", "plain", "")]);
+        let names = |models: Vec<FuelModel>| models.into_iter().map(|m| m.name).collect::<Vec<_>>();
+        assert_eq!(names(cache.by_metadata("label", "chair")), ["labeled"]);
+        assert_eq!(names(cache.by_metadata("label", "furniture")), ["labeled"]);
+        assert!(cache.by_metadata("label", "table").is_empty());
+        assert_eq!(cache.models[0].metadata_value("label"), Some("chair"));
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dir = TempDir::new();
+        let path = dir.0.join("cache").join("model_cache.json");
+        let cache = ModelCache::new(vec![model("o", "Table", "1"), model("o", "Chair", "2")]);
+        cache.save(&path, false).unwrap();
+        assert_eq!(ModelCache::load(&path), Ok(cache.clone()));
+        // Saving the same models again leaves the file as is
+        let written = fs::metadata(&path).unwrap().len();
+        cache.save(&path, false).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().len(), written);
+        assert!(!path.with_extension("json.tmp").exists());
+        assert_eq!(
+            cache.find("O", "table").map(|m| m.name.as_str()),
+            Some("Table")
+        );
+        assert_eq!(cache.owners(), ["o"]);
+    }
+}
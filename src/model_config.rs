@@ -0,0 +1,237 @@
+use futures_lite::future;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{header_version, uri, AssetKind, FuelClient, FuelError, FuelModel};
+
+/// Name of the metadata file at the root of model archives
+pub const MODEL_CONFIG_FILE: &str = "model.config";
+
+/// Content of a `model.config` file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelConfig {
+    pub name: String,
+    /// Version of the model as declared by its authors, unrelated to the Fuel version
+    pub version: Option<String>,
+    pub authors: Vec<ModelAuthor>,
+    pub description: String,
+    /// SDF files of the model, usually one per supported SDF spec version
+    pub sdf: Vec<SdfFile>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelAuthor {
+    pub name: String,
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SdfFile {
+    /// Path relative to the model directory
+    pub path: String,
+    /// SDF spec version the file targets, i.e. `1.6`
+    pub version: Option<String>,
+}
+
+impl ModelConfig {
+    pub fn parse(xml: &str) -> Result<Self, FuelError> {
+        let doc = roxmltree::Document::parse(xml).map_err(|e| FuelError::Parse(e.to_string()))?;
+        let root = doc.root_element();
+        if !root.has_tag_name("model") {
+            return Err(FuelError::Parse(format!(
+                "expected a <model> root element, found <{}>",
+                root.tag_name().name()
+            )));
+        }
+        let text = |node: roxmltree::Node, tag: &str| {
+            node.children()
+                .find(|child| child.has_tag_name(tag))
+                .and_then(|child| child.text())
+                .map(|text| text.trim().to_owned())
+        };
+        let elements = |tag: &'static str| root.children().filter(move |c| c.has_tag_name(tag));
+        Ok(Self {
+            name: text(root, "name").unwrap_or_default(),
+            version: text(root, "version"),
+            authors: elements("author")
+                .map(|author| ModelAuthor {
+                    name: text(author, "name").unwrap_or_default(),
+                    email: text(author, "email"),
+                })
+                .collect(),
+            description: text(root, "description").unwrap_or_default(),
+            sdf: elements("sdf")
+                .filter_map(|sdf| {
+                    Some(SdfFile {
+                        path: sdf.text()?.trim().to_owned(),
+                        version: sdf.attribute("version").map(String::from),
+                    })
+                })
+                .collect(),
+        })
+    }
+
+    /// Whether the model can be loaded by a simulator supporting the `sdf_version` spec, i.e. it
+    /// has an SDF file targeting that version or an older one. SDF files without a version are
+    /// assumed to be compatible.
+    pub fn supports_sdf(&self, sdf_version: &str) -> bool {
+        let target = parse_version(sdf_version);
+        self.sdf.iter().any(|sdf| {
+            sdf.version
+                .as_deref()
+                .is_none_or(|version| parse_version(version) <= target)
+        })
+    }
+
+    /// Loads the `model.config` of an extracted model directory
+    pub fn load(model_dir: &Path) -> Result<Self, FuelError> {
+        let xml = fs::read_to_string(model_dir.join(MODEL_CONFIG_FILE))
+            .map_err(|e| FuelError::Io(e.to_string()))?;
+        Self::parse(&xml)
+    }
+}
+
+fn parse_version(version: &str) -> Vec<u32> {
+    version
+        .trim()
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+impl FuelClient {
+    /// Configs are stored in a `model_configs` directory next to the model cache file, keyed by
+    /// owner, model and version like thumbnails
+    fn model_config_dir(&self, owner: &str, name: &str) -> Option<PathBuf> {
+        let cache_path = self.cache_path.clone().or_else(Self::default_cache_path)?;
+        Some(
+            cache_path
+                .parent()?
+                .join("model_configs")
+                .join(self.host())
+                .join(owner.to_lowercase())
+                .join(name.to_lowercase()),
+        )
+    }
+
+    /// Stores the `model.config` of a model version, best effort
+    pub(crate) fn store_model_config(&self, owner: &str, name: &str, version: u32, xml: &[u8]) {
+        if let Some(dir) = self.model_config_dir(owner, name) {
+            let dir = dir.join(version.to_string());
+            fs::create_dir_all(&dir)
+                .and_then(|_| fs::write(dir.join(MODEL_CONFIG_FILE), xml))
+                .ok();
+        }
+    }
+
+    /// `model.config` of the latest version of a model that was fetched or downloaded so far
+    pub fn cached_model_config(&self, owner: &str, name: &str) -> Option<ModelConfig> {
+        let dir = self.model_config_dir(owner, name)?;
+        let (_, path) = fs::read_dir(dir)
+            .ok()?
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let version = entry.file_name().to_str()?.parse::<u32>().ok()?;
+                let path = entry.path();
+                path.join(MODEL_CONFIG_FILE)
+                    .is_file()
+                    .then_some((version, path))
+            })
+            .max_by_key(|(version, _)| *version)?;
+        ModelConfig::load(&path).ok()
+    }
+
+    /// Fetches the `model.config` of the latest version of a model without downloading the
+    /// whole archive, the config is stored for `cached_model_config` if the server reports its
+    /// version
+    pub async fn fetch_model_config(
+        &self,
+        owner: &str,
+        name: &str,
+    ) -> Result<ModelConfig, FuelError> {
+        let url = self.endpoint(&format!(
+            "{}/tip/files/{MODEL_CONFIG_FILE}",
+            uri::asset_path(AssetKind::Model, owner, name)
+        ));
+        let res = self.fetch(self.get_request(url)).await?;
+        if !res.ok {
+            return Err(self.asset_error(AssetKind::Model, owner, name, res.status));
+        }
+        let config = ModelConfig::parse(&String::from_utf8_lossy(&res.bytes))?;
+        // Without the version the config can't be told apart from the ones of other versions
+        if let Some(version) = header_version(&res.headers) {
+            self.store_model_config(owner, name, version, &res.bytes);
+        }
+        Ok(config)
+    }
+
+    pub fn fetch_model_config_blocking(
+        &self,
+        owner: &str,
+        name: &str,
+    ) -> Result<ModelConfig, FuelError> {
+        future::block_on(self.fetch_model_config(owner, name))
+    }
+
+    /// Models compatible with the `sdf_version` spec, see `ModelConfig::supports_sdf`. Only the
+    /// configs that were already fetched or downloaded are used, models without one are left out.
+    pub fn models_by_sdf_version(
+        &self,
+        models: Option<&Vec<FuelModel>>,
+        sdf_version: &str,
+    ) -> Option<Vec<FuelModel>> {
+        let cached = self.models();
+        let models = models.map(Vec::as_slice).or(cached.as_deref())?;
+        Some(
+            models
+                .iter()
+                .filter(|model| {
+                    self.cached_model_config(&model.owner, &model.name)
+                        .is_some_and(|config| config.supports_sdf(sdf_version))
+                })
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, response, TempDir};
+
+    const CONFIG: &str = "<model><name>Table</name><sdf version='1.9'>model.sdf</sdf></model>";
+
+    fn client(dir: &TempDir, version: Option<&'static str>) -> FuelClient {
+        testing::builder(dir, move |req| {
+            let mut res = response(&req.url, 200, CONFIG);
+            if let Some(version) = version {
+                res.headers.insert("X-Ign-Resource-Version", version);
+            }
+            Ok(res)
+        })
+        .build()
+    }
+
+    #[test]
+    fn fetch_model_config_stores_known_version() {
+        let dir = TempDir::new();
+        let config = client(&dir, Some("3")).fetch_model_config_blocking("o", "Table");
+        assert_eq!(config.unwrap().name, "Table");
+        let stored = dir
+            .0
+            .join("model_configs/fuel.test/o/table/3")
+            .join(MODEL_CONFIG_FILE);
+        assert!(stored.is_file());
+    }
+
+    #[test]
+    fn fetch_model_config_without_version() {
+        let dir = TempDir::new();
+        let client = client(&dir, None);
+        assert!(client.fetch_model_config_blocking("o", "Table").is_ok());
+        assert_eq!(client.cached_model_config("o", "Table"), None);
+    }
+}
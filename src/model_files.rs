@@ -0,0 +1,85 @@
+use std::{fs, path::Path};
+
+use crate::{
+    upload::{files_in, relative_path},
+    DownloadedModel, FuelError, MODEL_CONFIG_FILE,
+};
+
+/// Kind of a file inside a model directory, guessed from its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModelFileKind {
+    /// `.dae`, `.obj`, `.stl`, `.fbx`, `.glb` or `.gltf` files
+    Mesh,
+    /// Image files, i.e. `.png` or `.jpg`
+    Texture,
+    Sdf,
+    /// The `model.config` file
+    Config,
+    Other,
+}
+
+impl ModelFileKind {
+    pub fn from_path(path: &str) -> Self {
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+        if file_name == MODEL_CONFIG_FILE {
+            return ModelFileKind::Config;
+        }
+        let extension = file_name
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_ascii_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "dae" | "obj" | "stl" | "fbx" | "glb" | "gltf" => ModelFileKind::Mesh,
+            "png" | "jpg" | "jpeg" | "tga" | "bmp" | "tif" | "tiff" | "dds" | "ktx" | "exr"
+            | "hdr" => ModelFileKind::Texture,
+            "sdf" | "world" => ModelFileKind::Sdf,
+            _ => ModelFileKind::Other,
+        }
+    }
+}
+
+/// A file of a downloaded model
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelFile {
+    /// Path relative to the model directory, with `/` separators
+    pub path: String,
+    /// Size in bytes
+    pub size: u64,
+    pub kind: ModelFileKind,
+}
+
+/// All the files of an extracted model directory, sorted by path. Files added by the client, i.e.
+/// the content store manifest, are left out, see `files_in`.
+pub fn model_files(model_dir: impl AsRef<Path>) -> Result<Vec<ModelFile>, FuelError> {
+    let model_dir = model_dir.as_ref();
+    files_in(model_dir)?
+        .into_iter()
+        .map(|path| {
+            let size = fs::metadata(&path)
+                .map_err(|e| FuelError::Io(e.to_string()))?
+                .len();
+            let relative = relative_path(model_dir, &path);
+            Ok(ModelFile {
+                kind: ModelFileKind::from_path(&relative),
+                path: relative,
+                size,
+            })
+        })
+        .collect()
+}
+
+impl DownloadedModel {
+    /// All the files of the downloaded model, see `model_files`
+    pub fn files(&self) -> Result<Vec<ModelFile>, FuelError> {
+        model_files(&self.path)
+    }
+
+    /// Files of the downloaded model of the given kind, i.e. all its meshes
+    pub fn files_of_kind(&self, kind: ModelFileKind) -> Result<Vec<ModelFile>, FuelError> {
+        Ok(self
+            .files()?
+            .into_iter()
+            .filter(|file| file.kind == kind)
+            .collect())
+    }
+}
@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{FuelClient, FuelError, FuelModel};
+
+/// Extension of the annotations file, stored next to the cache file
+const ANNOTATIONS_FILE: &str = "notes.json";
+
+/// Local note and tags of a model, never sent to the server
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Annotation {
+    pub owner: String,
+    pub name: String,
+    #[serde(default)]
+    pub note: String,
+    /// Kept apart from the server tags of the model, i.e. `physics-validated`
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl Annotation {
+    fn is_empty(&self) -> bool {
+        self.note.is_empty() && self.tags.is_empty()
+    }
+}
+
+/// Annotations keyed by lowercase `owner/name`, stored next to the cache file so cache refreshes
+/// keep them
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Annotations {
+    pub models: BTreeMap<String, Annotation>,
+}
+
+fn annotation_key(owner: &str, name: &str) -> String {
+    format!("{}/{}", owner.to_lowercase(), name.to_lowercase())
+}
+
+impl Annotations {
+    pub fn get(&self, owner: &str, name: &str) -> Option<&Annotation> {
+        self.models.get(&annotation_key(owner, name))
+    }
+}
+
+impl FuelClient {
+    /// Empty if there are none or the annotations file can't be parsed, the methods editing
+    /// annotations report the parse error instead
+    pub fn annotations(&self) -> Annotations {
+        self.read_sidecar(ANNOTATIONS_FILE).unwrap_or_default()
+    }
+
+    pub fn annotation(&self, owner: &str, name: &str) -> Option<Annotation> {
+        self.annotations().get(owner, name).cloned()
+    }
+
+    /// Applies `edit` to the annotation of a model, annotations left empty are removed
+    fn edit_annotation<T>(
+        &self,
+        owner: &str,
+        name: &str,
+        edit: impl FnOnce(&mut Annotation) -> T,
+    ) -> Result<T, FuelError> {
+        self.update_sidecar(ANNOTATIONS_FILE, |annotations: &mut Annotations| {
+            let key = annotation_key(owner, name);
+            let annotation = annotations
+                .models
+                .entry(key.clone())
+                .or_insert_with(|| Annotation {
+                    owner: owner.to_owned(),
+                    name: name.to_owned(),
+                    ..Default::default()
+                });
+            let res = edit(annotation);
+            if annotation.is_empty() {
+                annotations.models.remove(&key);
+            }
+            res
+        })
+    }
+
+    /// Replaces the note of a model, an empty note removes it
+    pub fn set_note(&self, owner: &str, name: &str, note: &str) -> Result<(), FuelError> {
+        self.edit_annotation(owner, name, |annotation| {
+            annotation.note = note.to_owned();
+        })
+    }
+
+    /// Returns false if the model already had the tag
+    pub fn add_local_tag(&self, owner: &str, name: &str, tag: &str) -> Result<bool, FuelError> {
+        self.edit_annotation(owner, name, |annotation| {
+            let added = !annotation.tags.iter().any(|t| t == tag);
+            if added {
+                annotation.tags.push(tag.to_owned());
+            }
+            added
+        })
+    }
+
+    /// Returns false if the model didn't have the tag
+    pub fn remove_local_tag(&self, owner: &str, name: &str, tag: &str) -> Result<bool, FuelError> {
+        self.edit_annotation(owner, name, |annotation| {
+            let len = annotation.tags.len();
+            annotation.tags.retain(|t| t != tag);
+            annotation.tags.len() != len
+        })
+    }
+
+    /// Models with the given local tag, see `add_local_tag`
+    pub fn models_by_local_tag(
+        &self,
+        models: Option<&Vec<FuelModel>>,
+        tag: &str,
+    ) -> Option<Vec<FuelModel>> {
+        self.models_by_annotation(models, |annotation| {
+            annotation.tags.iter().any(|t| t == tag)
+        })
+    }
+
+    /// Models whose note contains `text`, compared case insensitively
+    pub fn models_by_note(
+        &self,
+        models: Option<&Vec<FuelModel>>,
+        text: &str,
+    ) -> Option<Vec<FuelModel>> {
+        let text = text.to_lowercase();
+        self.models_by_annotation(models, |annotation| {
+            annotation.note.to_lowercase().contains(&text)
+        })
+    }
+
+    fn models_by_annotation(
+        &self,
+        models: Option<&Vec<FuelModel>>,
+        filter: impl Fn(&Annotation) -> bool,
+    ) -> Option<Vec<FuelModel>> {
+        let annotations = self.annotations();
+        let cached = self.models();
+        let models = models.map(Vec::as_slice).or(cached.as_deref())?;
+        Some(
+            models
+                .iter()
+                .filter(|model| {
+                    annotations
+                        .get(&model.owner, &model.name)
+                        .is_some_and(&filter)
+                })
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TempDir};
+
+    #[test]
+    fn empty_annotations_are_removed() {
+        let dir = TempDir::new();
+        let client = testing::builder(&dir, |_| Err("offline".into())).build();
+        client.set_note("Owner", "Table", "wobbly").unwrap();
+        assert_eq!(
+            client.annotation("owner", "table"),
+            Some(Annotation {
+                owner: "Owner".into(),
+                name: "Table".into(),
+                note: "wobbly".into(),
+                tags: Vec::new(),
+            })
+        );
+        assert_eq!(client.add_local_tag("owner", "table", "checked"), Ok(true));
+        assert_eq!(client.add_local_tag("owner", "table", "checked"), Ok(false));
+        client.set_note("owner", "table", "").unwrap();
+        assert_eq!(
+            client.annotation("owner", "table").unwrap().tags,
+            ["checked"]
+        );
+        assert_eq!(
+            client.remove_local_tag("owner", "table", "checked"),
+            Ok(true)
+        );
+        assert_eq!(client.annotation("owner", "table"), None);
+    }
+}
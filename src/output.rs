@@ -0,0 +1,108 @@
+//! Results of the CLI commands, printed as text or as JSON with `--json`. The JSON schemas are
+//! stable, fields are only ever added.
+
+use gz_fuel::{DownloadPlan, DownloadResult, PlannedAction};
+use serde::Serialize;
+use std::path::PathBuf;
+
+#[derive(Serialize)]
+pub struct UpdateOutput {
+    /// Whether the cache was older than the threshold and got refreshed
+    pub updated: bool,
+    /// Number of cached models after the command
+    pub models: usize,
+    /// `owner/name` of the cached models that were removed from the server
+    pub removed: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Fetch,
+    Replace,
+}
+
+/// Entry of a dry run, `error` is set if the download couldn't be resolved
+#[derive(Serialize)]
+pub struct PlanOutput {
+    pub owner: String,
+    pub name: String,
+    pub version: Option<u32>,
+    pub action: Option<Action>,
+    pub size: Option<u64>,
+    pub path: Option<PathBuf>,
+    pub error: Option<String>,
+}
+
+impl From<DownloadPlan> for PlanOutput {
+    fn from(plan: DownloadPlan) -> Self {
+        let request = plan.request;
+        let mut output = Self {
+            owner: request.owner,
+            name: request.name,
+            version: request.version,
+            action: None,
+            size: None,
+            path: None,
+            error: None,
+        };
+        match plan.result {
+            Ok(planned) => {
+                output.version = Some(planned.version);
+                output.action = Some(match planned.action {
+                    PlannedAction::Fetch => Action::Fetch,
+                    PlannedAction::Replace => Action::Replace,
+                });
+                output.size = planned.size;
+                output.path = Some(planned.path);
+            }
+            Err(e) => output.error = Some(e.to_string()),
+        }
+        output
+    }
+}
+
+/// Entry of a download, `error` is set if the download failed
+#[derive(Serialize)]
+pub struct DownloadOutput {
+    pub owner: String,
+    pub name: String,
+    pub version: Option<u32>,
+    pub path: Option<PathBuf>,
+    pub error: Option<String>,
+}
+
+impl From<DownloadResult> for DownloadOutput {
+    fn from(result: DownloadResult) -> Self {
+        let request = result.request;
+        match result.result {
+            Ok(model) => Self {
+                owner: request.owner,
+                name: request.name,
+                version: Some(model.version),
+                path: Some(model.path),
+                error: None,
+            },
+            Err(e) => Self {
+                owner: request.owner,
+                name: request.name,
+                version: request.version,
+                path: None,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+}
+
+/// Printed instead of the command output when a command fails as a whole
+#[derive(Serialize)]
+pub struct ErrorOutput {
+    pub error: String,
+}
+
+pub fn print_json(value: &impl Serialize) {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => println!("{json}"),
+        Err(e) => eprintln!("Failed serializing output: {e}"),
+    }
+}
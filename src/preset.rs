@@ -0,0 +1,122 @@
+use futures_lite::future;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{fuel_client::read_json_file, FuelClient, FuelError, FuelModel, ModelQuery};
+
+/// A saved query, recalled by name with `FuelClient::run_preset`
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct QueryPreset {
+    #[serde(flatten)]
+    pub query: ModelQuery,
+    /// Maximum number of models returned, all the matching models if None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+impl QueryPreset {
+    pub fn new(query: ModelQuery, limit: Option<usize>) -> Self {
+        Self { query, limit }
+    }
+}
+
+/// Presets keyed by name. The file is pretty printed so it can be edited by hand and shared.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct QueryPresets {
+    pub presets: BTreeMap<String, QueryPreset>,
+}
+
+impl FuelClient {
+    /// `presets.json` in the platform config directory, unless `presets_path` is set
+    pub(crate) fn default_presets_path() -> Option<PathBuf> {
+        let mut p = dirs::config_dir()?;
+        p.push("open-robotics");
+        p.push("gz-fuel");
+        p.push("presets.json");
+        Some(p)
+    }
+
+    fn presets_file(&self) -> Result<PathBuf, FuelError> {
+        self.presets_path
+            .clone()
+            .or_else(Self::default_presets_path)
+            .ok_or_else(|| FuelError::Io("no config path available".into()))
+    }
+
+    /// Saved presets, empty if there are none. Fails with `FuelError::Parse` if the presets file
+    /// can't be parsed, i.e. after a bad hand edit.
+    pub fn presets(&self) -> Result<QueryPresets, FuelError> {
+        let presets = read_json_file(&self.presets_file()?)?;
+        Ok(presets.unwrap_or_default())
+    }
+
+    /// Saves a preset, replacing any preset with the same name. A presets file that can't be
+    /// parsed is never overwritten.
+    pub fn save_preset(&self, name: &str, preset: QueryPreset) -> Result<(), FuelError> {
+        self.update_json_file(&self.presets_file()?, |presets: &mut QueryPresets| {
+            presets.presets.insert(name.to_owned(), preset);
+        })
+    }
+
+    /// Returns false if there was no such preset
+    pub fn remove_preset(&self, name: &str) -> Result<bool, FuelError> {
+        self.update_json_file(&self.presets_file()?, |presets: &mut QueryPresets| {
+            presets.presets.remove(name).is_some()
+        })
+    }
+
+    /// Runs the saved preset `name` against the server, see `query_models`. Fails with
+    /// `FuelError::Config` if there is no such preset.
+    pub async fn run_preset(&self, name: &str) -> Result<Vec<FuelModel>, FuelError> {
+        let preset = self
+            .presets()?
+            .presets
+            .remove(name)
+            .ok_or_else(|| FuelError::Config(format!("no preset named {name}")))?;
+        self.query_models(&preset.query, preset.limit.unwrap_or(usize::MAX))
+            .await
+    }
+
+    pub fn run_preset_blocking(&self, name: &str) -> Result<Vec<FuelModel>, FuelError> {
+        future::block_on(self.run_preset(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TempDir};
+    use std::fs;
+
+    #[test]
+    fn save_and_remove_presets() {
+        let dir = TempDir::new();
+        let client = testing::builder(&dir, |_| Err("offline".into()))
+            .presets_path(dir.0.join("presets.json"))
+            .build();
+        assert_eq!(client.presets(), Ok(QueryPresets::default()));
+        let preset = QueryPreset::new(ModelQuery::default(), Some(3));
+        client.save_preset("mine", preset.clone()).unwrap();
+        assert_eq!(client.presets().unwrap().presets["mine"], preset);
+        assert_eq!(client.remove_preset("mine"), Ok(true));
+        assert_eq!(client.remove_preset("mine"), Ok(false));
+    }
+
+    #[test]
+    fn unparsable_presets_are_kept() {
+        let dir = TempDir::new();
+        let path = dir.0.join("presets.json");
+        fs::write(&path, "{ \"presets\": ").unwrap();
+        let client = testing::builder(&dir, |_| Err("offline".into()))
+            .presets_path(&path)
+            .build();
+        assert!(matches!(client.presets(), Err(FuelError::Parse(_))));
+        let preset = QueryPreset::new(ModelQuery::default(), None);
+        assert!(matches!(
+            client.save_preset("mine", preset),
+            Err(FuelError::Parse(_))
+        ));
+        assert!(client.remove_preset("mine").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{ \"presets\": ");
+    }
+}
@@ -0,0 +1,63 @@
+//! Adapters driving `indicatif` progress bars from the progress events, built with the
+//! `indicatif` feature. Every adapter returns the sender to hand to the client and the thread
+//! updating the bar, the bar is finished once all the clones of the sender are dropped. Senders
+//! given to the builder are only dropped with the client.
+
+use crossbeam_channel::Sender;
+use indicatif::ProgressBar;
+use std::thread::{self, JoinHandle};
+
+use crate::{DownloadProgress, FuelModel, PageProgress};
+
+fn drive<T: Send + 'static>(
+    bar: ProgressBar,
+    update: impl Fn(&ProgressBar, T) + Send + 'static,
+) -> (Sender<T>, JoinHandle<()>) {
+    let (tx, rx) = crossbeam_channel::unbounded::<T>();
+    let handle = thread::spawn(move || {
+        for event in rx {
+            update(&bar, event);
+        }
+        bar.finish();
+    });
+    (tx, handle)
+}
+
+/// Pages of listing crawls, for `FuelClientBuilder::page_progress`. The length of the bar is the
+/// last page once the server reported it, the message is the number of fetched entries.
+pub fn page_bar(bar: ProgressBar) -> (Sender<PageProgress>, JoinHandle<()>) {
+    drive(bar, |bar, progress: PageProgress| {
+        if let Some(last) = progress.last_page {
+            bar.set_length(last as u64);
+        }
+        bar.set_position(progress.page as u64);
+        bar.set_message(format!("{} entries", progress.fetched));
+    })
+}
+
+/// Models of cache updates, for `FuelClientBuilder::progress` or the update calls. The server
+/// doesn't report the size of the catalog, so the bar counts up without a length unless one is
+/// set, i.e. the size of the previous cache.
+pub fn model_bar(bar: ProgressBar) -> (Sender<FuelModel>, JoinHandle<()>) {
+    drive(bar, |bar, model: FuelModel| {
+        bar.inc(1);
+        bar.set_message(format!("{}/{}", model.owner, model.name));
+    })
+}
+
+/// Batch downloads and mirror syncs, for `FuelClientBuilder::download_progress` or the
+/// `_with_progress` calls. The message is the last finished asset.
+pub fn download_bar(bar: ProgressBar) -> (Sender<DownloadProgress>, JoinHandle<()>) {
+    drive(bar, |bar, progress: DownloadProgress| {
+        bar.set_length(progress.total as u64);
+        bar.set_position(progress.completed as u64);
+        let status = match progress.succeeded {
+            true => "",
+            false => " failed",
+        };
+        bar.set_message(format!(
+            "{}/{}{status}",
+            progress.request.owner, progress.request.name
+        ));
+    })
+}
@@ -0,0 +1,248 @@
+//! `gz_fuel` Python module, built with the `python` feature. Blocking calls release the GIL so
+//! other Python threads keep running during downloads and refreshes.
+
+use pyo3::{create_exception, exceptions::PyException, prelude::*};
+use std::{path::PathBuf, time::Duration};
+
+use crate::{DownloadRequest, DownloadedModel, FuelClient, FuelModel};
+
+create_exception!(
+    gz_fuel,
+    FuelError,
+    PyException,
+    "Error returned by the Fuel client"
+);
+
+impl From<crate::FuelError> for PyErr {
+    fn from(e: crate::FuelError) -> Self {
+        FuelError::new_err(e.to_string())
+    }
+}
+
+/// Metadata of a model, as cached
+#[pyclass(name = "Model", module = "gz_fuel", get_all, frozen)]
+#[derive(Clone)]
+pub struct PyModel {
+    owner: String,
+    name: String,
+    description: String,
+    tags: Vec<String>,
+    categories: Vec<String>,
+    /// `(key, value)` pairs
+    metadata: Vec<(String, String)>,
+    likes: u32,
+    downloads: u32,
+    filesize: usize,
+    license_name: String,
+    private: bool,
+    created_at: String,
+    updated_at: String,
+}
+
+#[pymethods]
+impl PyModel {
+    fn __repr__(&self) -> String {
+        format!("Model('{}/{}')", self.owner, self.name)
+    }
+}
+
+impl From<&FuelModel> for PyModel {
+    fn from(model: &FuelModel) -> Self {
+        let strings =
+            |values: &[std::sync::Arc<str>]| values.iter().map(|v| v.to_string()).collect();
+        Self {
+            owner: model.owner.to_string(),
+            name: model.name.clone(),
+            description: model.description.clone(),
+            tags: strings(&model.tags),
+            categories: strings(&model.categories),
+            metadata: model
+                .metadata
+                .iter()
+                .map(|entry| (entry.key.to_string(), entry.value.clone()))
+                .collect(),
+            likes: model.likes,
+            downloads: model.downloads,
+            filesize: model.filesize,
+            license_name: model.license_name.to_string(),
+            private: model.private,
+            created_at: model.created_at.clone(),
+            updated_at: model.updated_at.clone(),
+        }
+    }
+}
+
+fn to_py(models: &[FuelModel]) -> Vec<PyModel> {
+    models.iter().map(PyModel::from).collect()
+}
+
+/// A model extracted to disk
+#[pyclass(name = "DownloadedModel", module = "gz_fuel", get_all, frozen)]
+pub struct PyDownloadedModel {
+    owner: String,
+    name: String,
+    version: u32,
+    path: PathBuf,
+}
+
+#[pymethods]
+impl PyDownloadedModel {
+    fn __repr__(&self) -> String {
+        format!(
+            "DownloadedModel('{}/{}', version={}, path='{}')",
+            self.owner,
+            self.name,
+            self.version,
+            self.path.display()
+        )
+    }
+}
+
+impl From<DownloadedModel> for PyDownloadedModel {
+    fn from(model: DownloadedModel) -> Self {
+        Self {
+            owner: model.owner,
+            name: model.name,
+            version: model.version,
+            path: model.path,
+        }
+    }
+}
+
+#[pyclass(name = "FuelClient", module = "gz_fuel")]
+pub struct PyFuelClient {
+    client: FuelClient,
+}
+
+#[pymethods]
+impl PyFuelClient {
+    #[new]
+    #[pyo3(signature = (url=None, token=None, cache_path=None, download_dir=None, offline=false))]
+    fn new(
+        url: Option<String>,
+        token: Option<String>,
+        cache_path: Option<PathBuf>,
+        download_dir: Option<PathBuf>,
+        offline: bool,
+    ) -> Self {
+        let mut builder = FuelClient::builder().offline(offline);
+        if let Some(url) = url {
+            builder = builder.url(url);
+        }
+        if let Some(token) = token {
+            builder = builder.token(token);
+        }
+        if let Some(path) = cache_path {
+            builder = builder.cache_path(path);
+        }
+        if let Some(path) = download_dir {
+            builder = builder.download_dir(path);
+        }
+        Self {
+            client: builder.build(),
+        }
+    }
+
+    /// Cached models, empty if there is no cache yet
+    fn models(&self) -> Vec<PyModel> {
+        self.client
+            .models()
+            .as_deref()
+            .map(to_py)
+            .unwrap_or_default()
+    }
+
+    #[pyo3(signature = (threshold_secs=None))]
+    fn should_update_cache(&self, threshold_secs: Option<u64>) -> bool {
+        self.client
+            .should_update_cache(&threshold_secs.map(Duration::from_secs))
+    }
+
+    /// Crawls the server catalog and replaces the cache, returns the number of cached models
+    #[pyo3(signature = (write_to_disk=true))]
+    fn update_cache(&self, py: Python<'_>, write_to_disk: bool) -> PyResult<usize> {
+        let models = py.allow_threads(|| self.client.update_cache_blocking(write_to_disk))?;
+        Ok(models.len())
+    }
+
+    fn models_by_owner(&self, owner: &str) -> Vec<PyModel> {
+        self.client
+            .models_by_owner(None, owner)
+            .as_deref()
+            .map(to_py)
+            .unwrap_or_default()
+    }
+
+    fn models_by_tag(&self, tag: &str) -> Vec<PyModel> {
+        self.client
+            .models_by_tag(None, tag)
+            .as_deref()
+            .map(to_py)
+            .unwrap_or_default()
+    }
+
+    fn models_by_metadata(&self, key: &str, value: &str) -> Vec<PyModel> {
+        self.client
+            .models_by_metadata(None, key, value)
+            .as_deref()
+            .map(to_py)
+            .unwrap_or_default()
+    }
+
+    fn models_by_private(&self, private: bool) -> Vec<PyModel> {
+        self.client
+            .models_by_private(None, private)
+            .as_deref()
+            .map(to_py)
+            .unwrap_or_default()
+    }
+
+    /// Full text search of the server catalog
+    #[pyo3(signature = (query, limit=100))]
+    fn search(&self, py: Python<'_>, query: &str, limit: usize) -> PyResult<Vec<PyModel>> {
+        let models = py.allow_threads(|| self.client.search_models_blocking(query, None, limit))?;
+        Ok(to_py(&models))
+    }
+
+    #[pyo3(signature = (owner, name, version=None))]
+    fn download_model(
+        &self,
+        py: Python<'_>,
+        owner: &str,
+        name: &str,
+        version: Option<u32>,
+    ) -> PyResult<PyDownloadedModel> {
+        let model =
+            py.allow_threads(|| self.client.download_model_blocking(owner, name, version))?;
+        Ok(model.into())
+    }
+
+    /// Downloads `(owner, name)` pairs in parallel, raises the first error once all the downloads
+    /// finished
+    #[pyo3(signature = (models, concurrency=4))]
+    fn download_models(
+        &self,
+        py: Python<'_>,
+        models: Vec<(String, String)>,
+        concurrency: usize,
+    ) -> PyResult<Vec<PyDownloadedModel>> {
+        let requests = models
+            .into_iter()
+            .map(|(owner, name)| DownloadRequest::new(owner, name));
+        let results =
+            py.allow_threads(|| self.client.download_models_blocking(requests, concurrency));
+        results
+            .into_iter()
+            .map(|res| Ok(res.result?.into()))
+            .collect()
+    }
+}
+
+#[pymodule]
+fn gz_fuel(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyFuelClient>()?;
+    m.add_class::<PyModel>()?;
+    m.add_class::<PyDownloadedModel>()?;
+    m.add("FuelError", m.py().get_type::<FuelError>())?;
+    Ok(())
+}
@@ -0,0 +1,410 @@
+use crossbeam_channel::Sender;
+use futures_lite::future;
+use serde::{Deserialize, Serialize};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError},
+    thread::{self, JoinHandle},
+};
+
+use crate::{fuel_client::read_json_file, DownloadRequest, DownloadResult, FuelClient, FuelError};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JobId(pub u64);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    Queued,
+    /// Skipped by the workers until resumed
+    Paused,
+    /// Being downloaded by a worker, running jobs can't be paused or cancelled
+    Running,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct QueuedJob {
+    pub id: JobId,
+    pub request: DownloadRequest,
+    /// Jobs with a higher priority are downloaded first, jobs with the same priority in the order
+    /// they were enqueued
+    pub priority: i32,
+    pub state: JobState,
+}
+
+/// Content of the queue file
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct QueueFile {
+    next_id: u64,
+    jobs: Vec<QueuedJob>,
+}
+
+struct QueueState {
+    file: QueueFile,
+    /// Set to make the workers exit once their current download finished
+    stopped: bool,
+}
+
+/// Download queue stored next to the cache file, so jobs that didn't finish are picked up again
+/// by the next process. Jobs are removed from the queue once downloaded, successfully or not.
+/// Clones share the same queue, a process should only create one with `FuelClient::download_queue`.
+#[derive(Clone)]
+pub struct DownloadQueue {
+    client: FuelClient,
+    path: Option<PathBuf>,
+    state: Arc<(Mutex<QueueState>, Condvar)>,
+}
+
+/// Worker threads started by `DownloadQueue::start`
+pub struct QueueWorkers {
+    queue: DownloadQueue,
+    threads: Vec<JoinHandle<()>>,
+}
+
+fn same_request(a: &DownloadRequest, b: &DownloadRequest) -> bool {
+    a.kind == b.kind
+        && a.version == b.version
+        && a.owner.eq_ignore_ascii_case(&b.owner)
+        && a.name.eq_ignore_ascii_case(&b.name)
+}
+
+impl DownloadQueue {
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn save(&self, file: &QueueFile) -> Result<(), FuelError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let bytes =
+            serde_json::ser::to_string_pretty(file).map_err(|e| FuelError::Io(e.to_string()))?;
+        self.client.write_cache_file(path, bytes)
+    }
+
+    /// Applies `f` to the job with the given id and saves the queue if `f` returns true
+    fn update_job(
+        &self,
+        id: JobId,
+        f: impl FnOnce(&mut Vec<QueuedJob>, usize) -> bool,
+    ) -> Result<bool, FuelError> {
+        let mut state = self.lock();
+        let Some(idx) = state.file.jobs.iter().position(|job| job.id == id) else {
+            return Ok(false);
+        };
+        if !f(&mut state.file.jobs, idx) {
+            return Ok(false);
+        }
+        self.save(&state.file)?;
+        self.state.1.notify_all();
+        Ok(true)
+    }
+
+    /// Adds a download to the queue. If the same download is already queued its priority is
+    /// raised to `priority` instead, i.e. to download what is on screen first after prefetching
+    /// everything with a low priority.
+    pub fn enqueue(&self, request: DownloadRequest, priority: i32) -> Result<JobId, FuelError> {
+        let mut state = self.lock();
+        let file = &mut state.file;
+        let id = match file
+            .jobs
+            .iter_mut()
+            .find(|job| same_request(&job.request, &request))
+        {
+            Some(job) => {
+                job.priority = job.priority.max(priority);
+                job.id
+            }
+            None => {
+                let id = JobId(file.next_id);
+                file.next_id += 1;
+                file.jobs.push(QueuedJob {
+                    id,
+                    request,
+                    priority,
+                    state: JobState::Queued,
+                });
+                id
+            }
+        };
+        self.save(&state.file)?;
+        self.state.1.notify_all();
+        Ok(id)
+    }
+
+    /// Returns false if there is no such job
+    pub fn set_priority(&self, id: JobId, priority: i32) -> Result<bool, FuelError> {
+        self.update_job(id, |jobs, idx| {
+            jobs[idx].priority = priority;
+            true
+        })
+    }
+
+    /// Returns false if there is no such job or it is running
+    pub fn pause(&self, id: JobId) -> Result<bool, FuelError> {
+        self.update_job(id, |jobs, idx| {
+            let queued = jobs[idx].state == JobState::Queued;
+            if queued {
+                jobs[idx].state = JobState::Paused;
+            }
+            queued
+        })
+    }
+
+    /// Returns false if there is no such job or it isn't paused
+    pub fn resume(&self, id: JobId) -> Result<bool, FuelError> {
+        self.update_job(id, |jobs, idx| {
+            let paused = jobs[idx].state == JobState::Paused;
+            if paused {
+                jobs[idx].state = JobState::Queued;
+            }
+            paused
+        })
+    }
+
+    /// Removes a job from the queue, returns false if there is no such job or it is running
+    pub fn cancel(&self, id: JobId) -> Result<bool, FuelError> {
+        self.update_job(id, |jobs, idx| {
+            let running = jobs[idx].state == JobState::Running;
+            if !running {
+                jobs.remove(idx);
+            }
+            !running
+        })
+    }
+
+    /// Jobs left in the queue, in the order they are downloaded in
+    pub fn jobs(&self) -> Vec<QueuedJob> {
+        let mut jobs = self.lock().file.jobs.clone();
+        jobs.sort_by(|a, b| b.priority.cmp(&a.priority).then(a.id.cmp(&b.id)));
+        jobs
+    }
+
+    /// Starts `workers` threads downloading the queued jobs, the result of every job is sent to
+    /// `results`. Workers wait for new jobs when the queue is empty, until they are stopped.
+    pub fn start(
+        &self,
+        workers: usize,
+        results: Option<Sender<DownloadResult>>,
+    ) -> Result<QueueWorkers, FuelError> {
+        self.lock().stopped = false;
+        let mut threads = Vec::new();
+        for _ in 0..workers.max(1) {
+            let queue = self.clone();
+            let results = results.clone();
+            let spawned = thread::Builder::new()
+                .name("gz-fuel-queue".to_owned())
+                .spawn(move || queue.run_worker(results));
+            match spawned {
+                Ok(thread) => threads.push(thread),
+                Err(e) => {
+                    QueueWorkers {
+                        queue: self.clone(),
+                        threads,
+                    }
+                    .stop();
+                    return Err(FuelError::Io(e.to_string()));
+                }
+            }
+        }
+        Ok(QueueWorkers {
+            queue: self.clone(),
+            threads,
+        })
+    }
+
+    /// Marks the next job as running, blocks until there is one. None once the queue is stopped.
+    fn next_job(&self) -> Option<QueuedJob> {
+        let mut state = self.lock();
+        loop {
+            if state.stopped {
+                return None;
+            }
+            let next = state
+                .file
+                .jobs
+                .iter_mut()
+                .filter(|job| job.state == JobState::Queued)
+                .min_by(|a, b| b.priority.cmp(&a.priority).then(a.id.cmp(&b.id)));
+            if let Some(job) = next {
+                job.state = JobState::Running;
+                let job = job.clone();
+                // Saving is best effort in the workers, the job is downloaded again at worst
+                self.save(&state.file).ok();
+                return Some(job);
+            }
+            state = self
+                .state
+                .1
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    fn run_worker(&self, results: Option<Sender<DownloadResult>>) {
+        while let Some(job) = self.next_job() {
+            let preflight = self.client.require_download_dir().and_then(|dir| {
+                self.client
+                    .preflight(std::slice::from_ref(&job.request), &dir)
+            });
+            let result = match preflight {
+                Ok(()) => future::block_on(self.client.download_request(&job.request)),
+                Err(e) => Err(e),
+            };
+            {
+                let mut state = self.lock();
+                state.file.jobs.retain(|queued| queued.id != job.id);
+                self.save(&state.file).ok();
+            }
+            if let Some(results) = &results {
+                results
+                    .send(DownloadResult {
+                        request: job.request,
+                        result,
+                    })
+                    .ok();
+            }
+        }
+    }
+}
+
+impl QueueWorkers {
+    /// Stops the workers once their current download finished and waits for them to exit, the
+    /// jobs left stay in the queue
+    pub fn stop(self) {
+        self.queue.lock().stopped = true;
+        self.queue.state.1.notify_all();
+        for thread in self.threads {
+            thread.join().ok();
+        }
+    }
+}
+
+impl FuelClient {
+    /// Download queue persisted next to the cache file, see `DownloadQueue`. Jobs that were
+    /// running when the previous process exited are queued again. Fails with
+    /// `FuelError::Parse` if the queue file can't be parsed, it is left as is.
+    pub fn download_queue(&self) -> Result<DownloadQueue, FuelError> {
+        let path = self.cache_sidecar_path("queue.json");
+        let mut file = match &path {
+            Some(path) => read_json_file::<QueueFile>(path)?.unwrap_or_default(),
+            None => QueueFile::default(),
+        };
+        for job in &mut file.jobs {
+            if job.state == JobState::Running {
+                job.state = JobState::Queued;
+            }
+        }
+        Ok(DownloadQueue {
+            client: self.clone(),
+            path,
+            state: Arc::new((
+                Mutex::new(QueueState {
+                    file,
+                    stopped: false,
+                }),
+                Condvar::new(),
+            )),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TempDir};
+    use std::fs;
+
+    fn offline_queue(dir: &TempDir) -> DownloadQueue {
+        testing::builder(dir, |_| Err("offline".into()))
+            .build()
+            .download_queue()
+            .unwrap()
+    }
+
+    fn names(queue: &DownloadQueue) -> Vec<String> {
+        queue
+            .jobs()
+            .into_iter()
+            .map(|job| job.request.name)
+            .collect()
+    }
+
+    #[test]
+    fn jobs_are_ordered_by_priority() {
+        let dir = TempDir::new();
+        let queue = offline_queue(&dir);
+        let a = queue.enqueue(DownloadRequest::new("o", "a"), 0).unwrap();
+        queue.enqueue(DownloadRequest::new("o", "b"), 5).unwrap();
+        let c = queue.enqueue(DownloadRequest::new("o", "c"), 0).unwrap();
+        assert_eq!(names(&queue), ["b", "a", "c"]);
+        // Enqueuing the same download again only raises its priority
+        assert_eq!(queue.enqueue(DownloadRequest::new("O", "A"), 10), Ok(a));
+        assert_eq!(names(&queue), ["a", "b", "c"]);
+        assert_eq!(queue.set_priority(c, 20), Ok(true));
+        assert_eq!(names(&queue), ["c", "a", "b"]);
+        assert_eq!(queue.set_priority(JobId(42), 0), Ok(false));
+        // The queue is persisted
+        assert_eq!(names(&offline_queue(&dir)), ["c", "a", "b"]);
+    }
+
+    #[test]
+    fn pause_resume_and_cancel() {
+        let dir = TempDir::new();
+        let queue = offline_queue(&dir);
+        let id = queue.enqueue(DownloadRequest::new("o", "a"), 0).unwrap();
+        assert_eq!(queue.resume(id), Ok(false));
+        assert_eq!(queue.pause(id), Ok(true));
+        assert_eq!(queue.pause(id), Ok(false));
+        assert_eq!(queue.jobs()[0].state, JobState::Paused);
+        assert_eq!(queue.resume(id), Ok(true));
+        assert_eq!(queue.jobs()[0].state, JobState::Queued);
+        assert_eq!(queue.cancel(id), Ok(true));
+        assert_eq!(queue.cancel(id), Ok(false));
+        assert!(queue.jobs().is_empty());
+    }
+
+    #[test]
+    fn running_jobs_are_queued_again_after_a_restart() {
+        let dir = TempDir::new();
+        let queue = offline_queue(&dir);
+        let id = queue.enqueue(DownloadRequest::new("o", "a"), 0).unwrap();
+        queue.lock().file.jobs[0].state = JobState::Running;
+        queue.save(&queue.lock().file).unwrap();
+        // Running jobs can't be paused or cancelled
+        assert_eq!(queue.pause(id), Ok(false));
+        assert_eq!(queue.cancel(id), Ok(false));
+        let restored = offline_queue(&dir);
+        assert_eq!(restored.jobs()[0].id, id);
+        assert_eq!(restored.jobs()[0].state, JobState::Queued);
+    }
+
+    #[test]
+    fn unparsable_queues_are_kept() {
+        let dir = TempDir::new();
+        let path = dir.0.join("model_cache.queue.json");
+        fs::write(&path, "{ \"jobs\": ").unwrap();
+        let client = testing::builder(&dir, |_| Err("offline".into())).build();
+        assert!(matches!(client.download_queue(), Err(FuelError::Parse(_))));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{ \"jobs\": ");
+    }
+
+    #[test]
+    fn workers_download_queued_jobs() {
+        let dir = TempDir::new();
+        let archive = testing::zip_archive(&[("model.sdf", "<sdf/>")]);
+        let client = testing::builder(&dir, move |req| {
+            Ok(testing::response(&req.url, 200, archive.clone()))
+        })
+        .download_dir(dir.0.join("downloads"))
+        .build();
+        let queue = client.download_queue().unwrap();
+        queue.enqueue(DownloadRequest::new("o", "a"), 0).unwrap();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let workers = queue.start(2, Some(tx)).unwrap();
+        let result = rx.recv().unwrap();
+        workers.stop();
+        assert_eq!(result.request.name, "a");
+        assert!(result.result.unwrap().path.join("model.sdf").is_file());
+        assert!(queue.jobs().is_empty());
+    }
+}
@@ -0,0 +1,377 @@
+use base64::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{FuelError, Metrics, Middleware};
+
+/// Header marking the requests the cache made conditional, so their 304 responses are replaced.
+/// It is removed before the request is sent.
+pub(crate) const REVALIDATION_HEADER: &str = "X-Gz-Fuel-Revalidation";
+
+/// A response as stored on disk
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct CachedResponse {
+    url: String,
+    status: u16,
+    status_text: String,
+    headers: Vec<(String, String)>,
+    /// Base64 encoded body
+    body: String,
+    /// Seconds since the unix epoch at which the response was stored or last revalidated
+    stored_at: u64,
+}
+
+impl CachedResponse {
+    fn etag(&self) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("ETag"))
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn age(&self) -> Duration {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        now.saturating_sub(Duration::from_secs(self.stored_at))
+    }
+
+    fn to_response(&self) -> Option<ehttp::Response> {
+        let mut headers = ehttp::Headers::default();
+        for (k, v) in &self.headers {
+            headers.insert(k, v);
+        }
+        Some(ehttp::Response {
+            url: self.url.clone(),
+            ok: true,
+            status: self.status,
+            status_text: self.status_text.clone(),
+            headers,
+            bytes: BASE64_STANDARD.decode(&self.body).ok()?,
+        })
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// On disk cache of raw API responses, i.e. to speed up repeated file listing, model config and
+/// category requests. Added with `FuelClientBuilder::response_cache`, it runs after all the
+/// other middlewares.
+///
+/// Successful GET responses are stored keyed by url and credentials. Responses younger than
+/// `ttl` are served without a request, older ones are revalidated with their ETag and served
+/// again if the server answers 304. Requests that are already conditional, requests with
+/// `Cache-Control: no-cache` and streamed downloads are never cached. The client sends every
+/// listing, refresh and freshness check request with `no-cache`, so they always reach the
+/// server.
+///
+/// Responses served from the cache, without a request or after a 304, are reported to the
+/// `Metrics` of the client as cache hits, the other cacheable requests as cache misses.
+///
+/// Stored responses are never evicted, even once the url they were stored for is gone, `clear`
+/// is the only way to reclaim the space they use.
+#[derive(Clone)]
+pub struct ResponseCache {
+    dir: PathBuf,
+    ttl: Duration,
+    /// Set by `FuelClientBuilder::build` to the metrics of the client
+    pub(crate) metrics: Option<Arc<dyn Metrics>>,
+}
+
+impl ResponseCache {
+    pub fn new(dir: impl Into<PathBuf>, ttl: Duration) -> Self {
+        Self {
+            dir: dir.into(),
+            ttl,
+            metrics: None,
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Removes every stored response
+    pub fn clear(&self) -> Result<(), FuelError> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(FuelError::Io(e.to_string())),
+            _ => Ok(()),
+        }
+    }
+
+    /// Responses depend on the credentials, private assets are only listed with a token
+    fn key(request: &ehttp::Request) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(request.url.as_bytes());
+        for (k, v) in &request.headers {
+            if k.eq_ignore_ascii_case("Private-token") || k.eq_ignore_ascii_case("Authorization") {
+                hasher.update(b"\0");
+                hasher.update(v.as_bytes());
+            }
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+
+    fn read(&self, key: &str) -> Option<CachedResponse> {
+        let bytes = fs::read(self.path(key)).ok()?;
+        serde_json::de::from_slice(&bytes).ok()
+    }
+
+    /// Errors are ignored, the response is simply fetched again next time
+    fn write(&self, key: &str, cached: &CachedResponse) {
+        let Ok(bytes) = serde_json::ser::to_vec(cached) else {
+            return;
+        };
+        let path = self.path(key);
+        let tmp = path.with_extension(format!("{}.tmp", fastrand::u64(..)));
+        let written = fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::write(&tmp, bytes))
+            .and_then(|_| fs::rename(&tmp, &path));
+        if written.is_err() {
+            fs::remove_file(tmp).ok();
+        }
+    }
+
+    fn record_cache_hit(&self, hit: bool) {
+        if let Some(metrics) = &self.metrics {
+            if hit {
+                metrics.on_cache_hit();
+            } else {
+                metrics.on_cache_miss();
+            }
+        }
+    }
+
+    fn is_cacheable(request: &ehttp::Request) -> bool {
+        let no_cache = request
+            .headers
+            .get("Cache-Control")
+            .is_some_and(|value| value.contains("no-cache"));
+        request.method == "GET"
+            && !no_cache
+            && request.headers.get("If-None-Match").is_none()
+            && request.headers.get("If-Modified-Since").is_none()
+    }
+}
+
+impl Middleware for ResponseCache {
+    fn before_request(&self, request: &mut ehttp::Request) -> Option<ehttp::Response> {
+        if !Self::is_cacheable(request) {
+            return None;
+        }
+        let key = Self::key(request);
+        let Some(cached) = self.read(&key) else {
+            self.record_cache_hit(false);
+            return None;
+        };
+        if cached.age() < self.ttl {
+            if let Some(res) = cached.to_response() {
+                self.record_cache_hit(true);
+                return Some(res);
+            }
+        }
+        match cached.etag() {
+            Some(etag) => {
+                // Reported once the server answered
+                request.headers.insert("If-None-Match", etag);
+                request.headers.insert(REVALIDATION_HEADER, "1");
+            }
+            None => self.record_cache_hit(false),
+        }
+        None
+    }
+
+    fn after_response(&self, request: &ehttp::Request, response: &mut ehttp::Response) {
+        if request.method != "GET" {
+            return;
+        }
+        let key = Self::key(request);
+        let revalidated = request.headers.get(REVALIDATION_HEADER).is_some();
+        if revalidated && response.status == 304 {
+            let res = self.read(&key).and_then(|mut cached| {
+                let res = cached.to_response()?;
+                cached.stored_at = unix_now();
+                self.write(&key, &cached);
+                Some(res)
+            });
+            self.record_cache_hit(res.is_some());
+            if let Some(res) = res {
+                *response = res;
+            }
+            return;
+        }
+        if revalidated {
+            self.record_cache_hit(false);
+        }
+        // Conditional requests of the caller, errors and streamed bodies aren't stored
+        let caller_conditional = !revalidated && !Self::is_cacheable(request);
+        if caller_conditional || !response.ok || response.bytes.is_empty() {
+            return;
+        }
+        let cached = CachedResponse {
+            url: response.url.clone(),
+            status: response.status,
+            status_text: response.status_text.clone(),
+            headers: response.headers.headers.clone(),
+            body: BASE64_STANDARD.encode(&response.bytes),
+            stored_at: unix_now(),
+        };
+        self.write(&key, &cached);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, response, TempDir};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn stale_responses_are_revalidated() {
+        let dir = TempDir::new();
+        let cache = ResponseCache::new(&dir.0, Duration::ZERO);
+        let url = "https://fuel.test/1.0/o/models/m";
+        let mut request = ehttp::Request::get(url);
+        assert!(cache.before_request(&mut request).is_none());
+        let mut res = response(url, 200, "{}");
+        res.headers.insert("ETag", "\"1\"");
+        cache.after_response(&request, &mut res);
+
+        let mut request = ehttp::Request::get(url);
+        assert!(cache.before_request(&mut request).is_none());
+        assert_eq!(request.headers.get("If-None-Match"), Some("\"1\""));
+        let mut res = response(url, 304, "");
+        cache.after_response(&request, &mut res);
+        assert_eq!((res.status, res.bytes.as_slice()), (200, b"{}".as_slice()));
+
+        // 304 responses to the conditional requests of the caller are theirs to handle
+        let mut request = ehttp::Request::get(url);
+        request.headers.insert("If-None-Match", "\"1\"");
+        assert!(cache.before_request(&mut request).is_none());
+        let mut res = response(url, 304, "");
+        cache.after_response(&request, &mut res);
+        assert_eq!(res.status, 304);
+    }
+
+    /// Client with a response cache of `ttl` answered by `backend`, with the requests it sent
+    fn cached_client<F>(
+        dir: &TempDir,
+        ttl: Duration,
+        backend: F,
+    ) -> (crate::FuelClient, Arc<Mutex<Vec<ehttp::Request>>>)
+    where
+        F: Fn(&ehttp::Request) -> ehttp::Response + Send + Sync + 'static,
+    {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let log = sent.clone();
+        let client = testing::builder(dir, move |req| {
+            log.lock().unwrap().push(req.clone());
+            Ok(backend(req))
+        })
+        .per_page(2)
+        .response_cache(ResponseCache::new(dir.0.join("responses"), ttl))
+        .build();
+        (client, sent)
+    }
+
+    #[test]
+    fn refreshes_always_reach_the_server() {
+        let dir = TempDir::new();
+        let catalog = ["a", "b", "c"].map(|name| testing::model("o", name, "1"));
+        let (client, sent) = cached_client(&dir, Duration::from_secs(3600), move |req| {
+            let page = match req.url.contains("page=1&") {
+                true => &catalog[..2],
+                false if req.url.contains("page=2&") => &catalog[2..],
+                false => &[],
+            };
+            response(&req.url, 200, serde_json::to_vec(page).unwrap())
+        });
+        let mut sent_per_round = Vec::new();
+        for _ in 0..2 {
+            assert_eq!(client.update_cache_blocking(false).unwrap().len(), 3);
+            assert!(!client.should_update_cache_from_server_blocking().unwrap());
+            assert!(client.outdated_models_blocking().unwrap().is_empty());
+            sent_per_round.push(std::mem::take(&mut *sent.lock().unwrap()));
+        }
+        let urls =
+            |sent: &[ehttp::Request]| sent.iter().map(|req| req.url.clone()).collect::<Vec<_>>();
+        // Nothing was served from the response cache the second time
+        assert!(urls(&sent_per_round[0])
+            .iter()
+            .any(|url| url.contains("page=2&")));
+        assert_eq!(urls(&sent_per_round[0]), urls(&sent_per_round[1]));
+    }
+
+    #[test]
+    fn revalidation_header_is_never_sent() {
+        let dir = TempDir::new();
+        let (client, sent) = cached_client(&dir, Duration::ZERO, |req| {
+            match req.headers.get("If-None-Match") {
+                Some(_) => response(&req.url, 304, ""),
+                None => {
+                    let mut res = response(&req.url, 200, "[]");
+                    res.headers.insert("ETag", "\"1\"");
+                    res
+                }
+            }
+        });
+        for _ in 0..2 {
+            let categories = client.fetch_categories_blocking().unwrap();
+            assert!(categories.is_empty());
+        }
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[1].headers.get("If-None-Match"), Some("\"1\""));
+        assert!(sent
+            .iter()
+            .all(|req| req.headers.get(REVALIDATION_HEADER).is_none()));
+    }
+
+    #[test]
+    fn hits_and_misses_are_reported() {
+        for ttl in [Duration::from_secs(3600), Duration::ZERO] {
+            let dir = TempDir::new();
+            let metrics = Arc::new(crate::CountingMetrics::default());
+            let client = testing::builder(&dir, |req| {
+                Ok(match req.headers.get("If-None-Match") {
+                    Some(_) => response(&req.url, 304, ""),
+                    None => {
+                        let mut res = response(&req.url, 200, "[]");
+                        res.headers.insert("ETag", "\"1\"");
+                        res
+                    }
+                })
+            })
+            .metrics(metrics.clone())
+            .response_cache(ResponseCache::new(dir.0.join("responses"), ttl))
+            .build();
+            for _ in 0..2 {
+                client.fetch_categories_blocking().unwrap();
+            }
+            let snapshot = metrics.snapshot();
+            assert_eq!((snapshot.cache_hits, snapshot.cache_misses), (1, 1));
+            // Fresh responses are served without a request, stale ones are revalidated
+            let requests = if ttl.is_zero() { 2 } else { 1 };
+            assert_eq!(snapshot.requests, requests);
+        }
+    }
+}
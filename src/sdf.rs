@@ -0,0 +1,249 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    path::Path,
+};
+
+use crate::{uri, AssetKind, DownloadedModel, FuelClient, FuelError, FuelModel};
+
+/// Pose of an included model relative to its parent frame, in meters and radians
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Pose {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub roll: f64,
+    pub pitch: f64,
+    pub yaw: f64,
+}
+
+impl Pose {
+    /// Pose without rotation
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Self {
+            x,
+            y,
+            z,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_rpy(mut self, roll: f64, pitch: f64, yaw: f64) -> Self {
+        self.roll = roll;
+        self.pitch = pitch;
+        self.yaw = yaw;
+        self
+    }
+}
+
+/// Value of an SDF `<pose>` element, i.e. `1 2 0 0 0 1.57`
+impl fmt::Display for Pose {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {}",
+            self.x, self.y, self.z, self.roll, self.pitch, self.yaw
+        )
+    }
+}
+
+/// Escapes the characters that can't appear in XML text
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// `<include>` block of the given URI, indented by `indent` spaces
+pub(crate) fn sdf_include(
+    uri: &str,
+    name: Option<&str>,
+    pose: Option<Pose>,
+    indent: usize,
+) -> String {
+    let pad = " ".repeat(indent);
+    let mut include = format!("{pad}<include>\n{pad}  <uri>{}</uri>\n", escape_xml(uri));
+    if let Some(name) = name {
+        include.push_str(&format!("{pad}  <name>{}</name>\n", escape_xml(name)));
+    }
+    if let Some(pose) = pose {
+        include.push_str(&format!("{pad}  <pose>{pose}</pose>\n"));
+    }
+    include.push_str(&format!("{pad}</include>\n"));
+    include
+}
+
+impl FuelModel {
+    /// Ready to paste `<include>` block of the model on the server at `base_url`, the latest
+    /// version is included when the world is loaded
+    pub fn to_sdf_include(&self, base_url: &str, pose: Option<Pose>) -> String {
+        let uri = uri::asset_url(base_url, AssetKind::Model, &self.owner, &self.name);
+        sdf_include(&uri, None, pose, 0)
+    }
+}
+
+/// Sun and ground plane, so composed worlds are lit and models don't fall forever
+const ENVIRONMENT: &str = r#"    <light type="directional" name="sun">
+      <cast_shadows>true</cast_shadows>
+      <pose>0 0 10 0 0 0</pose>
+      <diffuse>0.8 0.8 0.8 1</diffuse>
+      <specular>0.2 0.2 0.2 1</specular>
+      <direction>-0.5 0.1 -0.9</direction>
+    </light>
+    <model name="ground_plane">
+      <static>true</static>
+      <link name="link">
+        <collision name="collision">
+          <geometry>
+            <plane><normal>0 0 1</normal><size>100 100</size></plane>
+          </geometry>
+        </collision>
+        <visual name="visual">
+          <geometry>
+            <plane><normal>0 0 1</normal><size>100 100</size></plane>
+          </geometry>
+          <material>
+            <ambient>0.8 0.8 0.8 1</ambient>
+            <diffuse>0.8 0.8 0.8 1</diffuse>
+          </material>
+        </visual>
+      </link>
+    </model>
+"#;
+
+struct WorldInclude {
+    uri: String,
+    /// Model name the instance name is derived from
+    name: String,
+    pose: Pose,
+}
+
+/// Minimal SDF world including a set of models, i.e. to turn search results into a runnable
+/// world. Instances are named after their model, with a numeric suffix when a model is included
+/// more than once.
+pub struct WorldBuilder {
+    name: String,
+    environment: bool,
+    includes: Vec<WorldInclude>,
+}
+
+impl WorldBuilder {
+    /// The world has a sun and a ground plane unless `without_environment` is called
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            environment: true,
+            includes: Vec::new(),
+        }
+    }
+
+    pub fn without_environment(mut self) -> Self {
+        self.environment = false;
+        self
+    }
+
+    /// Includes a model through its Fuel URI on the server at `base_url`
+    pub fn model(mut self, base_url: &str, model: &FuelModel, pose: Pose) -> Self {
+        self.includes.push(WorldInclude {
+            uri: uri::asset_url(base_url, AssetKind::Model, &model.owner, &model.name),
+            name: model.name.clone(),
+            pose,
+        });
+        self
+    }
+
+    /// Includes a downloaded model through its local path, so the world loads offline
+    pub fn downloaded(mut self, model: &DownloadedModel, pose: Pose) -> Self {
+        self.includes.push(WorldInclude {
+            uri: model.path.to_string_lossy().into_owned(),
+            name: model.name.clone(),
+            pose,
+        });
+        self
+    }
+
+    pub fn to_sdf(&self) -> String {
+        let mut sdf = format!(
+            "<?xml version=\"1.0\" ?>\n<sdf version=\"1.9\">\n  <world name=\"{}\">\n",
+            escape_xml(&self.name)
+        );
+        if self.environment {
+            sdf.push_str(ENVIRONMENT);
+        }
+        // Suffixes are checked against every name in use, a model can be named like the
+        // duplicate of another one, i.e. `table_1`
+        let mut used = HashSet::<String>::new();
+        let mut counts = HashMap::<String, usize>::new();
+        for include in &self.includes {
+            let base = include
+                .name
+                .to_lowercase()
+                .replace(char::is_whitespace, "_");
+            let count = counts.entry(base.clone()).or_default();
+            let mut name = base.clone();
+            while used.contains(&name) {
+                *count += 1;
+                name = format!("{base}_{count}");
+            }
+            used.insert(name.clone());
+            sdf.push_str(&sdf_include(
+                &include.uri,
+                Some(&name),
+                Some(include.pose),
+                4,
+            ));
+        }
+        sdf.push_str("  </world>\n</sdf>\n");
+        sdf
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), FuelError> {
+        fs::write(path, self.to_sdf()).map_err(|e| FuelError::Io(e.to_string()))
+    }
+}
+
+impl FuelClient {
+    /// World including the given models through their Fuel URIs on this server, see
+    /// `WorldBuilder` for more control
+    pub fn compose_world(&self, name: &str, models: &[(FuelModel, Pose)]) -> String {
+        models
+            .iter()
+            .fold(WorldBuilder::new(name), |world, (model, pose)| {
+                world.model(&self.url, model, *pose)
+            })
+            .to_sdf()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn include(name: &str) -> WorldInclude {
+        WorldInclude {
+            uri: format!("model://{name}"),
+            name: name.to_owned(),
+            pose: Pose::default(),
+        }
+    }
+
+    #[test]
+    fn duplicate_names_are_unique() {
+        let mut world = WorldBuilder::new("w");
+        world.includes = ["Table", "table_1", "Table", "Table", "table_2"]
+            .into_iter()
+            .map(include)
+            .collect();
+        let sdf = world.to_sdf();
+        let names = sdf
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("<name>"))
+            .filter_map(|line| line.strip_suffix("</name>"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            ["table", "table_1", "table_2", "table_3", "table_2_1"]
+        );
+    }
+}
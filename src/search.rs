@@ -0,0 +1,296 @@
+use futures_lite::future;
+use serde::{Deserialize, Serialize};
+
+use crate::{asset::CacheEntry, uri, FuelClient, FuelError, FuelModel, FuelWorld};
+
+/// Field the server sorts listings by
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum SortField {
+    Name,
+    CreatedAt,
+    UpdatedAt,
+    Downloads,
+    Likes,
+}
+
+impl SortField {
+    /// Value of the `sort` query parameter
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SortField::Name => "name",
+            SortField::CreatedAt => "createdAt",
+            SortField::UpdatedAt => "updatedAt",
+            SortField::Downloads => "downloads",
+            SortField::Likes => "likes",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortOrder {
+    /// Value of the `order` query parameter
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SortOrder::Asc => "asc",
+            SortOrder::Desc => "desc",
+        }
+    }
+}
+
+/// Server side ordering of a listing, passed through as the `sort` and `order` query parameters
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Sort {
+    pub field: SortField,
+    pub order: SortOrder,
+}
+
+impl Sort {
+    /// Descending order, i.e. most downloaded or most recent first
+    pub fn desc(field: SortField) -> Self {
+        Self {
+            field,
+            order: SortOrder::Desc,
+        }
+    }
+
+    pub fn asc(field: SortField) -> Self {
+        Self {
+            field,
+            order: SortOrder::Asc,
+        }
+    }
+
+    pub fn most_downloaded() -> Self {
+        Self::desc(SortField::Downloads)
+    }
+
+    pub fn most_recent() -> Self {
+        Self::desc(SortField::UpdatedAt)
+    }
+
+    /// Query string fragment, without the leading `&`
+    pub(crate) fn query(&self) -> String {
+        format!("sort={}&order={}", self.field.as_str(), self.order.as_str())
+    }
+}
+
+/// Server side query of the models listing, all the set criteria are sent in a single request so
+/// targeted queries don't need the whole catalog in the cache. Queries can be saved as presets,
+/// see `FuelClient::save_preset`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ModelQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    owner: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    search: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    category: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sort: Option<Sort>,
+}
+
+impl ModelQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lists the models of `owner` through the `{owner}/models` endpoint
+    pub fn owner(mut self, owner: impl Into<String>) -> Self {
+        self.owner = Some(owner.into());
+        self
+    }
+
+    /// Full text search term, sent as the `q` parameter
+    pub fn search(mut self, term: impl Into<String>) -> Self {
+        self.search = Some(term.into());
+        self
+    }
+
+    /// Models must have this tag, can be called multiple times to require several tags. Tags are
+    /// sent in the `q` parameter with the `tags:` prefix of the server search syntax,
+    /// quoted if they contain whitespace.
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Models must be in this category, sent as the `category` parameter
+    pub fn category(mut self, category: impl Into<String>) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+
+    pub fn sort(mut self, sort: Sort) -> Self {
+        self.sort = Some(sort);
+        self
+    }
+
+    /// Listing endpoint the query is sent to
+    fn listing(&self) -> String {
+        match &self.owner {
+            Some(owner) => format!("{}/models", uri::encode_segment(owner)),
+            None => "models".to_owned(),
+        }
+    }
+
+    /// Query string parameters, without the pagination ones
+    fn params(&self) -> Vec<String> {
+        let terms = self
+            .search
+            .iter()
+            .cloned()
+            .chain(
+                self.tags
+                    .iter()
+                    .map(|tag| match tag.contains(char::is_whitespace) {
+                        true => format!("tags:\"{tag}\""),
+                        false => format!("tags:{tag}"),
+                    }),
+            )
+            .collect::<Vec<_>>();
+        let mut params = Vec::new();
+        if !terms.is_empty() {
+            params.push(format!("q={}", uri::encode_query_value(&terms.join(" "))));
+        }
+        if let Some(category) = &self.category {
+            params.push(format!("category={}", uri::encode_query_value(category)));
+        }
+        params.extend(self.sort.iter().map(Sort::query));
+        params
+    }
+}
+
+impl FuelClient {
+    /// Fetches the first `limit` entries of a listing, pages are only requested until `limit`
+    /// entries have been returned. `params` are appended to the query string as is.
+    pub(crate) async fn fetch_listing<A: CacheEntry>(
+        &self,
+        listing: &str,
+        params: &[String],
+        limit: usize,
+    ) -> Result<Vec<A>, FuelError> {
+        let per_page = self.per_page.clamp(1, limit.max(1) as u32);
+        let mut assets = Vec::new();
+        let mut page = 1;
+        while assets.len() < limit {
+            let mut url = format!("{}?page={page}&per_page={per_page}", self.endpoint(listing));
+            for param in params {
+                url.push('&');
+                url.push_str(param);
+            }
+            let res = self.fetch(self.uncached_get_request(url)).await?;
+            if !res.ok {
+                // Running past the last page
+                if res.status == 404 && page > 1 {
+                    break;
+                }
+                return Err(FuelError::from_status(res.status));
+            }
+            let mut fetched = serde_json::de::from_slice::<Vec<A>>(&res.bytes)
+                .map_err(|e| FuelError::Parse(e.to_string()))?;
+            let is_last = fetched.len() < per_page as usize;
+            assets.append(&mut fetched);
+            if is_last {
+                break;
+            }
+            page += 1;
+        }
+        assets.truncate(limit);
+        Ok(assets)
+    }
+
+    /// Lists up to `limit` models straight from the server, in the server order if `sort` is
+    /// None. Unlike `update_cache` this doesn't crawl the whole catalog nor touch the cache.
+    pub async fn list_models(
+        &self,
+        sort: Option<Sort>,
+        limit: usize,
+    ) -> Result<Vec<FuelModel>, FuelError> {
+        let params = sort.iter().map(Sort::query).collect::<Vec<_>>();
+        self.fetch_listing("models", &params, limit).await
+    }
+
+    pub fn list_models_blocking(
+        &self,
+        sort: Option<Sort>,
+        limit: usize,
+    ) -> Result<Vec<FuelModel>, FuelError> {
+        future::block_on(self.list_models(sort, limit))
+    }
+
+    /// Runs a combined server side query, returns up to `limit` models without touching the
+    /// cache
+    pub async fn query_models(
+        &self,
+        query: &ModelQuery,
+        limit: usize,
+    ) -> Result<Vec<FuelModel>, FuelError> {
+        self.fetch_listing(&query.listing(), &query.params(), limit)
+            .await
+    }
+
+    pub fn query_models_blocking(
+        &self,
+        query: &ModelQuery,
+        limit: usize,
+    ) -> Result<Vec<FuelModel>, FuelError> {
+        future::block_on(self.query_models(query, limit))
+    }
+
+    /// Full text search of the server catalog, returns up to `limit` models
+    pub async fn search_models(
+        &self,
+        query: &str,
+        sort: Option<Sort>,
+        limit: usize,
+    ) -> Result<Vec<FuelModel>, FuelError> {
+        let params = search_params(query, sort);
+        self.fetch_listing("models", &params, limit).await
+    }
+
+    pub fn search_models_blocking(
+        &self,
+        query: &str,
+        sort: Option<Sort>,
+        limit: usize,
+    ) -> Result<Vec<FuelModel>, FuelError> {
+        future::block_on(self.search_models(query, sort, limit))
+    }
+
+    /// Same as `search_models` for worlds
+    pub async fn search_worlds(
+        &self,
+        query: &str,
+        sort: Option<Sort>,
+        limit: usize,
+    ) -> Result<Vec<FuelWorld>, FuelError> {
+        let params = search_params(query, sort);
+        self.fetch_listing("worlds", &params, limit).await
+    }
+
+    pub fn search_worlds_blocking(
+        &self,
+        query: &str,
+        sort: Option<Sort>,
+        limit: usize,
+    ) -> Result<Vec<FuelWorld>, FuelError> {
+        future::block_on(self.search_worlds(query, sort, limit))
+    }
+}
+
+fn search_params(query: &str, sort: Option<Sort>) -> Vec<String> {
+    let mut params = vec![format!("q={}", uri::encode_query_value(query))];
+    params.extend(sort.iter().map(Sort::query));
+    params
+}
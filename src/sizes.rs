@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use crate::{FuelClient, FuelModel};
+
+/// What filesize statistics are grouped by, a model counts in every group of its tags or
+/// categories
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SizeGrouping {
+    Tag,
+    Category,
+    Owner,
+}
+
+/// Archive sizes of a set of models, in bytes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SizeStats {
+    pub count: usize,
+    pub total: u64,
+    pub largest: u64,
+}
+
+impl SizeStats {
+    pub fn of(models: &[FuelModel]) -> Self {
+        let mut stats = Self::default();
+        for model in models {
+            stats.add(model);
+        }
+        stats
+    }
+
+    fn add(&mut self, model: &FuelModel) {
+        self.count += 1;
+        self.total += model.filesize as u64;
+        self.largest = self.largest.max(model.filesize as u64);
+    }
+
+    pub fn average(&self) -> u64 {
+        self.total.checked_div(self.count as u64).unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeGroup {
+    /// Tag, category or owner, groups are case insensitive and named after the first spelling
+    /// found
+    pub key: String,
+    pub stats: SizeStats,
+}
+
+/// Filesize statistics of `models` per group, largest total first. Summing the totals of tag or
+/// category groups counts models with several tags more than once, use `SizeStats::of` for the
+/// size of the whole set.
+pub fn filesize_stats(models: &[FuelModel], grouping: SizeGrouping) -> Vec<SizeGroup> {
+    let mut groups = HashMap::<String, SizeGroup>::new();
+    for model in models {
+        let keys = match grouping {
+            SizeGrouping::Tag => model.tags.clone(),
+            SizeGrouping::Category => model.categories.clone(),
+            SizeGrouping::Owner => vec![model.owner.clone()],
+        };
+        for key in keys {
+            groups
+                .entry(key.to_lowercase())
+                .or_insert_with(|| SizeGroup {
+                    key: key.to_string(),
+                    stats: SizeStats::default(),
+                })
+                .stats
+                .add(model);
+        }
+    }
+    let mut groups = groups.into_values().collect::<Vec<_>>();
+    groups.sort_by(|a, b| {
+        b.stats
+            .total
+            .cmp(&a.stats.total)
+            .then_with(|| a.key.to_lowercase().cmp(&b.key.to_lowercase()))
+    });
+    groups
+}
+
+impl FuelClient {
+    /// See `filesize_stats`, the cached models are used if `models` is None
+    pub fn filesize_stats(
+        &self,
+        models: Option<&Vec<FuelModel>>,
+        grouping: SizeGrouping,
+    ) -> Option<Vec<SizeGroup>> {
+        let cached = self.models();
+        let models = models.map(Vec::as_slice).or(cached.as_deref())?;
+        Some(filesize_stats(models, grouping))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TempDir};
+
+    fn asset(owner: &str, filesize: usize, tags: &[&str]) -> FuelModel {
+        FuelModel {
+            filesize,
+            tags: tags.iter().map(|&tag| tag.into()).collect(),
+            ..testing::model(owner, &format!("{owner}-{filesize}"), "1")
+        }
+    }
+
+    fn totals(groups: &[SizeGroup]) -> Vec<(&str, usize, u64, u64)> {
+        groups
+            .iter()
+            .map(|g| {
+                (
+                    g.key.as_str(),
+                    g.stats.count,
+                    g.stats.total,
+                    g.stats.largest,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cached_filesize_stats() {
+        let dir = TempDir::new();
+        let catalog = vec![
+            asset("o", 100, &["Kitchen", "table"]),
+            asset("o", 300, &["kitchen"]),
+            asset("p", 50, &["table"]),
+        ];
+        let client = testing::builder(&dir, move |req| Ok(testing::listing(req, &catalog))).build();
+        assert_eq!(client.filesize_stats(None, SizeGrouping::Owner), None);
+        client.update_cache_blocking(false).unwrap();
+
+        let by_tag = client.filesize_stats(None, SizeGrouping::Tag).unwrap();
+        assert_eq!(
+            totals(&by_tag),
+            [("Kitchen", 2, 400, 300), ("table", 2, 150, 100)]
+        );
+        assert_eq!(by_tag[0].stats.average(), 200);
+        let by_owner = client.filesize_stats(None, SizeGrouping::Owner).unwrap();
+        assert_eq!(totals(&by_owner), [("o", 2, 400, 300), ("p", 1, 50, 50)]);
+        // Explicit models are used instead of the cache
+        let models = vec![asset("q", 10, &[])];
+        let by_owner = client.filesize_stats(Some(&models), SizeGrouping::Owner);
+        assert_eq!(totals(&by_owner.unwrap()), [("q", 1, 10, 10)]);
+        assert!(client
+            .filesize_stats(None, SizeGrouping::Category)
+            .unwrap()
+            .is_empty());
+    }
+}
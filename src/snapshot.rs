@@ -0,0 +1,192 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{FuelClient, FuelError, FuelModel};
+
+/// A copy of the cache file taken after a cache refresh
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheSnapshot {
+    pub time: SystemTime,
+    pub path: PathBuf,
+}
+
+impl CacheSnapshot {
+    pub fn load(&self) -> Result<Vec<FuelModel>, FuelError> {
+        let bytes = fs::read(&self.path).map_err(|e| FuelError::Io(e.to_string()))?;
+        serde_json::de::from_slice(&bytes).map_err(|e| FuelError::Parse(e.to_string()))
+    }
+}
+
+/// A model whose `updatedAt` changed between two catalogs
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelChange {
+    pub before: FuelModel,
+    pub after: FuelModel,
+}
+
+/// Differences between two catalogs, models are matched by case insensitive owner and name
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CacheDiff {
+    pub added: Vec<FuelModel>,
+    pub removed: Vec<FuelModel>,
+    /// Only models with a new `updatedAt`, changes of counters like downloads are ignored
+    pub updated: Vec<ModelChange>,
+}
+
+impl CacheDiff {
+    pub fn between(before: &[FuelModel], after: &[FuelModel]) -> Self {
+        let key = |model: &FuelModel| (model.owner.to_lowercase(), model.name.to_lowercase());
+        let mut previous = before
+            .iter()
+            .map(|model| (key(model), model))
+            .collect::<HashMap<_, _>>();
+        let mut diff = Self::default();
+        for model in after {
+            match previous.remove(&key(model)) {
+                None => diff.added.push(model.clone()),
+                Some(old) if old.updated_at != model.updated_at => diff.updated.push(ModelChange {
+                    before: old.clone(),
+                    after: model.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        // Keep the order of `before` for the removed models
+        diff.removed = before
+            .iter()
+            .filter(|model| previous.contains_key(&key(model)))
+            .cloned()
+            .collect();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
+}
+
+impl FuelClient {
+    /// Snapshots are stored in a directory next to the cache file, named after the unix time they
+    /// were taken at
+    fn snapshot_dir(&self) -> Option<PathBuf> {
+        self.cache_sidecar_path("snapshots")
+    }
+
+    /// Snapshots taken so far, oldest first
+    pub fn snapshots(&self) -> Vec<CacheSnapshot> {
+        let Some(entries) = self.snapshot_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+            return Vec::new();
+        };
+        let mut snapshots = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let secs = path.file_stem()?.to_str()?.parse::<u64>().ok()?;
+                Some(CacheSnapshot {
+                    time: UNIX_EPOCH + Duration::from_secs(secs),
+                    path,
+                })
+            })
+            .collect::<Vec<_>>();
+        snapshots.sort_by_key(|snapshot| snapshot.time);
+        snapshots
+    }
+
+    /// Latest snapshot taken at or before `time`, i.e. to compare with the catalog of last month
+    pub fn snapshot_at(&self, time: SystemTime) -> Option<CacheSnapshot> {
+        self.snapshots()
+            .into_iter()
+            .rev()
+            .find(|snapshot| snapshot.time <= time)
+    }
+
+    /// Changes from `snapshot` to the current cache, None if there is no cache
+    pub fn diff_snapshot(&self, snapshot: &CacheSnapshot) -> Result<Option<CacheDiff>, FuelError> {
+        let before = snapshot.load()?;
+        Ok(self
+            .models()
+            .as_ref()
+            .map(|models| CacheDiff::between(&before, models)))
+    }
+
+    /// Copies the cache file to the snapshot directory and removes the snapshots exceeding
+    /// `keep_snapshots`, oldest first
+    pub(crate) fn take_snapshot(&self) -> Result<(), FuelError> {
+        let io_err = |e: std::io::Error| FuelError::Io(e.to_string());
+        let (Some(cache), Some(dir)) = (
+            self.cache_path.clone().or_else(Self::default_cache_path),
+            self.snapshot_dir(),
+        ) else {
+            return Ok(());
+        };
+        fs::create_dir_all(&dir).map_err(io_err)?;
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let path = dir.join(format!("{secs}.json"));
+        {
+            let _lock = self.lock_cache_file();
+            fs::remove_file(&path).ok();
+            // The cache file is only ever replaced, never modified, so it can be shared
+            if fs::hard_link(&cache, &path).is_err() {
+                fs::copy(&cache, &path).map_err(io_err)?;
+            }
+        }
+        let snapshots = self.snapshots();
+        let excess = snapshots.len().saturating_sub(self.keep_snapshots);
+        for snapshot in &snapshots[..excess] {
+            fs::remove_file(&snapshot.path).map_err(io_err)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, model, TempDir};
+
+    fn names(models: &[FuelModel]) -> Vec<&str> {
+        models.iter().map(|model| model.name.as_str()).collect()
+    }
+
+    #[test]
+    fn refreshes_rotate_snapshots() {
+        let dir = TempDir::new();
+        let catalog = vec![model("o", "a", "2"), model("o", "c", "1")];
+        let client = testing::builder(&dir, move |req| Ok(testing::listing(req, &catalog)))
+            .keep_snapshots(2)
+            .build();
+        let snapshot_dir = client.snapshot_dir().unwrap();
+        fs::create_dir_all(&snapshot_dir).unwrap();
+        let old = serde_json::to_vec(&[model("o", "a", "1"), model("o", "b", "1")]).unwrap();
+        for secs in [100, 200] {
+            fs::write(snapshot_dir.join(format!("{secs}.json")), &old).unwrap();
+        }
+        client.update_cache_blocking(true).unwrap();
+
+        let snapshots = client.snapshots();
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(snapshots[0].time, UNIX_EPOCH + Duration::from_secs(200));
+        assert_eq!(names(&snapshots[1].load().unwrap()), ["a", "c"]);
+        let at = UNIX_EPOCH + Duration::from_secs(250);
+        assert_eq!(client.snapshot_at(at).as_ref(), Some(&snapshots[0]));
+        assert_eq!(
+            client.snapshot_at(UNIX_EPOCH + Duration::from_secs(150)),
+            None
+        );
+
+        let diff = client.diff_snapshot(&snapshots[0]).unwrap().unwrap();
+        assert_eq!(names(&diff.added), ["c"]);
+        assert_eq!(names(&diff.removed), ["b"]);
+        assert_eq!(diff.updated.len(), 1);
+        assert_eq!(diff.updated[0].before.updated_at, "1");
+        assert_eq!(diff.updated[0].after.updated_at, "2");
+        let latest = client.diff_snapshot(&snapshots[1]).unwrap().unwrap();
+        assert!(latest.is_empty());
+    }
+}
@@ -0,0 +1,137 @@
+use std::path::Path;
+
+use crate::{find_asset, AssetKind, DownloadRequest, FuelClient, FuelError};
+
+/// Archives are streamed to disk and extracted next to themselves, so downloading needs at least
+/// twice their size
+const EXTRACTION_FACTOR: u64 = 2;
+
+/// Disk space needed by a set of downloads, estimated from the declared archive sizes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpaceCheck {
+    pub needed: u64,
+    pub available: u64,
+    /// Number of downloads whose size isn't known from the cache, they aren't counted in `needed`
+    pub unknown: usize,
+}
+
+impl SpaceCheck {
+    pub fn is_enough(&self) -> bool {
+        self.needed <= self.available
+    }
+}
+
+/// Space available to the current user on the filesystem of `path`, or of its closest existing
+/// ancestor if it doesn't exist yet
+pub fn available_space(path: &Path) -> Option<u64> {
+    let existing = path.ancestors().find(|p| p.exists())?;
+    fs4::available_space(existing).ok()
+}
+
+impl FuelClient {
+    /// Archive size of a cached asset
+    pub(crate) fn cached_filesize(&self, kind: AssetKind, owner: &str, name: &str) -> Option<u64> {
+        match kind {
+            AssetKind::Model => {
+                find_asset(self.models().as_ref()?, owner, name).map(|m| m.filesize as u64)
+            }
+            AssetKind::World => {
+                find_asset(self.worlds().as_ref()?, owner, name).map(|w| w.filesize as u64)
+            }
+        }
+    }
+
+    /// Compares the cached sizes of `requests` with the space available at `dest`, None if the
+    /// available space can't be determined. Sizes are those of the latest versions.
+    pub fn check_disk_space<'a>(
+        &self,
+        requests: impl IntoIterator<Item = &'a DownloadRequest>,
+        dest: &Path,
+    ) -> Option<SpaceCheck> {
+        let available = available_space(dest)?;
+        let mut check = SpaceCheck {
+            needed: 0,
+            available,
+            unknown: 0,
+        };
+        for request in requests {
+            match self.cached_filesize(request.kind, &request.owner, &request.name) {
+                Some(size) => check.needed += size * EXTRACTION_FACTOR,
+                None => check.unknown += 1,
+            }
+        }
+        Some(check)
+    }
+
+    /// Fails with `FuelError::InsufficientSpace` if `requests` can't fit in the download
+    /// directory, unless `disk_space_check` is disabled
+    pub(crate) fn preflight<'a>(
+        &self,
+        requests: impl IntoIterator<Item = &'a DownloadRequest>,
+        dest: &Path,
+    ) -> Result<(), FuelError> {
+        if !self.disk_space_check {
+            return Ok(());
+        }
+        match self.check_disk_space(requests, dest) {
+            Some(check) if !check.is_enough() => Err(FuelError::InsufficientSpace {
+                needed: check.needed,
+                available: check.available,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{self, TempDir},
+        FuelModel,
+    };
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    #[test]
+    fn downloads_too_large_for_the_disk_are_refused() {
+        let dir = TempDir::new();
+        let catalog =
+            [("small", 1000), ("huge", usize::MAX / 4)].map(|(name, filesize)| FuelModel {
+                filesize,
+                ..testing::model("o", name, "1")
+            });
+        let archive = testing::zip_archive(&[("model.sdf", "<sdf/>")]);
+        let downloads = Arc::new(AtomicUsize::new(0));
+        let counter = downloads.clone();
+        let builder = testing::builder(&dir, move |req| {
+            if req.url.contains("/models?") {
+                return Ok(testing::listing(req, &catalog));
+            }
+            counter.fetch_add(1, Ordering::Relaxed);
+            Ok(testing::response(&req.url, 200, archive.clone()))
+        })
+        .download_dir(dir.0.join("downloads"));
+        let client = builder.clone().build();
+        client.update_cache_blocking(true).unwrap();
+
+        let requests = [
+            DownloadRequest::new("o", "small"),
+            DownloadRequest::new("o", "unknown"),
+        ];
+        let check = client.check_disk_space(&requests, &dir.0).unwrap();
+        assert_eq!((check.needed, check.unknown), (2000, 1));
+        assert!(check.is_enough());
+
+        let res = client.download_model_blocking("o", "huge", None);
+        assert!(matches!(res, Err(FuelError::InsufficientSpace { .. })));
+        assert_eq!(downloads.load(Ordering::Relaxed), 0);
+        let unchecked = builder.disk_space_check(false).build();
+        unchecked
+            .download_model_blocking("o", "huge", None)
+            .unwrap();
+        assert!(downloads.load(Ordering::Relaxed) > 0);
+    }
+}
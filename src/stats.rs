@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{FuelClient, FuelError, FuelModel};
+
+/// Counters of a model at the time of a cache refresh
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatsSample {
+    /// Seconds since the unix epoch
+    pub time: u64,
+    pub downloads: u32,
+    pub likes: u32,
+}
+
+/// Samples of every model keyed by lowercase `owner/name`, oldest first. A sample is only added
+/// when the counters changed since the previous one, so the history stays small.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct StatsHistory {
+    pub models: BTreeMap<String, Vec<StatsSample>>,
+}
+
+/// Change of the counters of a model over a period
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatsDelta {
+    pub downloads: i64,
+    pub likes: i64,
+    /// Time of the sample the delta is computed from, in seconds since the unix epoch. It is
+    /// later than the start of the requested period if the history doesn't go back that far.
+    pub since: u64,
+}
+
+fn history_key(owner: &str, name: &str) -> String {
+    format!("{}/{}", owner.to_lowercase(), name.to_lowercase())
+}
+
+fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+impl StatsHistory {
+    /// Adds a sample for every model whose counters changed since its last sample
+    pub fn record(&mut self, models: &[FuelModel], time: SystemTime) {
+        let time = unix_time(time);
+        for model in models {
+            let samples = self
+                .models
+                .entry(history_key(&model.owner, &model.name))
+                .or_default();
+            let changed = samples
+                .last()
+                .is_none_or(|last| last.downloads != model.downloads || last.likes != model.likes);
+            if changed {
+                samples.push(StatsSample {
+                    time,
+                    downloads: model.downloads,
+                    likes: model.likes,
+                });
+            }
+        }
+    }
+
+    pub fn samples(&self, owner: &str, name: &str) -> &[StatsSample] {
+        self.models
+            .get(&history_key(owner, name))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Change of the counters of a model between `period` ago and its latest sample, None if the
+    /// model has no samples
+    pub fn delta(&self, owner: &str, name: &str, period: Duration) -> Option<StatsDelta> {
+        let samples = self.samples(owner, name);
+        let latest = samples.last()?;
+        let start = unix_time(SystemTime::now().checked_sub(period).unwrap_or(UNIX_EPOCH));
+        // Counters were those of the last sample before the period started, if there is one
+        let base = samples
+            .iter()
+            .rev()
+            .find(|sample| sample.time <= start)
+            .or(samples.first())?;
+        Some(StatsDelta {
+            downloads: latest.downloads as i64 - base.downloads as i64,
+            likes: latest.likes as i64 - base.likes as i64,
+            since: base.time.max(start),
+        })
+    }
+}
+
+impl FuelClient {
+    /// History is stored next to the cache file
+    fn stats_history_path(&self) -> Option<PathBuf> {
+        self.cache_sidecar_path("stats.json")
+    }
+
+    /// Counters recorded by the cache refreshes of clients with `track_stats` set, empty if there
+    /// are none
+    pub fn stats_history(&self) -> StatsHistory {
+        self.stats_history_path()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|b| serde_json::de::from_slice(&b).ok())
+            .unwrap_or_default()
+    }
+
+    /// Change of the downloads and likes of a model over `period`, i.e. the downloads in the last
+    /// 30 days. See `StatsHistory::delta`.
+    pub fn stats_delta(&self, owner: &str, name: &str, period: Duration) -> Option<StatsDelta> {
+        self.stats_history().delta(owner, name, period)
+    }
+
+    pub(crate) fn record_stats(&self, models: &[FuelModel]) -> Result<(), FuelError> {
+        let Some(path) = self.stats_history_path() else {
+            return Ok(());
+        };
+        let mut history = self.stats_history();
+        history.record(models, SystemTime::now());
+        let bytes =
+            serde_json::ser::to_string(&history).map_err(|e| FuelError::Io(e.to_string()))?;
+        self.write_cache_file(&path, bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TempDir};
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+
+    #[test]
+    fn cache_refreshes_record_changed_counters() {
+        let dir = TempDir::new();
+        let downloads = Arc::new(AtomicU32::new(10));
+        let counter = downloads.clone();
+        let backend = move |req: &ehttp::Request| {
+            let mut model = testing::model("o", "a", "1");
+            model.downloads = counter.load(Ordering::Relaxed);
+            Ok(testing::listing(req, &[model]))
+        };
+        let untracked = testing::builder(&dir, backend.clone()).build();
+        untracked.update_cache_blocking(true).unwrap();
+        assert_eq!(untracked.stats_history(), StatsHistory::default());
+
+        let client = testing::builder(&dir, backend).track_stats(true).build();
+        for count in [10, 10, 25] {
+            downloads.store(count, Ordering::Relaxed);
+            client.update_cache_blocking(true).unwrap();
+        }
+        let history = client.stats_history();
+        let samples = history.samples("O", "A");
+        let counts = samples.iter().map(|s| s.downloads).collect::<Vec<_>>();
+        assert_eq!(counts, [10, 25]);
+        let delta = client
+            .stats_delta("o", "a", Duration::from_secs(3600))
+            .unwrap();
+        assert_eq!((delta.downloads, delta.likes), (15, 0));
+        assert_eq!(delta.since, samples[0].time);
+        assert!(client.stats_delta("o", "b", Duration::MAX).is_none());
+    }
+}
@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    upload::{files_in, relative_path},
+    FuelError,
+};
+
+/// Name of the manifest written in every extracted asset directory, it isn't listed by `files_in`
+/// so it is never uploaded with the asset
+pub(crate) const MANIFEST_FILE: &str = ".gz-fuel-content.json";
+
+/// Blob store deduplicating the files of extracted assets. Every file is stored once, named after
+/// the SHA-256 of its content, and hard linked into the asset directories that contain it, so
+/// models sharing textures or meshes only take the space of one copy. Files are copied instead
+/// when hard links aren't supported, i.e. across filesystems.
+///
+/// Hard linked files are shared, modifying one in place modifies it in every asset containing it.
+/// Models processed by download hooks get their own copies instead, see `DownloadHook`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentStore {
+    pub root: PathBuf,
+}
+
+/// Files of an extracted asset and the hash of their content, keyed by path relative to the
+/// asset directory
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ContentManifest {
+    pub files: BTreeMap<String, String>,
+}
+
+impl ContentManifest {
+    /// Loads the manifest of an asset directory extracted with a content store
+    pub fn load(dir: &Path) -> Option<Self> {
+        let bytes = fs::read(dir.join(MANIFEST_FILE)).ok()?;
+        serde_json::de::from_slice(&bytes).ok()
+    }
+}
+
+impl ContentStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn blob_path(&self, hash: &str) -> PathBuf {
+        self.root.join(&hash[..2]).join(hash)
+    }
+
+    /// Moves every file of `dir` to the store and replaces it with a link to its blob
+    pub(crate) fn deduplicate(&self, dir: &Path) -> Result<ContentManifest, FuelError> {
+        let io_err = |e: std::io::Error| FuelError::Io(e.to_string());
+        let mut manifest = ContentManifest::default();
+        for path in files_in(dir)? {
+            let hash = format!("{:x}", Sha256::digest(fs::read(&path).map_err(io_err)?));
+            let blob = self.blob_path(&hash);
+            if !blob.exists() {
+                if let Some(parent) = blob.parent() {
+                    fs::create_dir_all(parent).map_err(io_err)?;
+                }
+                if fs::rename(&path, &blob).is_err() {
+                    // Other assets may be linking the same blob concurrently, it must never be
+                    // seen partially written
+                    let tmp = blob.with_extension(format!("{}.tmp", fastrand::u64(..)));
+                    let copied = fs::copy(&path, &tmp).and_then(|_| fs::rename(&tmp, &blob));
+                    if let Err(e) = copied {
+                        fs::remove_file(&tmp).ok();
+                        return Err(io_err(e));
+                    }
+                }
+            }
+            fs::remove_file(&path).ok();
+            if fs::hard_link(&blob, &path).is_err() {
+                fs::copy(&blob, &path).map_err(io_err)?;
+            }
+            manifest.files.insert(relative_path(dir, &path), hash);
+        }
+        let bytes = serde_json::ser::to_string_pretty(&manifest)
+            .map_err(|e| FuelError::Io(e.to_string()))?;
+        fs::write(dir.join(MANIFEST_FILE), bytes).map_err(io_err)?;
+        Ok(manifest)
+    }
+
+    /// Replaces the links of `dir` to the store with copies, so its files can be modified without
+    /// touching other assets. The manifest is removed, it wouldn't match modified files.
+    pub(crate) fn detach(&self, dir: &Path) -> Result<(), FuelError> {
+        let io_err = |e: std::io::Error| FuelError::Io(e.to_string());
+        for path in files_in(dir)? {
+            let tmp = path.with_extension(format!("{}.tmp", fastrand::u64(..)));
+            let copied = fs::copy(&path, &tmp).and_then(|_| fs::rename(&tmp, &path));
+            if let Err(e) = copied {
+                fs::remove_file(&tmp).ok();
+                return Err(io_err(e));
+            }
+        }
+        match fs::remove_file(dir.join(MANIFEST_FILE)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(io_err(e)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Removes the blobs that aren't linked from any asset directory anymore, returns the number
+    /// of removed blobs
+    #[cfg(unix)]
+    pub fn collect_garbage(&self) -> Result<usize, FuelError> {
+        use std::os::unix::fs::MetadataExt;
+        if !self.root.exists() {
+            return Ok(0);
+        }
+        let mut removed = 0;
+        for blob in files_in(&self.root)? {
+            let metadata = fs::metadata(&blob).map_err(|e| FuelError::Io(e.to_string()))?;
+            if metadata.nlink() <= 1 && fs::remove_file(&blob).is_ok() {
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn deduplicate_shared_files() {
+        let dir = TempDir::new();
+        let store = ContentStore::new(dir.0.join("store"));
+        for model in ["a", "b"] {
+            let model = dir.0.join(model);
+            fs::create_dir_all(model.join("meshes")).unwrap();
+            fs::write(model.join("meshes/mesh.dae"), "shared").unwrap();
+            fs::write(model.join("model.sdf"), model.to_string_lossy().as_bytes()).unwrap();
+            let manifest = store.deduplicate(&model).unwrap();
+            assert_eq!(ContentManifest::load(&model), Some(manifest));
+            // The manifest isn't part of the model files, i.e. it isn't uploaded
+            let files = files_in(&model).unwrap();
+            assert_eq!(
+                files,
+                [model.join("meshes/mesh.dae"), model.join("model.sdf")]
+            );
+        }
+        assert_eq!(files_in(&store.root).unwrap().len(), 3);
+        // Leftovers of interrupted copies aren't kept in the store
+        assert!(files_in(&store.root)
+            .unwrap()
+            .iter()
+            .all(|blob| blob.extension().is_none()));
+        assert_eq!(
+            fs::read_to_string(dir.0.join("b/meshes/mesh.dae")).unwrap(),
+            "shared"
+        );
+    }
+}
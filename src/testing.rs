@@ -0,0 +1,103 @@
+//! Helpers shared by the unit tests
+
+use std::path::PathBuf;
+#[cfg(feature = "client")]
+use std::sync::Arc;
+
+use crate::FuelModel;
+#[cfg(feature = "client")]
+use crate::{FuelClient, FuelClientBuilder, HttpBackend, RetryPolicy};
+
+/// Url of the server of the clients built by `builder`
+#[cfg(feature = "client")]
+pub(crate) const SERVER: &str = "https://fuel.test/1.0/";
+
+/// Backend answering every request with the given closure
+#[cfg(feature = "client")]
+pub(crate) struct FakeBackend<F>(pub F);
+
+#[cfg(feature = "client")]
+impl<F> HttpBackend for FakeBackend<F>
+where
+    F: Fn(&ehttp::Request) -> Result<ehttp::Response, String> + Send + Sync,
+{
+    fn fetch_blocking(&self, request: &ehttp::Request) -> Result<ehttp::Response, String> {
+        (self.0)(request)
+    }
+}
+
+#[cfg(feature = "client")]
+pub(crate) fn response(url: &str, status: u16, body: impl Into<Vec<u8>>) -> ehttp::Response {
+    ehttp::Response {
+        url: url.to_owned(),
+        ok: (200..300).contains(&status),
+        status,
+        status_text: String::new(),
+        headers: Default::default(),
+        bytes: body.into(),
+    }
+}
+
+/// A new empty directory, removed when the returned guard is dropped
+pub(crate) struct TempDir(pub PathBuf);
+
+impl TempDir {
+    pub(crate) fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("gz-fuel-test-{}", fastrand::u64(..)));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.0).ok();
+    }
+}
+
+/// Builder of a client of `SERVER` answered by `backend`, without retries and with its cache in
+/// `dir`
+#[cfg(feature = "client")]
+pub(crate) fn builder<F>(dir: &TempDir, backend: F) -> FuelClientBuilder
+where
+    F: Fn(&ehttp::Request) -> Result<ehttp::Response, String> + Send + Sync + 'static,
+{
+    FuelClient::builder()
+        .url(SERVER)
+        .cache_path(dir.0.join("model_cache.json"))
+        .retry_policy(RetryPolicy::none())
+        .backend(Arc::new(FakeBackend(backend)))
+}
+
+pub(crate) fn model(owner: &str, name: &str, updated_at: &str) -> FuelModel {
+    FuelModel {
+        owner: owner.into(),
+        name: name.to_owned(),
+        updated_at: updated_at.to_owned(),
+        ..Default::default()
+    }
+}
+
+/// Response to a listing request with every entry on the first page
+#[cfg(feature = "client")]
+pub(crate) fn listing<T: serde::Serialize>(
+    request: &ehttp::Request,
+    entries: &[T],
+) -> ehttp::Response {
+    match request.url.contains("page=1&") {
+        true => response(&request.url, 200, serde_json::to_vec(entries).unwrap()),
+        false => response(&request.url, 200, "[]"),
+    }
+}
+
+/// Zip archive containing `files`, as `(path, contents)`
+#[cfg(feature = "client")]
+pub(crate) fn zip_archive(files: &[(&str, &str)]) -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for (path, contents) in files {
+        zip.start_file(*path, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        std::io::Write::write_all(&mut zip, contents.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
@@ -0,0 +1,164 @@
+use crossbeam_channel::Sender;
+use futures_lite::future;
+use std::{
+    fs,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use crate::{
+    download::run_pool, resolved_version, uri, AssetKind, DownloadProgress, DownloadRequest,
+    FuelClient, FuelError, FuelModel,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThumbnailResult {
+    pub owner: String,
+    pub name: String,
+    /// Local path of the thumbnail
+    pub result: Result<PathBuf, FuelError>,
+}
+
+impl FuelClient {
+    /// Thumbnails are stored in a `thumbnails` directory next to the model cache file
+    fn thumbnails_dir(&self) -> Option<PathBuf> {
+        let cache_path = self.cache_path.clone().or_else(Self::default_cache_path)?;
+        Some(cache_path.parent()?.join("thumbnails"))
+    }
+
+    fn thumbnail_model_dir(&self, owner: &str, name: &str) -> Option<PathBuf> {
+        Some(
+            self.thumbnails_dir()?
+                .join(self.host())
+                .join(owner.to_lowercase())
+                .join(name.to_lowercase()),
+        )
+    }
+
+    /// Local path of the thumbnail of the latest version of a model that was fetched so far, None
+    /// if the thumbnail was never fetched
+    pub fn thumbnail_path(&self, owner: &str, name: &str) -> Option<PathBuf> {
+        let dir = self.thumbnail_model_dir(owner, name)?;
+        fs::read_dir(dir)
+            .ok()?
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let version = entry.file_name().to_str()?.parse::<u32>().ok()?;
+                let path = entry.path().join("thumbnail.png");
+                path.is_file().then_some((version, path))
+            })
+            .max_by_key(|(version, _)| *version)
+            .map(|(_, path)| path)
+    }
+
+    /// Fetches the thumbnail of the latest version of a model and stores it in the thumbnail
+    /// cache, keyed by owner, model and version. Returns the local path of the thumbnail.
+    pub async fn fetch_thumbnail(&self, owner: &str, name: &str) -> Result<PathBuf, FuelError> {
+        let url = self.endpoint(&uri::thumbnail_path(owner, name));
+        let res = self.fetch(self.get_request(url)).await?;
+        if !res.ok {
+            return Err(self.asset_error(AssetKind::Model, owner, name, res.status));
+        }
+        let version = resolved_version(&res.headers, None);
+        let path = self
+            .thumbnail_model_dir(owner, name)
+            .ok_or_else(|| FuelError::Io("no cache directory available".into()))?
+            .join(version.to_string())
+            .join("thumbnail.png");
+        let io_err = |e: std::io::Error| FuelError::Io(e.to_string());
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(io_err)?;
+        }
+        fs::write(&path, &res.bytes).map_err(io_err)?;
+        Ok(path)
+    }
+
+    pub fn fetch_thumbnail_blocking(&self, owner: &str, name: &str) -> Result<PathBuf, FuelError> {
+        future::block_on(self.fetch_thumbnail(owner, name))
+    }
+
+    /// Returns the cached thumbnail if present, fetches it otherwise
+    pub async fn thumbnail(&self, owner: &str, name: &str) -> Result<PathBuf, FuelError> {
+        let cached = self.thumbnail_path(owner, name);
+        self.record_cache_hit(cached.is_some());
+        match cached {
+            Some(path) => Ok(path),
+            None => self.fetch_thumbnail(owner, name).await,
+        }
+    }
+
+    /// Fetches the thumbnails of `models` that aren't cached yet using up to `concurrency`
+    /// parallel requests, i.e. to warm the preview grid of an asset browser. Results are returned
+    /// in the same order as the models, `progress` is sent a `DownloadProgress` every time a
+    /// thumbnail is done.
+    pub async fn prefetch_thumbnails(
+        &self,
+        models: &[FuelModel],
+        concurrency: usize,
+        progress: Option<Sender<DownloadProgress>>,
+    ) -> Vec<ThumbnailResult> {
+        let client = self.clone();
+        let pool_models = models.to_vec();
+        let (tx, rx) = async_channel::bounded(1);
+        let spawned = thread::Builder::new()
+            .name("gz-fuel-thumbnails".to_owned())
+            .spawn(move || {
+                tx.send_blocking(client.run_thumbnail_pool(&pool_models, concurrency, progress))
+                    .ok();
+            });
+        let error = match spawned {
+            // The channel is only closed without results if the pool panicked
+            Ok(_) => match rx.recv().await {
+                Ok(results) => return results,
+                Err(_) => "the thumbnail pool stopped unexpectedly".to_owned(),
+            },
+            Err(e) => e.to_string(),
+        };
+        models
+            .iter()
+            .map(|model| ThumbnailResult {
+                owner: model.owner.to_string(),
+                name: model.name.clone(),
+                result: Err(FuelError::Io(error.clone())),
+            })
+            .collect()
+    }
+
+    pub fn prefetch_thumbnails_blocking(
+        &self,
+        models: &[FuelModel],
+        concurrency: usize,
+        progress: Option<Sender<DownloadProgress>>,
+    ) -> Vec<ThumbnailResult> {
+        future::block_on(self.prefetch_thumbnails(models, concurrency, progress))
+    }
+
+    fn run_thumbnail_pool(
+        &self,
+        models: &[FuelModel],
+        concurrency: usize,
+        progress: Option<Sender<DownloadProgress>>,
+    ) -> Vec<ThumbnailResult> {
+        let total = models.len();
+        let completed = AtomicUsize::new(0);
+        run_pool(models.iter().collect(), concurrency, |model| {
+            let result = future::block_on(self.thumbnail(&model.owner, &model.name));
+            if let Some(progress) = &progress {
+                progress
+                    .send(DownloadProgress {
+                        request: DownloadRequest::from(model),
+                        succeeded: result.is_ok(),
+                        completed: completed.fetch_add(1, Ordering::Relaxed) + 1,
+                        total,
+                    })
+                    .ok();
+            }
+            ThumbnailResult {
+                owner: model.owner.to_string(),
+                name: model.name.clone(),
+                result,
+            }
+        })
+    }
+}
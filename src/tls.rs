@@ -0,0 +1,69 @@
+use rustls::{
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    ClientConfig, RootCertStore,
+};
+use std::{fmt, path::Path, sync::Arc};
+
+use crate::FuelError;
+
+/// TLS settings for servers using a private PKI, applied to the default `UreqBackend`
+#[derive(Clone)]
+pub struct TlsConfig {
+    pub(crate) client_config: Arc<ClientConfig>,
+}
+
+impl fmt::Debug for TlsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsConfig").finish_non_exhaustive()
+    }
+}
+
+impl TlsConfig {
+    /// Trusts the certificates in the PEM `ca_bundle`, in addition to the public web roots if
+    /// `include_default_roots` is set. `client_auth` is an optional pair of PEM files with a
+    /// client certificate chain and its private key.
+    pub fn load(
+        ca_bundle: Option<&Path>,
+        include_default_roots: bool,
+        client_auth: Option<(&Path, &Path)>,
+    ) -> Result<Self, FuelError> {
+        let tls_err = |e: &dyn fmt::Display| FuelError::Config(format!("tls: {e}"));
+        let mut roots = RootCertStore::empty();
+        if include_default_roots {
+            roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        }
+        if let Some(ca_bundle) = ca_bundle {
+            for cert in CertificateDer::pem_file_iter(ca_bundle).map_err(|e| tls_err(&e))? {
+                roots
+                    .add(cert.map_err(|e| tls_err(&e))?)
+                    .map_err(|e| tls_err(&e))?;
+            }
+        }
+        let builder =
+            ClientConfig::builder_with_provider(rustls::crypto::ring::default_provider().into())
+                .with_protocol_versions(&[&rustls::version::TLS12, &rustls::version::TLS13])
+                .map_err(|e| tls_err(&e))?
+                .with_root_certificates(roots);
+        let config = match client_auth {
+            Some((cert_chain, key)) => {
+                let certs = CertificateDer::pem_file_iter(cert_chain)
+                    .map_err(|e| tls_err(&e))?
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| tls_err(&e))?;
+                let key = PrivateKeyDer::from_pem_file(key).map_err(|e| tls_err(&e))?;
+                builder
+                    .with_client_auth_cert(certs, key)
+                    .map_err(|e| tls_err(&e))?
+            }
+            None => builder.with_no_client_auth(),
+        };
+        Ok(Self {
+            client_config: Arc::new(config),
+        })
+    }
+
+    /// Uses an already built rustls configuration
+    pub fn from_client_config(client_config: Arc<ClientConfig>) -> Self {
+        Self { client_config }
+    }
+}
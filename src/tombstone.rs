@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{CacheDiff, FuelClient, FuelError, FuelModel};
+
+/// Extension of the tombstones file, stored next to the cache file
+const TOMBSTONES_FILE: &str = "tombstones.json";
+
+/// A cached model that was missing from the server catalog in a later refresh
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Tombstone {
+    /// Seconds since the unix epoch of the refresh the model was first missing from
+    pub removed_at: u64,
+    /// Last cached metadata of the model
+    pub model: FuelModel,
+}
+
+/// Removed models keyed by lowercase `owner/name`, stored next to the cache file. Tombstones are
+/// kept until they are forgotten or the model shows up on the server again.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Tombstones {
+    pub models: BTreeMap<String, Tombstone>,
+}
+
+fn tombstone_key(owner: &str, name: &str) -> String {
+    format!("{}/{}", owner.to_lowercase(), name.to_lowercase())
+}
+
+impl Tombstones {
+    /// Adds a tombstone for every model of `before` missing from `after` and removes the ones of
+    /// models in `after`. Returns the models that were newly removed.
+    pub fn record(
+        &mut self,
+        before: &[FuelModel],
+        after: &[FuelModel],
+        time: SystemTime,
+    ) -> Vec<FuelModel> {
+        let removed_at = time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        for model in after {
+            self.models
+                .remove(&tombstone_key(&model.owner, &model.name));
+        }
+        let removed = CacheDiff::between(before, after).removed;
+        for model in &removed {
+            self.models
+                .entry(tombstone_key(&model.owner, &model.name))
+                .or_insert_with(|| Tombstone {
+                    removed_at,
+                    model: model.clone(),
+                });
+        }
+        removed
+    }
+
+    pub fn get(&self, owner: &str, name: &str) -> Option<&Tombstone> {
+        self.models.get(&tombstone_key(owner, name))
+    }
+}
+
+impl FuelClient {
+    /// Models removed from the server as seen by the cache refreshes written to disk, i.e. to
+    /// clean up their downloaded assets. Empty if the tombstones file can't be parsed.
+    pub fn tombstones(&self) -> Tombstones {
+        self.read_sidecar(TOMBSTONES_FILE).unwrap_or_default()
+    }
+
+    /// Removes the tombstone of a model once it was dealt with, returns false if there was none
+    pub fn forget_tombstone(&self, owner: &str, name: &str) -> Result<bool, FuelError> {
+        self.update_sidecar(TOMBSTONES_FILE, |tombstones: &mut Tombstones| {
+            tombstones
+                .models
+                .remove(&tombstone_key(owner, name))
+                .is_some()
+        })
+    }
+
+    /// Records the models of `before` missing from `after`, see `Tombstones::record`
+    pub(crate) fn record_tombstones(
+        &self,
+        before: &[FuelModel],
+        after: &[FuelModel],
+    ) -> Result<Vec<FuelModel>, FuelError> {
+        self.update_sidecar(TOMBSTONES_FILE, |tombstones: &mut Tombstones| {
+            tombstones.record(before, after, SystemTime::now())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TempDir};
+    use std::sync::{Arc, Mutex};
+
+    fn removed(client: &FuelClient) -> Vec<String> {
+        client.tombstones().models.into_keys().collect()
+    }
+
+    #[test]
+    fn refreshes_record_removed_models() {
+        let dir = TempDir::new();
+        let catalog = Arc::new(Mutex::new(Vec::new()));
+        let listed = catalog.clone();
+        let client = testing::builder(&dir, move |req| {
+            Ok(testing::listing(req, &listed.lock().unwrap()))
+        })
+        .build();
+        let refresh = |names: &[&str]| {
+            *catalog.lock().unwrap() = names
+                .iter()
+                .map(|name| testing::model("o", name, "1"))
+                .collect();
+            client.update_cache_blocking(true).unwrap();
+        };
+        refresh(&["a", "B", "c"]);
+        assert!(removed(&client).is_empty());
+        refresh(&["a", "c"]);
+        assert_eq!(removed(&client), ["o/b"]);
+        let tombstone = client.tombstones().get("O", "b").cloned().unwrap();
+        assert_eq!(tombstone.model.name, "B");
+        refresh(&["a"]);
+        assert_eq!(removed(&client), ["o/b", "o/c"]);
+        assert_eq!(client.tombstones().get("o", "b"), Some(&tombstone));
+        // Models back on the server lose their tombstone
+        refresh(&["a", "b"]);
+        assert_eq!(removed(&client), ["o/c"]);
+
+        assert_eq!(client.forget_tombstone("o", "C"), Ok(true));
+        assert_eq!(client.forget_tombstone("o", "c"), Ok(false));
+        assert!(removed(&client).is_empty());
+    }
+}
@@ -0,0 +1,414 @@
+//! Interactive browser of the model cache, drawn with plain ANSI escape sequences
+
+use gz_fuel::{FuelClient, FuelModel};
+use std::{
+    cmp::Ordering,
+    io::{self, Read, Write},
+};
+
+/// Column the model list is sorted by, cycled with Tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    Name,
+    Owner,
+    Downloads,
+    Likes,
+    Size,
+    Updated,
+}
+
+impl Column {
+    const ALL: [Column; 6] = [
+        Column::Name,
+        Column::Owner,
+        Column::Downloads,
+        Column::Likes,
+        Column::Size,
+        Column::Updated,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            Column::Name => "name",
+            Column::Owner => "owner",
+            Column::Downloads => "downloads",
+            Column::Likes => "likes",
+            Column::Size => "size",
+            Column::Updated => "updated",
+        }
+    }
+
+    fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|c| *c == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    /// Numbers and dates are sorted descending, text ascending
+    fn compare(self, a: &FuelModel, b: &FuelModel) -> Ordering {
+        match self {
+            Column::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            Column::Owner => a.owner.to_lowercase().cmp(&b.owner.to_lowercase()),
+            Column::Downloads => b.downloads.cmp(&a.downloads),
+            Column::Likes => b.likes.cmp(&a.likes),
+            Column::Size => b.filesize.cmp(&a.filesize),
+            Column::Updated => b.updated_at.cmp(&a.updated_at),
+        }
+    }
+}
+
+enum Key {
+    Char(char),
+    Backspace,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Tab,
+    Enter,
+    Quit,
+}
+
+/// Puts the terminal in raw mode and restores it when dropped
+struct RawMode {
+    original: libc::termios,
+}
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        // SAFETY: termios is a plain C struct filled by tcgetattr
+        unsafe {
+            let mut original = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut raw = original;
+            libc::cfmakeraw(&mut raw);
+            // Reads return after 100ms without input, so lone escapes can be told apart from
+            // escape sequences
+            raw.c_cc[libc::VMIN] = 0;
+            raw.c_cc[libc::VTIME] = 1;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Self { original })
+        }
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        // SAFETY: restores the attributes read in `enable`
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+/// Switches to the alternate screen with a hidden cursor and switches back when dropped, panics
+/// included
+struct AltScreen;
+
+impl AltScreen {
+    fn enter() -> io::Result<Self> {
+        let mut stdout = io::stdout();
+        stdout.write_all(b"\x1b[?1049h\x1b[?25l")?;
+        stdout.flush()?;
+        Ok(Self)
+    }
+}
+
+impl Drop for AltScreen {
+    fn drop(&mut self) {
+        let mut stdout = io::stdout();
+        stdout.write_all(b"\x1b[?25h\x1b[?1049l").ok();
+        stdout.flush().ok();
+    }
+}
+
+/// Terminal size as (columns, rows)
+fn terminal_size() -> (usize, usize) {
+    // SAFETY: winsize is a plain C struct filled by the ioctl
+    unsafe {
+        let mut size: libc::winsize = std::mem::zeroed();
+        if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) == 0 && size.ws_col > 0 {
+            (size.ws_col as usize, size.ws_row as usize)
+        } else {
+            (80, 24)
+        }
+    }
+}
+
+/// Keys pressed since the last read, several can arrive at once when pasting or typing fast
+fn read_keys(stdin: &mut io::Stdin) -> io::Result<Vec<Key>> {
+    let mut buf = [0u8; 64];
+    let n = stdin.read(&mut buf)?;
+    let text = String::from_utf8_lossy(&buf[..n]);
+    let mut chars = text.chars().peekable();
+    let mut keys = Vec::new();
+    while let Some(c) = chars.next() {
+        let key = match c {
+            '\x03' | '\x04' => Key::Quit,
+            '\x1b' if chars.peek() != Some(&'[') => Key::Quit,
+            '\x1b' => {
+                chars.next();
+                match (chars.next(), chars.peek()) {
+                    (Some('A'), _) => Key::Up,
+                    (Some('B'), _) => Key::Down,
+                    (Some('5'), Some('~')) => {
+                        chars.next();
+                        Key::PageUp
+                    }
+                    (Some('6'), Some('~')) => {
+                        chars.next();
+                        Key::PageDown
+                    }
+                    _ => continue,
+                }
+            }
+            '\t' => Key::Tab,
+            '\r' | '\n' => Key::Enter,
+            '\x7f' | '\x08' => Key::Backspace,
+            c if c.is_control() => continue,
+            c => Key::Char(c),
+        };
+        keys.push(key);
+    }
+    Ok(keys)
+}
+
+/// Cuts `text` to `width` characters, padding it with spaces
+fn fit(text: &str, width: usize) -> String {
+    let mut fitted = text.chars().take(width).collect::<String>();
+    let len = fitted.chars().count();
+    fitted.extend(std::iter::repeat_n(' ', width - len));
+    fitted
+}
+
+struct Browser<'a> {
+    client: &'a FuelClient,
+    models: Vec<FuelModel>,
+    query: String,
+    sort: Column,
+    /// Indices in `models` of the models matching the query, in display order
+    visible: Vec<usize>,
+    selected: usize,
+    scroll: usize,
+    status: String,
+}
+
+impl<'a> Browser<'a> {
+    fn new(client: &'a FuelClient, models: Vec<FuelModel>) -> Self {
+        let mut browser = Self {
+            client,
+            models,
+            query: String::new(),
+            sort: Column::Downloads,
+            visible: Vec::new(),
+            selected: 0,
+            scroll: 0,
+            status: "type to search, tab: sort, enter: download, esc: quit".to_owned(),
+        };
+        browser.refilter();
+        browser
+    }
+
+    /// Matches the query against names, owners, tags and descriptions, case insensitively
+    fn refilter(&mut self) {
+        let query = self.query.to_lowercase();
+        let mut visible = self
+            .models
+            .iter()
+            .enumerate()
+            .filter(|(_, model)| {
+                query.is_empty()
+                    || model.name.to_lowercase().contains(&query)
+                    || model.owner.to_lowercase().contains(&query)
+                    || model.tags.iter().any(|t| t.to_lowercase().contains(&query))
+                    || model.description.to_lowercase().contains(&query)
+            })
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+        visible.sort_by(|a, b| self.sort.compare(&self.models[*a], &self.models[*b]));
+        self.visible = visible;
+        self.selected = 0;
+        self.scroll = 0;
+    }
+
+    fn selected_model(&self) -> Option<&FuelModel> {
+        self.visible
+            .get(self.selected)
+            .map(|idx| &self.models[*idx])
+    }
+
+    fn move_selection(&mut self, delta: isize, list_height: usize) {
+        let last = self.visible.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + list_height {
+            self.scroll = self.selected + 1 - list_height;
+        }
+    }
+
+    fn download_selected(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let Some(model) = self.selected_model().cloned() else {
+            return Ok(());
+        };
+        self.status = format!("downloading {}/{}...", model.owner, model.name);
+        self.draw(out)?;
+        self.status = match self
+            .client
+            .download_model_blocking(&model.owner, &model.name, None)
+        {
+            Ok(downloaded) => format!(
+                "downloaded {}/{}@{} to {}",
+                model.owner,
+                model.name,
+                downloaded.version,
+                downloaded.path.display()
+            ),
+            Err(e) => format!("failed downloading {}/{}: {e}", model.owner, model.name),
+        };
+        Ok(())
+    }
+
+    /// Rows used by the search line, header, preview and status line
+    const CHROME_ROWS: usize = 9;
+
+    fn list_height() -> usize {
+        terminal_size().1.saturating_sub(Self::CHROME_ROWS).max(1)
+    }
+
+    fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        let (width, _) = terminal_size();
+        let list_height = Self::list_height();
+        let mut frame = String::from("\x1b[H\x1b[2J");
+        let line = |frame: &mut String, text: &str| {
+            frame.push_str(&fit(text, width));
+            frame.push_str("\r\n");
+        };
+        line(
+            &mut frame,
+            &format!(
+                "search: {}  ({} of {} models)",
+                self.query,
+                self.visible.len(),
+                self.models.len()
+            ),
+        );
+        let name_width = width.saturating_sub(62).max(10);
+        let header = Column::ALL
+            .iter()
+            .map(|column| {
+                let title = if *column == self.sort {
+                    format!("[{}]", column.title())
+                } else {
+                    column.title().to_owned()
+                };
+                match column {
+                    Column::Name => fit(&title, name_width),
+                    Column::Owner => fit(&title, 20),
+                    Column::Updated => fit(&title, 11),
+                    _ => fit(&title, 10),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        frame.push_str("\x1b[1m");
+        line(&mut frame, &header);
+        frame.push_str("\x1b[0m");
+        for row in 0..list_height {
+            let pos = self.scroll + row;
+            let Some(model) = self.visible.get(pos).map(|idx| &self.models[*idx]) else {
+                line(&mut frame, "");
+                continue;
+            };
+            let text = format!(
+                "{} {} {:>10} {:>10} {:>8.1}MB {}",
+                fit(&model.name, name_width),
+                fit(&model.owner, 20),
+                model.downloads,
+                model.likes,
+                model.filesize as f64 / 1_000_000.0,
+                model.updated_at.get(..10).unwrap_or(&model.updated_at),
+            );
+            if pos == self.selected {
+                frame.push_str("\x1b[7m");
+                line(&mut frame, &text);
+                frame.push_str("\x1b[0m");
+            } else {
+                line(&mut frame, &text);
+            }
+        }
+        line(&mut frame, &"-".repeat(width));
+        let preview = self
+            .selected_model()
+            .map(FuelModel::summary)
+            .unwrap_or_default();
+        let mut preview = preview.lines();
+        for _ in 0..Self::CHROME_ROWS - 4 {
+            line(&mut frame, preview.next().unwrap_or_default());
+        }
+        frame.push_str(&fit(&self.status, width));
+        out.write_all(frame.as_bytes())?;
+        out.flush()
+    }
+
+    /// Returns false if the browser should quit
+    fn handle_key(&mut self, key: &Key, out: &mut impl Write) -> io::Result<bool> {
+        let list_height = Self::list_height();
+        let page = list_height as isize;
+        match key {
+            Key::Quit => return Ok(false),
+            Key::Char(c) => {
+                self.query.push(*c);
+                self.refilter();
+            }
+            Key::Backspace => {
+                self.query.pop();
+                self.refilter();
+            }
+            Key::Up => self.move_selection(-1, list_height),
+            Key::Down => self.move_selection(1, list_height),
+            Key::PageUp => self.move_selection(-page, list_height),
+            Key::PageDown => self.move_selection(page, list_height),
+            Key::Tab => {
+                self.sort = self.sort.next();
+                self.refilter();
+            }
+            Key::Enter => self.download_selected(out)?,
+        }
+        Ok(true)
+    }
+
+    fn run(&mut self) -> io::Result<()> {
+        let mut stdin = io::stdin();
+        // Dropped in reverse order, the screen is restored before the terminal attributes
+        let _raw = RawMode::enable()?;
+        let _screen = AltScreen::enter()?;
+        let mut stdout = io::stdout().lock();
+        self.draw(&mut stdout)?;
+        loop {
+            let keys = read_keys(&mut stdin)?;
+            for key in &keys {
+                if !self.handle_key(key, &mut stdout)? {
+                    return Ok(());
+                }
+            }
+            if !keys.is_empty() {
+                self.draw(&mut stdout)?;
+            }
+        }
+    }
+}
+
+/// Browses the cached models until the user quits
+pub fn run(client: &FuelClient) -> io::Result<()> {
+    let models = client.models().clone().unwrap_or_default().models;
+    if models.is_empty() {
+        return Err(io::Error::other(
+            "the model cache is empty, run the update command first",
+        ));
+    }
+    Browser::new(client, models).run()
+}
@@ -0,0 +1,62 @@
+use crossbeam_channel::Receiver;
+use futures_lite::future;
+use std::thread;
+
+use crate::{FuelClient, FuelError, FuelModel, PageProgress};
+
+/// Handle to a cache update running in a background thread, meant to be polled from immediate mode
+/// GUI update loops. The client the update was started from sees the new models once it finishes.
+pub struct UpdateHandle {
+    progress: Receiver<FuelModel>,
+    pages: Receiver<PageProgress>,
+    result: Receiver<Result<Vec<FuelModel>, FuelError>>,
+}
+
+impl UpdateHandle {
+    /// Models fetched since the last call, never blocks
+    pub fn try_progress(&self) -> Vec<FuelModel> {
+        self.progress.try_iter().collect()
+    }
+
+    /// Latest page fetched since the last call, never blocks
+    pub fn try_page(&self) -> Option<PageProgress> {
+        self.pages.try_iter().last()
+    }
+
+    /// Returns the update result once it finished, None while it's still running or if the result
+    /// was already returned by a previous call
+    pub fn try_finish(&self) -> Option<Result<Vec<FuelModel>, FuelError>> {
+        self.result.try_recv().ok()
+    }
+}
+
+impl FuelClient {
+    /// Starts a cache update in a background thread and returns immediately, the fetched pages
+    /// are reported to the handle rather than to `page_progress`
+    pub fn start_update_cache(&self, write_to_disk: bool) -> UpdateHandle {
+        let (progress_tx, progress) = crossbeam_channel::unbounded();
+        let (pages_tx, pages) = crossbeam_channel::unbounded();
+        let (result_tx, result) = crossbeam_channel::bounded(1);
+        let mut client = self.clone();
+        client.page_progress = Some(pages_tx);
+        let spawned = thread::Builder::new()
+            .name("gz-fuel-update".to_owned())
+            .spawn({
+                let result_tx = result_tx.clone();
+                move || {
+                    let res = future::block_on(
+                        client.update_cache_with_progress(write_to_disk, Some(progress_tx)),
+                    );
+                    result_tx.send(res).ok();
+                }
+            });
+        if let Err(e) = spawned {
+            result_tx.send(Err(FuelError::Io(e.to_string()))).ok();
+        }
+        UpdateHandle {
+            progress,
+            pages,
+            result,
+        }
+    }
+}
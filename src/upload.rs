@@ -0,0 +1,192 @@
+use futures_lite::future;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{store::MANIFEST_FILE, uri, AssetKind, FuelClient, FuelError};
+
+/// `multipart/form-data` request body
+pub(crate) struct Multipart {
+    boundary: String,
+    body: Vec<u8>,
+}
+
+impl Multipart {
+    pub(crate) fn new() -> Self {
+        let boundary = format!(
+            "gz-fuel-{}",
+            std::iter::repeat_with(fastrand::alphanumeric)
+                .take(24)
+                .collect::<String>()
+        );
+        Self {
+            boundary,
+            body: Vec::new(),
+        }
+    }
+
+    pub(crate) fn text(&mut self, name: &str, value: &str) {
+        self.body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n",
+                self.boundary
+            )
+            .as_bytes(),
+        );
+    }
+
+    pub(crate) fn file(&mut self, name: &str, filename: &str, contents: &[u8]) {
+        self.body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{name}\"; filename=\"{}\"\r\n\
+                 Content-Type: application/octet-stream\r\n\r\n",
+                self.boundary,
+                filename.replace('"', "%22"),
+            )
+            .as_bytes(),
+        );
+        self.body.extend_from_slice(contents);
+        self.body.extend_from_slice(b"\r\n");
+    }
+
+    /// Returns the `Content-Type` header value and the body
+    pub(crate) fn finish(mut self) -> (String, Vec<u8>) {
+        self.body
+            .extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+        (
+            format!("multipart/form-data; boundary={}", self.boundary),
+            self.body,
+        )
+    }
+}
+
+impl FuelClient {
+    /// Uploads the content of `dir` as a new version of an existing model, the version number is
+    /// assigned by the server and returned. Requires a token with write access to the model.
+    pub async fn upload_model_version(
+        &self,
+        owner: &str,
+        name: &str,
+        dir: impl AsRef<Path>,
+    ) -> Result<u32, FuelError> {
+        let dir = dir.as_ref();
+        let files = files_in(dir)?;
+        if files.is_empty() {
+            return Err(FuelError::Io(format!(
+                "no files to upload in {}",
+                dir.display()
+            )));
+        }
+        let mut form = Multipart::new();
+        for path in files {
+            let contents = fs::read(&path).map_err(|e| FuelError::Io(e.to_string()))?;
+            // Paths relative to the model root, the server recreates the hierarchy from them
+            form.file("file", &relative_path(dir, &path), &contents);
+        }
+        let (content_type, body) = form.finish();
+        let url = self.endpoint(&uri::asset_path(AssetKind::Model, owner, name));
+        let mut req = self.get_request(url);
+        req.method = "PATCH".to_owned();
+        req.headers.insert("Content-Type", content_type);
+        req.body = body;
+        let res = self.fetch(req).await?;
+        if !res.ok {
+            return Err(FuelError::from_status(res.status));
+        }
+        if let Some(version) = res
+            .headers
+            .get("x-ign-resource-version")
+            .and_then(|v| v.trim().parse().ok())
+        {
+            return Ok(version);
+        }
+        // Ask the server which version the latest one is
+        self.latest_version(AssetKind::Model, owner, name).await
+    }
+
+    pub fn upload_model_version_blocking(
+        &self,
+        owner: &str,
+        name: &str,
+        dir: impl AsRef<Path>,
+    ) -> Result<u32, FuelError> {
+        future::block_on(self.upload_model_version(owner, name, dir))
+    }
+}
+
+/// All the files under `dir`, recursively, sorted by path. The content store manifest of `dir`
+/// is left out, it isn't part of the asset.
+pub(crate) fn files_in(dir: &Path) -> Result<Vec<PathBuf>, FuelError> {
+    let io_err = |e: std::io::Error| FuelError::Io(e.to_string());
+    let manifest = dir.join(MANIFEST_FILE);
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir).map_err(io_err)? {
+            let entry = entry.map_err(io_err)?;
+            if entry.file_type().map_err(io_err)?.is_dir() {
+                dirs.push(entry.path());
+            } else if entry.path() != manifest {
+                files.push(entry.path());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Path of `path` relative to `dir`, with `/` separators
+pub(crate) fn relative_path(dir: &Path, path: &Path) -> String {
+    path.strip_prefix(dir)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TempDir};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn upload_model_version() {
+        let dir = TempDir::new();
+        let model = dir.0.join("table");
+        fs::create_dir_all(model.join("meshes")).unwrap();
+        fs::write(model.join("model.sdf"), "<sdf/>").unwrap();
+        fs::write(model.join("meshes/table.dae"), "mesh").unwrap();
+        fs::write(model.join(MANIFEST_FILE), "{}").unwrap();
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let log = sent.clone();
+        let client = testing::builder(&dir, move |req| {
+            log.lock().unwrap().push(req.clone());
+            let mut res = testing::response(&req.url, 200, "");
+            res.headers.insert("X-Ign-Resource-Version", "3");
+            Ok(res)
+        })
+        .token("secret")
+        .build();
+        assert_eq!(
+            client.upload_model_version_blocking("o", "table", &model),
+            Ok(3)
+        );
+        // Nothing is sent for an empty directory
+        let empty = dir.0.join("empty");
+        fs::create_dir_all(&empty).unwrap();
+        let res = client.upload_model_version_blocking("o", "table", &empty);
+        assert!(matches!(res, Err(FuelError::Io(_))));
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].method, "PATCH");
+        assert_eq!(sent[0].url, format!("{}o/models/table", testing::SERVER));
+        assert_eq!(sent[0].headers.get("Private-token"), Some("secret"));
+        let body = String::from_utf8_lossy(&sent[0].body);
+        assert!(body.contains("filename=\"meshes/table.dae\""));
+        assert!(body.contains("filename=\"model.sdf\""));
+        assert!(!body.contains(MANIFEST_FILE));
+    }
+}
@@ -0,0 +1,421 @@
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+
+use crate::AssetKind;
+
+pub const DEFAULT_URL: &str = "https://fuel.gazebosim.org/1.0/";
+/// Server of `DEFAULT_URL`, without the API version
+pub const DEFAULT_SERVER: &str = "https://fuel.gazebosim.org";
+pub const DEFAULT_API_VERSION: &str = "1.0";
+/// API versions the client knows the endpoints of, newest first
+pub const KNOWN_API_VERSIONS: &[&str] = &["1.0"];
+
+/// Characters that can't appear unescaped in a url path segment
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Characters that can't appear unescaped in a query parameter value
+#[cfg(feature = "client")]
+const QUERY_VALUE: &AsciiSet = &SEGMENT.add(b'&').add(b'=').add(b'+');
+
+/// Hosts that served Fuel before the move to gazebosim.org, with the host that replaced them
+const LEGACY_HOSTS: [(&str, &str); 2] = [
+    ("fuel.ignitionrobotics.org", "fuel.gazebosim.org"),
+    ("app.ignitionrobotics.org", "app.gazebosim.org"),
+];
+
+/// Rewrites urls or scheme-less URIs pointing to a legacy ignitionrobotics.org host to the
+/// equivalent gazebosim.org one, other urls are returned unchanged
+pub fn normalize_url(url: &str) -> String {
+    let (scheme, rest) = match url.find("://") {
+        Some(idx) => url.split_at(idx + 3),
+        None => ("", url),
+    };
+    let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let host = LEGACY_HOSTS
+        .iter()
+        .find(|(legacy, _)| host.eq_ignore_ascii_case(legacy))
+        .map(|(_, current)| *current)
+        .unwrap_or(host);
+    format!("{scheme}{host}{path}")
+}
+
+/// Base url of version `version` of the API of the server at `server`, i.e.
+/// `https://fuel.gazebosim.org/1.0/`. `server` can include a path prefix.
+pub fn api_url(server: &str, version: &str) -> String {
+    format!(
+        "{}/{}/",
+        server.trim_end_matches('/'),
+        version.trim_matches('/')
+    )
+}
+
+/// Splits an API url into the server url and the API version, the last path segment made of
+/// digits and dots. The version is None for servers mounted without one.
+pub fn split_api_url(url: &str) -> (String, Option<String>) {
+    let url = url.trim_end_matches('/');
+    let path_start = url.find("://").map_or(0, |idx| idx + 3);
+    let is_version = |s: &str| {
+        s.starts_with(|c: char| c.is_ascii_digit())
+            && s.chars().all(|c| c.is_ascii_digit() || c == '.')
+    };
+    match url.rsplit_once('/') {
+        // The host itself isn't a version
+        Some((server, version)) if server.len() >= path_start && is_version(version) => {
+            (server.to_owned(), Some(version.to_owned()))
+        }
+        _ => (url.to_owned(), None),
+    }
+}
+
+/// A model on a given server, as parsed from any of the urls pointing to it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ModelRef {
+    /// API url of the server, i.e. `https://fuel.gazebosim.org/1.0/`
+    pub server: String,
+    pub owner: String,
+    pub name: String,
+    pub version: Option<u32>,
+}
+
+impl ModelRef {
+    /// Parses API urls (`https://fuel.gazebosim.org/1.0/OpenRobotics/models/Table/2`) and website
+    /// browse urls (`https://app.gazebosim.org/OpenRobotics/fuel/models/Table`), with or without
+    /// trailing segments like `/tip/files/model.sdf`. The server of website urls is assumed to
+    /// serve the default API version on the `fuel.` host, legacy ignitionrobotics.org hosts are
+    /// normalized.
+    pub fn parse(url: &str) -> Option<Self> {
+        let url = normalize_url(url.trim());
+        let (scheme, rest) = match url.find("://") {
+            Some(idx) => url.split_at(idx + 3),
+            None => ("https://", url.as_str()),
+        };
+        let rest = rest.split(['?', '#']).next()?;
+        let (host, path) = rest.split_at(rest.find('/')?);
+        let segments = path
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+        let models = segments
+            .iter()
+            .position(|s| s.eq_ignore_ascii_case("models"))
+            .filter(|idx| *idx >= 1)?;
+        let is_version = |s: &str| s.chars().all(|c| c.is_ascii_digit() || c == '.');
+        let is_website =
+            models == 2 && segments[1].eq_ignore_ascii_case("fuel") && !is_version(segments[0]);
+        let (server, owner) = if is_website {
+            let host = match host.get(..4) {
+                Some(app) if app.eq_ignore_ascii_case("app.") => format!("fuel.{}", &host[4..]),
+                _ => host.to_owned(),
+            };
+            (
+                format!("{scheme}{host}/{DEFAULT_API_VERSION}/"),
+                segments[0],
+            )
+        } else {
+            let prefix = segments[..models - 1]
+                .iter()
+                .map(|s| format!("{s}/"))
+                .collect::<String>();
+            (format!("{scheme}{host}/{prefix}"), segments[models - 1])
+        };
+        let name = segments.get(models + 1)?;
+        // `tip` is the latest version, anything after the version (i.e. `files/model.sdf` or the
+        // archive name) is ignored, as are trailing segments of urls without a version
+        let version = segments
+            .get(models + 2)
+            .and_then(|version| version.parse().ok());
+        let decode = |s: &str| {
+            percent_decode_str(s)
+                .decode_utf8()
+                .ok()
+                .map(|s| s.into_owned())
+        };
+        Some(Self {
+            server,
+            owner: decode(owner)?,
+            name: decode(name)?,
+            version,
+        })
+    }
+}
+
+/// Splits a model URI of the form `[scheme://]host/version/owner/models/name[/version]` into its
+/// owner, name and version, provided it belongs to the server at `base_url`
+#[cfg(feature = "client")]
+pub(crate) fn parse_model_uri(base_url: &str, uri: &str) -> Option<(String, String, Option<u32>)> {
+    let strip_scheme = |u: &str| -> String {
+        let u = normalize_url(u);
+        match u.find("://") {
+            Some(idx) => u[idx + 3..].to_owned(),
+            None => u,
+        }
+    };
+    let base = strip_scheme(base_url);
+    let uri = strip_scheme(uri);
+    let base = base.trim_end_matches('/');
+    let has_base = uri
+        .get(..base.len())
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(base));
+    if uri.len() <= base.len() || !has_base {
+        return None;
+    }
+    let mut segments = uri[base.len()..].strip_prefix('/')?.split('/');
+    let owner = segments.next()?;
+    if !segments.next()?.eq_ignore_ascii_case("models") {
+        return None;
+    }
+    let name = segments.next()?;
+    let version = segments.next().and_then(|v| v.parse().ok());
+    let decode = |s: &str| {
+        percent_decode_str(s)
+            .decode_utf8()
+            .ok()
+            .map(|s| s.into_owned())
+    };
+    Some((decode(owner)?, decode(name)?, version))
+}
+
+/// Percent encodes an owner or asset name to be used as a url path segment
+pub(crate) fn encode_segment(segment: &str) -> String {
+    utf8_percent_encode(segment, SEGMENT).to_string()
+}
+
+/// Percent encodes a query parameter value
+#[cfg(feature = "client")]
+pub(crate) fn encode_query_value(value: &str) -> String {
+    utf8_percent_encode(value, QUERY_VALUE).to_string()
+}
+
+/// Joins a path relative to an API url to it
+fn join(base_url: &str, path: &str) -> String {
+    format!("{}/{path}", base_url.trim_end_matches('/'))
+}
+
+/// Path of an asset relative to the API url, i.e. `{owner}/models/{name}`
+pub(crate) fn asset_path(kind: AssetKind, owner: &str, name: &str) -> String {
+    format!(
+        "{}/{}/{}",
+        encode_segment(owner),
+        kind.path_segment(),
+        encode_segment(name),
+    )
+}
+
+/// Path of an asset archive relative to the API url, `tip` is used for the latest version
+pub(crate) fn archive_path(
+    kind: AssetKind,
+    owner: &str,
+    name: &str,
+    version: Option<u32>,
+) -> String {
+    format!(
+        "{}/{}/{}.zip",
+        asset_path(kind, owner, name),
+        version.map_or_else(|| "tip".to_owned(), |v| v.to_string()),
+        encode_segment(name),
+    )
+}
+
+/// Path of the first thumbnail of the latest version of a model relative to the API url
+pub(crate) fn thumbnail_path(owner: &str, name: &str) -> String {
+    format!(
+        "{}/tip/files/thumbnails/1.png",
+        asset_path(AssetKind::Model, owner, name)
+    )
+}
+
+/// Api url of an asset, i.e. `{base_url}{owner}/models/{name}`
+pub(crate) fn asset_url(base_url: &str, kind: AssetKind, owner: &str, name: &str) -> String {
+    join(base_url, &asset_path(kind, owner, name))
+}
+
+/// Url of an asset archive on the server at `base_url`, `tip` is used for the latest version
+pub(crate) fn archive_url(
+    base_url: &str,
+    kind: AssetKind,
+    owner: &str,
+    name: &str,
+    version: Option<u32>,
+) -> String {
+    join(base_url, &archive_path(kind, owner, name, version))
+}
+
+/// Website browse url of an asset, the website is assumed to be served on the `app.` host of
+/// servers whose api is on the `fuel.` host
+pub(crate) fn website_url(base_url: &str, kind: AssetKind, owner: &str, name: &str) -> String {
+    let url = normalize_url(base_url);
+    let (scheme, rest) = match url.find("://") {
+        Some(idx) => url.split_at(idx + 3),
+        None => ("https://", url.as_str()),
+    };
+    let host = rest.split('/').next().unwrap_or_default();
+    let host = match host.get(..5) {
+        Some(fuel) if fuel.eq_ignore_ascii_case("fuel.") => format!("app.{}", &host[5..]),
+        _ => host.to_owned(),
+    };
+    format!(
+        "{scheme}{host}/{}/fuel/{}/{}",
+        encode_segment(owner),
+        kind.path_segment(),
+        encode_segment(name),
+    )
+}
+
+/// Url of the first thumbnail of the latest version of a model
+pub(crate) fn thumbnail_url(base_url: &str, owner: &str, name: &str) -> String {
+    join(base_url, &thumbnail_path(owner, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model_ref(server: &str, owner: &str, name: &str, version: Option<u32>) -> Option<ModelRef> {
+        Some(ModelRef {
+            server: server.to_owned(),
+            owner: owner.to_owned(),
+            name: name.to_owned(),
+            version,
+        })
+    }
+
+    #[test]
+    fn parse_api_urls() {
+        let table = |version| model_ref(BASE, "OpenRobotics", "Table", version);
+        let parse = |path: &str| ModelRef::parse(&format!("{BASE}OpenRobotics/models/{path}"));
+        assert_eq!(parse("Table"), table(None));
+        assert_eq!(parse("Table/2"), table(Some(2)));
+        assert_eq!(parse("Table/tip"), table(None));
+        assert_eq!(parse("Table/tip/files/meshes/table.dae"), table(None));
+        assert_eq!(parse("Table/3/table.zip"), table(Some(3)));
+        assert_eq!(parse("Table/files/model.sdf"), table(None));
+        assert_eq!(parse("Table/?page=1"), table(None));
+        assert_eq!(
+            ModelRef::parse("https://fuel.gazebosim.org/1.0/OpenRobotics/models"),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_website_urls() {
+        assert_eq!(
+            ModelRef::parse("https://app.gazebosim.org/OpenRobotics/fuel/models/Table/tip"),
+            model_ref(BASE, "OpenRobotics", "Table", None)
+        );
+        assert_eq!(
+            ModelRef::parse("app.ignitionrobotics.org/OpenRobotics/fuel/models/Big%20Table/4"),
+            model_ref(BASE, "OpenRobotics", "Big Table", Some(4))
+        );
+    }
+
+    const BASE: &str = "https://fuel.gazebosim.org/1.0/";
+
+    #[test]
+    fn split_api_urls() {
+        let split = split_api_url;
+        let versioned = |server: &str, version: &str| (server.to_owned(), Some(version.to_owned()));
+        assert_eq!(split(BASE), versioned("https://fuel.gazebosim.org", "1.0"));
+        assert_eq!(
+            split("https://example.com/fuel/2.1"),
+            versioned("https://example.com/fuel", "2.1")
+        );
+        assert_eq!(
+            split("https://example.com/fuel/"),
+            ("https://example.com/fuel".to_owned(), None)
+        );
+        // Hosts and non version segments aren't versions
+        assert_eq!(
+            split("http://10.0.0.1"),
+            ("http://10.0.0.1".to_owned(), None)
+        );
+        assert_eq!(
+            split("https://example.com/.1"),
+            ("https://example.com/.1".to_owned(), None)
+        );
+        assert_eq!(
+            split_api_url(&api_url("https://example.com/", "1.0"))
+                .1
+                .as_deref(),
+            Some("1.0")
+        );
+    }
+
+    #[test]
+    fn asset_urls() {
+        assert_eq!(
+            archive_url(
+                "https://fuel.test/1.0",
+                AssetKind::World,
+                "o",
+                "My World",
+                Some(2)
+            ),
+            "https://fuel.test/1.0/o/worlds/My%20World/2/My%20World.zip"
+        );
+        assert_eq!(
+            thumbnail_url(BASE, "o", "m"),
+            "https://fuel.gazebosim.org/1.0/o/models/m/tip/files/thumbnails/1.png"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "client")]
+    fn parse_model_uri_of_server() {
+        assert_eq!(
+            parse_model_uri(
+                BASE,
+                "https://fuel.gazebosim.org/1.0/OpenRobotics/models/Table/2"
+            ),
+            Some(("OpenRobotics".into(), "Table".into(), Some(2)))
+        );
+        // Scheme-less, legacy host, percent encoded name
+        assert_eq!(
+            parse_model_uri(BASE, "fuel.ignitionrobotics.org/1.0/o/Models/Big%20Table"),
+            Some(("o".into(), "Big Table".into(), None))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "client")]
+    fn parse_model_uri_of_other_server() {
+        assert_eq!(
+            parse_model_uri(BASE, "https://example.com/1.0/o/models/m"),
+            None
+        );
+        assert_eq!(
+            parse_model_uri(BASE, "https://fuel.gazebosim.org/1.0/"),
+            None
+        );
+        assert_eq!(
+            parse_model_uri(BASE, "https://fuel.gazebosim.org/1.0/o/worlds/w"),
+            None
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "client")]
+    fn parse_model_uri_non_ascii() {
+        // The length of the base falls in the middle of a multi byte character
+        assert_eq!(
+            parse_model_uri(BASE, "fuel.gazebosim.org/1.éé/o/models/m"),
+            None
+        );
+        assert_eq!(
+            parse_model_uri(
+                BASE,
+                "https://fuel.gazebosim.org/1.0/R%C3%A9my/models/Mod%C3%A8le"
+            ),
+            Some(("Rémy".into(), "Modèle".into(), None))
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use crossbeam_channel::Sender;
+use futures_lite::future;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{AssetKind, FuelClient, FuelError, FuelModel};
+
+/// Extension of the watchlist file, stored next to the cache file
+const WATCHLIST_FILE: &str = "watchlist.json";
+
+/// A watched model as seen by the last check, None until it was checked once
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WatchedModel {
+    pub owner: String,
+    pub name: String,
+    pub version: Option<u32>,
+    pub model: Option<FuelModel>,
+}
+
+/// Watched models keyed by lowercase `owner/name`, stored next to the cache file
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Watchlist {
+    pub models: BTreeMap<String, WatchedModel>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchEventKind {
+    NewVersion {
+        previous: u32,
+        version: u32,
+    },
+    /// The metadata changed without a new version, changes of downloads and likes are ignored
+    MetadataChanged,
+    /// The server doesn't have the model anymore, it stays in the watchlist
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchEvent {
+    pub owner: String,
+    pub name: String,
+    pub kind: WatchEventKind,
+    /// Metadata as of this check, None for removed models
+    pub model: Option<FuelModel>,
+}
+
+fn watch_key(owner: &str, name: &str) -> String {
+    format!("{}/{}", owner.to_lowercase(), name.to_lowercase())
+}
+
+/// Whether anything but the counters changed
+fn metadata_changed(before: &FuelModel, after: &FuelModel) -> bool {
+    let without_counters = |model: &FuelModel| FuelModel {
+        downloads: 0,
+        likes: 0,
+        ..model.clone()
+    };
+    without_counters(before) != without_counters(after)
+}
+
+impl FuelClient {
+    /// Empty if there is none or the watchlist file can't be parsed, the methods editing the
+    /// watchlist report the parse error instead
+    pub fn watchlist(&self) -> Watchlist {
+        self.read_sidecar(WATCHLIST_FILE).unwrap_or_default()
+    }
+
+    /// Adds a model to the watchlist, its current state is recorded by the next check
+    pub fn watch(&self, owner: &str, name: &str) -> Result<(), FuelError> {
+        self.update_sidecar(WATCHLIST_FILE, |watchlist: &mut Watchlist| {
+            watchlist
+                .models
+                .entry(watch_key(owner, name))
+                .or_insert_with(|| WatchedModel {
+                    owner: owner.to_owned(),
+                    name: name.to_owned(),
+                    version: None,
+                    model: None,
+                });
+        })
+    }
+
+    /// Returns false if the model wasn't watched
+    pub fn unwatch(&self, owner: &str, name: &str) -> Result<bool, FuelError> {
+        self.update_sidecar(WATCHLIST_FILE, |watchlist: &mut Watchlist| {
+            watchlist.models.remove(&watch_key(owner, name)).is_some()
+        })
+    }
+
+    /// Checks every watched model for new versions and metadata changes with two requests per
+    /// model, without touching the cache. Events are sent to `events` as they are found and
+    /// returned once the check is over. Models checked for the first time only record their
+    /// state. If a request fails the state of the models checked so far is saved and the error
+    /// is returned.
+    pub async fn check_watchlist(
+        &self,
+        events: Option<Sender<WatchEvent>>,
+    ) -> Result<Vec<WatchEvent>, FuelError> {
+        let mut watchlist = self.read_sidecar::<Watchlist>(WATCHLIST_FILE)?;
+        let mut found = Vec::new();
+        let mut result = Ok(());
+        for watched in watchlist.models.values_mut() {
+            let event = match self.check_watched(watched).await {
+                Ok(event) => event,
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            };
+            if let Some(event) = event {
+                if let Some(events) = &events {
+                    events.send(event.clone()).ok();
+                }
+                found.push(event);
+            }
+        }
+        // Models watched or unwatched during the check are left as they are
+        self.update_sidecar(WATCHLIST_FILE, |current: &mut Watchlist| {
+            for (key, watched) in watchlist.models {
+                if let Some(entry) = current.models.get_mut(&key) {
+                    *entry = watched;
+                }
+            }
+        })?;
+        result.map(|_| found)
+    }
+
+    pub fn check_watchlist_blocking(
+        &self,
+        events: Option<Sender<WatchEvent>>,
+    ) -> Result<Vec<WatchEvent>, FuelError> {
+        future::block_on(self.check_watchlist(events))
+    }
+
+    async fn check_watched(
+        &self,
+        watched: &mut WatchedModel,
+    ) -> Result<Option<WatchEvent>, FuelError> {
+        let (owner, name) = (watched.owner.clone(), watched.name.clone());
+        let event = |kind, model: Option<&FuelModel>| WatchEvent {
+            owner: owner.clone(),
+            name: name.clone(),
+            kind,
+            model: model.cloned(),
+        };
+        let model = match self.fetch_model(&owner, &name).await {
+            Ok(model) => model,
+            // Models made private are gone for the credentials of the client
+            Err(FuelError::Http(404) | FuelError::PrivateAsset { .. }) => {
+                let was_listed = watched.model.take().is_some();
+                watched.version = None;
+                return Ok(was_listed.then(|| event(WatchEventKind::Removed, None)));
+            }
+            Err(e) => return Err(e),
+        };
+        let version = self.latest_version(AssetKind::Model, &owner, &name).await?;
+        let kind = match (watched.version, &watched.model) {
+            (Some(previous), _) if previous != version => {
+                Some(WatchEventKind::NewVersion { previous, version })
+            }
+            (_, Some(before)) if metadata_changed(before, &model) => {
+                Some(WatchEventKind::MetadataChanged)
+            }
+            _ => None,
+        };
+        let found = kind.map(|kind| event(kind, Some(&model)));
+        watched.version = Some(version);
+        watched.model = Some(model);
+        Ok(found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TempDir};
+    use std::sync::{Arc, Mutex};
+
+    /// Version and metadata of the model on the fake server, None once removed
+    type Server = Arc<Mutex<Option<(u32, FuelModel)>>>;
+
+    fn kinds(events: &[WatchEvent]) -> Vec<WatchEventKind> {
+        events.iter().map(|event| event.kind.clone()).collect()
+    }
+
+    #[test]
+    fn check_watchlist() {
+        let dir = TempDir::new();
+        let server: Server = Arc::new(Mutex::new(Some((1, testing::model("o", "a", "1")))));
+        let state = server.clone();
+        let client = testing::builder(&dir, move |req| {
+            let Some((version, model)) = state.lock().unwrap().clone() else {
+                return Ok(testing::response(&req.url, 404, ""));
+            };
+            let mut res = testing::response(&req.url, 200, serde_json::to_vec(&model).unwrap());
+            res.headers
+                .insert("X-Ign-Resource-Version", version.to_string());
+            Ok(res)
+        })
+        .build();
+        client.watch("O", "A").unwrap();
+        client.watch("o", "a").unwrap();
+        assert_eq!(client.watchlist().models.len(), 1);
+        // The first check only records the state of the model
+        assert_eq!(client.check_watchlist_blocking(None), Ok(Vec::new()));
+
+        let edit = |f: &dyn Fn(&mut (u32, FuelModel))| f(server.lock().unwrap().as_mut().unwrap());
+        edit(&|(_, model)| model.downloads += 10);
+        assert_eq!(client.check_watchlist_blocking(None), Ok(Vec::new()));
+        edit(&|(_, model)| model.description = "A table".to_owned());
+        let events = client.check_watchlist_blocking(None).unwrap();
+        assert_eq!(kinds(&events), [WatchEventKind::MetadataChanged]);
+        assert_eq!(events[0].model.as_ref().unwrap().description, "A table");
+        edit(&|(version, _)| *version = 2);
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let events = client.check_watchlist_blocking(Some(tx)).unwrap();
+        let new_version = WatchEventKind::NewVersion {
+            previous: 1,
+            version: 2,
+        };
+        assert_eq!(kinds(&events), [new_version]);
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), events);
+
+        *server.lock().unwrap() = None;
+        let events = client.check_watchlist_blocking(None).unwrap();
+        assert_eq!(kinds(&events), [WatchEventKind::Removed]);
+        assert_eq!(client.check_watchlist_blocking(None), Ok(Vec::new()));
+        assert_eq!(client.watchlist().models["o/a"].version, None);
+
+        assert_eq!(client.unwatch("o", "a"), Ok(true));
+        assert_eq!(client.unwatch("o", "a"), Ok(false));
+    }
+}
@@ -0,0 +1,271 @@
+use futures_lite::future;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    asset, uri, AssetKind, DownloadRequest, DownloadResult, DownloadedModel, FuelClient, FuelError,
+    FuelWorld,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadedWorld {
+    pub owner: String,
+    pub name: String,
+    pub version: u32,
+    /// Directory the world was extracted to
+    pub path: PathBuf,
+    /// Top level world file, with the Fuel model URIs it includes replaced by local paths
+    pub sdf_path: PathBuf,
+    /// Fuel models included by the world
+    pub models: Vec<DownloadedModel>,
+    /// Included models that couldn't be downloaded, their `<uri>` is left as is
+    pub failed: Vec<DownloadResult>,
+}
+
+impl FuelClient {
+    /// Crawls the worlds listing and replaces the cached worlds, the worlds cache is stored next
+    /// to the models cache
+    pub async fn update_world_cache(
+        &self,
+        write_to_disk: bool,
+    ) -> Result<Vec<FuelWorld>, FuelError> {
+        let Some(crawl) = self
+            .crawl_listing::<FuelWorld>("worlds", None, None, None)
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+        *self.write_worlds() = Some(crawl.models.clone());
+        if write_to_disk {
+            if let Some(path) = self.worlds_cache_path() {
+                let bytes = self.serialize_cache(&*self.worlds())?;
+                self.write_cache_file(&path, bytes)?;
+            }
+        }
+        Ok(crawl.models)
+    }
+
+    pub fn update_world_cache_blocking(
+        &self,
+        write_to_disk: bool,
+    ) -> Result<Vec<FuelWorld>, FuelError> {
+        future::block_on(self.update_world_cache(write_to_disk))
+    }
+
+    // Filtering functions, same as the model ones, return the cached worlds if `worlds` is None
+    pub fn worlds_by_owner(
+        &self,
+        worlds: Option<&Vec<FuelWorld>>,
+        owner: &str,
+    ) -> Option<Vec<FuelWorld>> {
+        let cached = self.worlds();
+        let worlds = worlds.or(cached.as_ref())?;
+        Some(asset::assets_by_owner(worlds, owner))
+    }
+
+    pub fn worlds_by_private(
+        &self,
+        worlds: Option<&Vec<FuelWorld>>,
+        private: bool,
+    ) -> Option<Vec<FuelWorld>> {
+        let cached = self.worlds();
+        let worlds = worlds.or(cached.as_ref())?;
+        Some(asset::assets_by_private(worlds, private))
+    }
+
+    pub fn worlds_by_tag(
+        &self,
+        worlds: Option<&Vec<FuelWorld>>,
+        tag: &str,
+    ) -> Option<Vec<FuelWorld>> {
+        let cached = self.worlds();
+        let worlds = worlds.or(cached.as_ref())?;
+        Some(asset::assets_by_tag(worlds, tag))
+    }
+
+    /// Cached world named `owner/name`, compared case insensitively
+    pub fn find_world(&self, owner: &str, name: &str) -> Option<FuelWorld> {
+        asset::find_asset(self.worlds().as_ref()?, owner, name).cloned()
+    }
+
+    /// Downloads and extracts the latest version of a world together with all the Fuel models it
+    /// includes, `concurrency` of them at a time. The `<uri>` of included models is rewritten to
+    /// the local model directory, so the returned `sdf_path` can be loaded without any further
+    /// network access. Only the world download itself failing is an error, included models that
+    /// failed are reported in `DownloadedWorld::failed`.
+    pub async fn download_world(
+        &self,
+        owner: &str,
+        name: &str,
+        concurrency: usize,
+    ) -> Result<DownloadedWorld, FuelError> {
+        let DownloadedModel { version, path, .. } = self
+            .download_asset(AssetKind::World, owner, name, None)
+            .await?;
+        let sdf_path = find_world_file(&path, name)?;
+        let io_err = |e: std::io::Error| FuelError::Io(e.to_string());
+        let sdf = fs::read_to_string(&sdf_path).map_err(io_err)?;
+        // The same model can be included several times, only download it once
+        let mut requests = Vec::new();
+        for uri in included_uris(&sdf) {
+            let Some((owner, name, version)) = uri::parse_model_uri(&self.url, &uri) else {
+                continue;
+            };
+            let request = DownloadRequest {
+                version,
+                ..DownloadRequest::new(owner, name)
+            };
+            if !requests.contains(&request) {
+                requests.push(request);
+            }
+        }
+        let mut models = Vec::new();
+        let mut failed = Vec::new();
+        for result in self.download_models(requests, concurrency).await {
+            match result.result {
+                Ok(model) => models.push(model),
+                Err(_) => failed.push(result),
+            }
+        }
+        let resolved = rewrite_uris(&sdf, |uri| {
+            let (owner, name, version) = uri::parse_model_uri(&self.url, uri)?;
+            let model = models.iter().find(|model| {
+                owner.eq_ignore_ascii_case(&model.owner)
+                    && name.eq_ignore_ascii_case(&model.name)
+                    && version.is_none_or(|v| v == model.version)
+            })?;
+            Some(model.path.to_string_lossy().into_owned())
+        });
+        if resolved != sdf {
+            // The file might be hard linked from a content store, don't modify it in place
+            fs::remove_file(&sdf_path).map_err(io_err)?;
+            fs::write(&sdf_path, resolved).map_err(io_err)?;
+        }
+        Ok(DownloadedWorld {
+            owner: owner.to_owned(),
+            name: name.to_owned(),
+            version,
+            path,
+            sdf_path,
+            models,
+            failed,
+        })
+    }
+
+    pub fn download_world_blocking(
+        &self,
+        owner: &str,
+        name: &str,
+        concurrency: usize,
+    ) -> Result<DownloadedWorld, FuelError> {
+        future::block_on(self.download_world(owner, name, concurrency))
+    }
+}
+
+/// Contents of all the `<uri>` elements of an SDF file
+fn included_uris(sdf: &str) -> Vec<String> {
+    sdf.split("<uri>")
+        .skip(1)
+        .filter_map(|rest| Some(rest.split_once("</uri>")?.0.trim().to_owned()))
+        .collect()
+}
+
+/// Replaces the contents of the `<uri>` elements for which `local` returns a path, `local` is
+/// called with the trimmed contents
+fn rewrite_uris(sdf: &str, local: impl Fn(&str) -> Option<String>) -> String {
+    let mut chunks = sdf.split("<uri>");
+    let mut resolved = chunks.next().unwrap_or_default().to_owned();
+    for chunk in chunks {
+        resolved.push_str("<uri>");
+        match chunk.split_once("</uri>") {
+            Some((uri, rest)) => match local(uri.trim()) {
+                Some(path) => {
+                    resolved.push_str(&path);
+                    resolved.push_str("</uri>");
+                    resolved.push_str(rest);
+                }
+                None => resolved.push_str(chunk),
+            },
+            None => resolved.push_str(chunk),
+        }
+    }
+    resolved
+}
+
+/// World archives contain the world file at their root, usually named after the world
+fn find_world_file(dir: &Path, name: &str) -> Result<PathBuf, FuelError> {
+    let candidates = fs::read_dir(dir)
+        .map_err(|e| FuelError::Io(e.to_string()))?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext == "sdf" || ext == "world")
+        })
+        .collect::<Vec<_>>();
+    candidates
+        .iter()
+        .find(|path| {
+            path.file_stem()
+                .is_some_and(|stem| stem.to_string_lossy().eq_ignore_ascii_case(name))
+        })
+        .or(candidates.first())
+        .cloned()
+        .ok_or_else(|| FuelError::Parse(format!("no world file found in {}", dir.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, response, zip_archive, TempDir};
+
+    #[test]
+    fn download_world_with_failed_include() {
+        let dir = TempDir::new();
+        let sdf = "<sdf><include><uri> https://fuel.test/1.0/o/models/Table </uri></include>\
+            <include><uri>https://fuel.test/1.0/o/models/Missing</uri></include></sdf>";
+        let world = zip_archive(&[("w.sdf", sdf)]);
+        let table = zip_archive(&[("model.sdf", "<sdf/>")]);
+        let client = testing::builder(&dir, move |req| {
+            Ok(if req.url.ends_with("/worlds/w/tip/w.zip") {
+                response(&req.url, 200, world.clone())
+            } else if req.url.ends_with("/models/Table/tip/Table.zip") {
+                response(&req.url, 200, table.clone())
+            } else {
+                response(&req.url, 404, "")
+            })
+        })
+        .download_dir(dir.0.join("downloads"))
+        .build();
+        let world = client.download_world_blocking("o", "w", 2).unwrap();
+        assert_eq!(world.models.len(), 1);
+        assert_eq!(world.failed.len(), 1);
+        assert_eq!(world.failed[0].request.name, "Missing");
+        let resolved = fs::read_to_string(&world.sdf_path).unwrap();
+        let table = world.models[0].path.to_string_lossy();
+        assert!(resolved.contains(&format!("<uri>{table}</uri>")));
+        assert!(resolved.contains("<uri>https://fuel.test/1.0/o/models/Missing</uri>"));
+    }
+
+    #[test]
+    fn rewrite_padded_uris() {
+        let sdf =
+            "<include>\n  <uri>\n    https://fuel.test/1.0/o/models/Table\n  </uri>\n</include>\n\
+            <include><uri>model://local</uri></include><uri>unclosed";
+        let rewritten = rewrite_uris(sdf, |uri| {
+            (uri == "https://fuel.test/1.0/o/models/Table").then(|| "/models/table".to_owned())
+        });
+        assert_eq!(
+            rewritten,
+            "<include>\n  <uri>/models/table</uri>\n</include>\n\
+            <include><uri>model://local</uri></include><uri>unclosed"
+        );
+        assert_eq!(
+            included_uris(sdf),
+            ["https://fuel.test/1.0/o/models/Table", "model://local"]
+        );
+    }
+}